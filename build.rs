@@ -0,0 +1,70 @@
+//!
+//! テンプレートが参照する静的ファイルを圧縮し、バイナリに埋め込む
+//! views/pages配下のasset(path="...")で参照されるファイルだけを対象とし、
+//! 起動時には圧縮しない
+//!
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+const PAGES_DIR: &str = "views/pages";
+const STATIC_DIR: &str = "views/static";
+// テンプレートで静的ファイルを参照する関数の呼び出し
+const ASSET_CALL: &str = "asset(path=\"";
+// brotliの圧縮レベル(ビルド時に一度だけ圧縮するため最大とする)
+const BROTLI_QUALITY: u32 = 11;
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", PAGES_DIR);
+    println!("cargo:rerun-if-changed={}", STATIC_DIR);
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR"));
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR"));
+    let mut paths = BTreeSet::new();
+    collect_references(&manifest_dir.join(PAGES_DIR), &mut paths);
+    // (論理パス,非圧縮,gzip,brotli)の配列を生成する
+    let mut table = String::from("&[\n");
+    for (index, path) in paths.iter().enumerate() {
+        let source = manifest_dir.join(STATIC_DIR).join(path);
+        let identity = fs::read(&source)
+            .unwrap_or_else(|error| panic!("テンプレートが参照する静的ファイル{}を読み込めません: {}", source.display(), error));
+        let gzip_file = out_dir.join(format!("asset{}.gz", index));
+        let brotli_file = out_dir.join(format!("asset{}.br", index));
+        fs::write(&gzip_file, gzip(&identity)).expect("gzip圧縮した静的ファイルを出力できません");
+        fs::write(&brotli_file, brotli(&identity)).expect("brotli圧縮した静的ファイルを出力できません");
+        table.push_str(&format!("    ({:?}, include_bytes!({:?}) as &[u8], include_bytes!({:?}) as &[u8], include_bytes!({:?}) as &[u8]),\n",
+            path, source, gzip_file, brotli_file));
+    }
+    table.push(']');
+    fs::write(out_dir.join("assets.rs"), table).expect("静的ファイルの一覧を出力できません");
+}
+
+// テンプレートを再帰的に走査し、asset()で参照される論理パスを収集する
+fn collect_references(dir: &Path, paths: &mut BTreeSet<String>) {
+    for entry in fs::read_dir(dir).unwrap_or_else(|error| panic!("{}: {}", dir.display(), error)) {
+        let path = entry.expect("テンプレートのディレクトリを走査できません").path();
+        if path.is_dir() {
+            collect_references(&path, paths);
+        } else if path.extension().map_or(false, |extension| extension == "html") {
+            let source = fs::read_to_string(&path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+            for (index, _) in source.match_indices(ASSET_CALL) {
+                let rest = &source[index + ASSET_CALL.len()..];
+                if let Some(end) = rest.find('"') {
+                    paths.insert(rest[..end].to_string());
+                }
+            }
+        }
+    }
+}
+fn gzip(content: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(content).expect("gzip圧縮できません");
+    encoder.finish().expect("gzip圧縮できません")
+}
+fn brotli(content: &[u8]) -> Vec<u8> {
+    let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, BROTLI_QUALITY, 22);
+    writer.write_all(content).expect("brotli圧縮できません");
+    writer.into_inner()
+}
\ No newline at end of file
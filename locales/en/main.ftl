@@ -0,0 +1,175 @@
+## Common
+app-name = actix-web Sample ②
+locale-ja = 日本語
+locale-en = English
+common-back = Back
+common-register = Register
+common-update = Update
+common-delete = Delete
+common-search = Search
+common-send = Send
+common-change = Change
+common-set = Set
+common-confirm = Verify
+common-edit = Edit
+common-back-to-login = Back to sign in
+
+## Navigation
+nav-search = Product search
+nav-register = Product registration
+nav-accounts = Accounts
+nav-audit = Audit log
+nav-user = { $name }
+nav-password = Change password
+nav-mfa = Two-factor authentication
+nav-logout = Sign out
+nav-login = Sign in
+
+## Fields
+field-user-name = User name
+field-email = Email address
+field-password = Password
+field-password-confirm = Password (confirm)
+field-current-password = Current password
+field-new-password = New password
+field-new-password-confirm = New password (confirm)
+field-admin = Administrator
+field-enabled = Enabled
+field-must-change-password = Require a password change at next sign-in
+field-mfa-code = Authentication code
+
+## Sign in
+login-title = Sign in
+login-submit = Sign in
+login-forgot = Forgot your password?
+login-failed = The user name or password is incorrect.
+login-password-guide = This is your first sign-in. Please set a new password.
+login-mfa-guide = Enter the 6-digit code shown in your authenticator app, or a recovery code.
+login-mfa-locked = Too many incorrect codes. Please sign in again.
+
+## Menu
+menu-title = actix-web sample program
+
+## Errors
+error-title = System information
+error-stopped = The system is currently unavailable
+too-many-title = Too many requests
+too-many-heading = Too many requests
+too-many-retry = Please try again in { $seconds } seconds.
+
+## Products
+search-title = Product search
+search-keyword = Keyword
+register-title = Product registration
+register-finished = Registration complete
+register-completed = The product has been registered.
+product-id = Product No.
+product-name = Product name
+product-price = Unit price
+product-category = Category
+product-not-found = No products containing { $keyword } were found.
+product-exists = The product { $name } is already registered.
+product-category-unknown = The selected category does not exist.
+
+## Change password
+password-title = Change password
+password-changed = Your password has been changed. Other devices have been signed out.
+password-current-mismatch = The current password is incorrect.
+password-same-as-current = The new password must differ from the current one.
+
+## Password reset
+forgot-guide = Enter the email address registered to your account.
+forgot-sent = If the email address is registered, a password reset link has been sent.
+reset-guide = Please set a new password.
+reset-completed = Your password has been reset. Please sign in with the new password.
+reset-to-login = Go to sign in
+reset-invalid = This link is invalid or has expired.
+reset-retry = Start over
+reset-mail-subject = Password reset instructions
+reset-mail-body = Dear { $name },
+
+    Please reset your password using the link below.
+    { $url }
+
+    The link expires in { $minutes } minutes.
+    If you did not request this, please ignore this email.
+
+## Two-factor authentication
+mfa-title = Two-factor authentication
+mfa-recovery-guide = Store these recovery codes in a safe place. Each code can be used once if your authenticator app is unavailable. They will not be shown again.
+mfa-enabled = Two-factor authentication is enabled. (Unused recovery codes: { $remaining })
+mfa-regenerate = Regenerate recovery codes
+mfa-disable = Disable two-factor authentication
+mfa-scan-guide = Scan the QR code with your authenticator app and enter the 6-digit code it shows.
+mfa-qr-code = QR code
+mfa-secret-guide = If you cannot scan it, enter the following key.
+mfa-enable = Enable
+mfa-code-mismatch = The authentication code is incorrect.
+
+## Account administration
+account-list-title = Accounts
+account-new-title = New account
+account-edit-title = Edit account
+account-new = New account
+account-status = Status
+account-password-required = Password change required
+account-updated-at = Updated at
+account-enabled = Enabled
+account-disabled = Disabled
+account-reset-password = Reset password
+account-exists = The user name { $name } is already registered.
+account-self-disable = You cannot disable your own account or remove your own administrator role.
+account-self-delete = You cannot delete your own account.
+account-created = Account { $name } has been created.
+account-updated = Account { $name } has been updated.
+account-password-reset = The password of account { $name } has been reset. It must be changed at next sign-in.
+account-deleted = Account { $name } has been deleted.
+
+## Audit log
+audit-title = Audit log
+audit-all-actions = All actions
+audit-created-at = Date
+audit-user = User
+audit-action = Action
+audit-target = Target
+audit-before = Before
+audit-after = After
+audit-ip-address = IP address
+audit-request-id = Request ID
+audit-action-login_succeeded = Sign-in succeeded
+audit-action-login_failed = Sign-in failed
+audit-action-product_registered = Product registered
+audit-action-account_created = Account created
+audit-action-account_updated = Account updated
+audit-action-account_deleted = Account deleted
+audit-action-password_reset = Password reset
+audit-action-password_reset_requested = Password reset requested
+audit-action-password_changed = Password changed
+audit-action-mfa_enabled = Two-factor enabled
+audit-action-mfa_disabled = Two-factor disabled
+
+## Validation
+validation-required = This field is required.
+validation-length = Please enter between { $min } and { $max } characters.
+validation-length-min = Please enter at least { $min } characters.
+validation-length-max = Please enter no more than { $max } characters.
+validation-range = Please enter a value between { $min } and { $max }.
+validation-range-min = Please enter a value of { $min } or more.
+validation-range-max = Please enter a value of { $max } or less.
+validation-invalid = Please enter a valid value.
+validation-user-name-length = User name must be between 1 and 20 characters.
+validation-email-required = Email address is required.
+validation-email-format = The email address is not valid.
+validation-password-required = Password is required.
+validation-password-confirm = The passwords do not match.
+validation-current-password-required = Current password is required.
+validation-code-required = Authentication code is required.
+password-policy-length = Password must be between { $min } and { $max } characters.
+password-policy-class = Password must contain { $class ->
+        [upper] an uppercase letter
+        [lower] a lowercase letter
+        [digit] a digit
+       *[symbol] a symbol
+    }.
+password-policy-user-name = Password must not be the same as the user name.
+password-policy-breached = This password has appeared in a data breach and cannot be used.
\ No newline at end of file
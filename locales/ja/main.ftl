@@ -0,0 +1,175 @@
+## 共通
+app-name = actix-web サンプル ②
+locale-ja = 日本語
+locale-en = English
+common-back = 戻る
+common-register = 登録
+common-update = 更新
+common-delete = 削除
+common-search = 検索
+common-send = 送信
+common-change = 変更
+common-set = 設定
+common-confirm = 確認
+common-edit = 編集
+common-back-to-login = ログイン画面へ戻る
+
+## ナビゲーション
+nav-search = 商品検索
+nav-register = 商品登録
+nav-accounts = アカウント管理
+nav-audit = 監査ログ
+nav-user = { $name } さん
+nav-password = パスワード変更
+nav-mfa = 二要素認証
+nav-logout = ログアウト
+nav-login = ログイン
+
+## 入力項目
+field-user-name = ユーザー名
+field-email = メールアドレス
+field-password = パスワード
+field-password-confirm = パスワード(確認)
+field-current-password = 現在のパスワード
+field-new-password = 新しいパスワード
+field-new-password-confirm = 新しいパスワード(確認)
+field-admin = 管理者
+field-enabled = 有効
+field-must-change-password = 次回ログイン時にパスワードを変更させる
+field-mfa-code = 認証コード
+
+## ログイン
+login-title = ログイン
+login-submit = ログイン
+login-forgot = パスワードを忘れた場合
+login-failed = ユーザー名またはパスワードが違います。
+login-password-guide = 初回ログインのため、新しいパスワードを設定してください。
+login-mfa-guide = 認証アプリに表示されている6桁のコード、またはリカバリーコードを入力してください。
+login-mfa-locked = 認証コードの入力に続けて失敗しました。再度ログインしてください。
+
+## メニュー
+menu-title = actix-web サンプルプログラム
+
+## エラー
+error-title = システム情報
+error-stopped = システムは停止中です
+too-many-title = リクエスト制限
+too-many-heading = リクエストが集中しています
+too-many-retry = { $seconds }秒後に再度お試しください。
+
+## 商品
+search-title = 商品検索
+search-keyword = キーワード
+register-title = 商品登録
+register-finished = 登録完了
+register-completed = 商品を登録しました。
+product-id = 商品番号
+product-name = 商品名
+product-price = 単価
+product-category = カテゴリ
+product-not-found = キーワード:{ $keyword }を含む商品は見つかりませんでした。
+product-exists = 商品:{ $name }は既に登録済みです。
+product-category-unknown = 選択されたカテゴリは存在しません。
+
+## パスワード変更
+password-title = パスワード変更
+password-changed = パスワードを変更しました。他の端末ではログアウトされます。
+password-current-mismatch = 現在のパスワードが違います。
+password-same-as-current = 現在と同じパスワードは利用できません。
+
+## パスワード再設定
+forgot-guide = 登録済みのメールアドレスを入力してください。
+forgot-sent = 入力されたメールアドレスが登録されている場合、パスワード再設定用のリンクを送信しました。
+reset-guide = 新しいパスワードを設定してください。
+reset-completed = パスワードを再設定しました。新しいパスワードでログインしてください。
+reset-to-login = ログイン画面へ
+reset-invalid = リンクが無効、または有効期限が切れています。
+reset-retry = 再設定をやり直す
+reset-mail-subject = パスワード再設定のご案内
+reset-mail-body = { $name } 様
+
+    以下のリンクからパスワードを再設定してください。
+    { $url }
+
+    リンクの有効期限は{ $minutes }分です。
+    お心当たりのない場合は、このメールを破棄してください。
+
+## 二要素認証
+mfa-title = 二要素認証
+mfa-recovery-guide = リカバリーコードを安全な場所に保管してください。認証アプリを利用できない場合に、各コードを一度だけ利用できます。このコードは再表示できません。
+mfa-enabled = 二要素認証は有効です。(未使用のリカバリーコード: { $remaining }個)
+mfa-regenerate = リカバリーコードを再生成
+mfa-disable = 二要素認証を無効にする
+mfa-scan-guide = 認証アプリでQRコードを読み取り、表示された6桁のコードを入力してください。
+mfa-qr-code = QRコード
+mfa-secret-guide = 読み取れない場合は次のキーを入力してください。
+mfa-enable = 有効にする
+mfa-code-mismatch = 認証コードが違います。
+
+## アカウント管理
+account-list-title = アカウント管理
+account-new-title = アカウント登録
+account-edit-title = アカウント編集
+account-new = 新規登録
+account-status = 状態
+account-password-required = パスワード変更要求
+account-updated-at = 更新日時
+account-enabled = 有効
+account-disabled = 無効
+account-reset-password = パスワードをリセット
+account-exists = ユーザー名:{ $name }は既に登録済みです。
+account-self-disable = 自身のアカウントを無効化、または管理者から外すことはできません。
+account-self-delete = 自身のアカウントは削除できません。
+account-created = アカウント:{ $name }を登録しました。
+account-updated = アカウント:{ $name }を更新しました。
+account-password-reset = アカウント:{ $name }のパスワードをリセットしました。次回ログイン時に変更が必要です。
+account-deleted = アカウント:{ $name }を削除しました。
+
+## 監査ログ
+audit-title = 監査ログ
+audit-all-actions = すべての操作
+audit-created-at = 日時
+audit-user = ユーザー
+audit-action = 操作
+audit-target = 対象
+audit-before = 変更前
+audit-after = 変更後
+audit-ip-address = IPアドレス
+audit-request-id = リクエストId
+audit-action-login_succeeded = ログイン成功
+audit-action-login_failed = ログイン失敗
+audit-action-product_registered = 商品登録
+audit-action-account_created = アカウント登録
+audit-action-account_updated = アカウント更新
+audit-action-account_deleted = アカウント削除
+audit-action-password_reset = パスワードリセット
+audit-action-password_reset_requested = パスワード再設定要求
+audit-action-password_changed = パスワード変更
+audit-action-mfa_enabled = 二要素認証有効化
+audit-action-mfa_disabled = 二要素認証無効化
+
+## 入力値の検証
+validation-required = 必須項目です。
+validation-length = { $min }文字以上{ $max }文字以内で入力してください。
+validation-length-min = { $min }文字以上で入力してください。
+validation-length-max = { $max }文字以内で入力してください。
+validation-range = { $min }～{ $max }の範囲で入力してください。
+validation-range-min = { $min }以上の値を入力してください。
+validation-range-max = { $max }以下の値を入力してください。
+validation-invalid = 入力値が正しくありません。
+validation-user-name-length = ユーザー名は1文字以上20文字以内で入力してください。
+validation-email-required = メールアドレスは必須です。
+validation-email-format = メールアドレスの形式が正しくありません。
+validation-password-required = パスワードは必須です。
+validation-password-confirm = 確認用パスワードが一致しません。
+validation-current-password-required = 現在のパスワードは必須です。
+validation-code-required = 認証コードは必須です。
+password-policy-length = パスワードは{ $min }文字以上{ $max }文字以内で入力してください。
+password-policy-class = パスワードには{ $class ->
+        [upper] 英大文字
+        [lower] 英小文字
+        [digit] 数字
+       *[symbol] 記号
+    }を含めてください。
+password-policy-user-name = ユーザー名と同じパスワードは利用できません。
+password-policy-breached = このパスワードは漏洩が確認されているため利用できません。
\ No newline at end of file
@@ -0,0 +1,46 @@
+//!
+//! web_sampleのデータベースマイグレーション
+//! app_commonsのエンティティが参照するテーブルと、アカウント、監査ログなどアプリケーションが管理するテーブルを定義する
+//! テーブルを追加する場合はファイルを追加し、Migrator::migrations()に登録する
+//!
+pub use sea_orm_migration::prelude::*;
+
+mod m20221001_000001_create_product_category;
+mod m20221001_000002_create_product;
+mod m20221001_000003_create_user;
+mod m20221001_000004_create_accounts;
+mod m20221001_000005_create_account_mfa_settings;
+mod m20221001_000006_create_password_reset_tokens;
+mod m20221001_000007_create_audit_logs;
+mod m20221001_000008_add_accounts_email;
+mod m20221001_000009_copy_users_to_accounts;
+
+pub use m20221001_000001_create_product_category::ProductCategory;
+pub use m20221001_000002_create_product::Product;
+pub use m20221001_000003_create_user::User;
+pub use m20221001_000004_create_accounts::Accounts;
+pub use m20221001_000005_create_account_mfa_settings::AccountMfaSettings;
+pub use m20221001_000006_create_password_reset_tokens::PasswordResetTokens;
+pub use m20221001_000007_create_audit_logs::AuditLogs;
+pub use m20221001_000008_add_accounts_email::AccountsEmail;
+
+///
+/// マイグレーションの一覧(適用順)
+///
+pub struct Migrator;
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20221001_000001_create_product_category::Migration) ,
+            Box::new(m20221001_000002_create_product::Migration) ,
+            Box::new(m20221001_000003_create_user::Migration) ,
+            Box::new(m20221001_000004_create_accounts::Migration) ,
+            Box::new(m20221001_000005_create_account_mfa_settings::Migration) ,
+            Box::new(m20221001_000006_create_password_reset_tokens::Migration) ,
+            Box::new(m20221001_000007_create_audit_logs::Migration) ,
+            Box::new(m20221001_000008_add_accounts_email::Migration) ,
+            Box::new(m20221001_000009_copy_users_to_accounts::Migration)
+        ]
+    }
+}
\ No newline at end of file
@@ -0,0 +1,28 @@
+use sea_orm_migration::prelude::*;
+
+///
+/// 商品カテゴリテーブル
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(Table::create()
+            .table(ProductCategory::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(ProductCategory::Id).integer().not_null().auto_increment().primary_key())
+            .col(ColumnDef::new(ProductCategory::Name).string_len(20).not_null().unique_key())
+            .to_owned()).await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ProductCategory::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+pub enum ProductCategory {
+    Table ,
+    Id ,    // カテゴリ番号
+    Name    // カテゴリ名
+}
\ No newline at end of file
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+use crate::ProductCategory;
+
+///
+/// 商品テーブル
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(Table::create()
+            .table(Product::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(Product::Id).integer().not_null().auto_increment().primary_key())
+            .col(ColumnDef::new(Product::Name).string_len(30).not_null().unique_key())
+            .col(ColumnDef::new(Product::Price).integer().not_null())
+            .col(ColumnDef::new(Product::CategoryId).integer().not_null())
+            .foreign_key(ForeignKey::create()
+                .name("fk_product_category_id")
+                .from(Product::Table, Product::CategoryId)
+                .to(ProductCategory::Table, ProductCategory::Id)
+                .on_delete(ForeignKeyAction::Restrict))
+            .to_owned()).await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Product::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+pub enum Product {
+    Table ,
+    Id ,        // 商品番号
+    Name ,      // 商品名
+    Price ,     // 単価
+    CategoryId  // カテゴリ番号
+}
\ No newline at end of file
@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+///
+/// ユーザーテーブル(app_commonsの認証サービスが参照する)
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(Table::create()
+            .table(User::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(User::Id).integer().not_null().auto_increment().primary_key())
+            .col(ColumnDef::new(User::UserId).string_len(36).not_null().unique_key())
+            .col(ColumnDef::new(User::UserName).string_len(20).not_null().unique_key())
+            .col(ColumnDef::new(User::Password).string().not_null())
+            .col(ColumnDef::new(User::Mail).string())
+            .to_owned()).await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(User::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+pub enum User {
+    Table ,
+    Id ,
+    UserId ,    // ユーザーId(Uuid)
+    UserName ,  // ユーザー名
+    Password ,  // パスワード
+    Mail        // メールアドレス
+}
\ No newline at end of file
@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+///
+/// アカウントテーブル
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(Table::create()
+            .table(Accounts::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(Accounts::Id).string().not_null().primary_key())
+            .col(ColumnDef::new(Accounts::UserName).string().not_null().unique_key())
+            .col(ColumnDef::new(Accounts::PasswordHash).string().not_null())
+            .col(ColumnDef::new(Accounts::Roles).string().not_null())
+            .col(ColumnDef::new(Accounts::Enabled).boolean().not_null())
+            .col(ColumnDef::new(Accounts::MustChangePassword).boolean().not_null())
+            .col(ColumnDef::new(Accounts::CreatedAt).timestamp_with_time_zone().not_null())
+            .col(ColumnDef::new(Accounts::UpdatedAt).timestamp_with_time_zone().not_null())
+            .to_owned()).await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Accounts::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+pub enum Accounts {
+    Table ,
+    Id ,                    // ユーザーId(Uuid)
+    UserName ,              // ユーザー名
+    PasswordHash ,          // パスワード(Argon2のPHC文字列)
+    Roles ,                 // ロール(カンマ区切り)
+    Enabled ,               // 有効/無効
+    MustChangePassword ,    // 次回ログイン時にパスワード変更を要求する
+    CreatedAt ,             // 登録日時
+    UpdatedAt               // 更新日時
+}
\ No newline at end of file
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+///
+/// 二要素認証設定テーブル
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(Table::create()
+            .table(AccountMfaSettings::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(AccountMfaSettings::AccountId).string().not_null().primary_key())
+            .col(ColumnDef::new(AccountMfaSettings::Secret).string().not_null())
+            .col(ColumnDef::new(AccountMfaSettings::RecoveryCodes).json().not_null())
+            .col(ColumnDef::new(AccountMfaSettings::LastStep).big_integer())
+            .col(ColumnDef::new(AccountMfaSettings::CreatedAt).timestamp_with_time_zone().not_null())
+            .col(ColumnDef::new(AccountMfaSettings::UpdatedAt).timestamp_with_time_zone().not_null())
+            .to_owned()).await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(AccountMfaSettings::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+pub enum AccountMfaSettings {
+    Table ,
+    AccountId ,     // ユーザーId
+    Secret ,        // TOTPの共有鍵(Base32)
+    RecoveryCodes , // 未使用のリカバリーコードのSHA-256ハッシュ
+    LastStep ,      // 最後に使用したTOTPのステップ
+    CreatedAt ,     // 登録日時
+    UpdatedAt       // 更新日時
+}
\ No newline at end of file
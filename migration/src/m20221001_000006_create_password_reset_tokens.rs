@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+///
+/// パスワード再設定トークンテーブル
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(Table::create()
+            .table(PasswordResetTokens::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(PasswordResetTokens::Id).big_integer().not_null().auto_increment().primary_key())
+            .col(ColumnDef::new(PasswordResetTokens::AccountId).string().not_null())
+            .col(ColumnDef::new(PasswordResetTokens::TokenHash).string().not_null().unique_key())
+            .col(ColumnDef::new(PasswordResetTokens::ExpiresAt).timestamp_with_time_zone().not_null())
+            .col(ColumnDef::new(PasswordResetTokens::UsedAt).timestamp_with_time_zone())
+            .col(ColumnDef::new(PasswordResetTokens::CreatedAt).timestamp_with_time_zone().not_null())
+            .to_owned()).await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(PasswordResetTokens::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+pub enum PasswordResetTokens {
+    Table ,
+    Id ,
+    AccountId , // ユーザーId
+    TokenHash , // トークンのSHA-256ハッシュ
+    ExpiresAt , // 有効期限
+    UsedAt ,    // 使用日時
+    CreatedAt   // 発行日時
+}
\ No newline at end of file
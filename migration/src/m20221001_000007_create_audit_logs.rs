@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+///
+/// 監査ログテーブル
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(Table::create()
+            .table(AuditLogs::Table)
+            .if_not_exists()
+            .col(ColumnDef::new(AuditLogs::Id).big_integer().not_null().auto_increment().primary_key())
+            .col(ColumnDef::new(AuditLogs::UserId).string())
+            .col(ColumnDef::new(AuditLogs::UserName).string())
+            .col(ColumnDef::new(AuditLogs::Action).string().not_null())
+            .col(ColumnDef::new(AuditLogs::Target).string())
+            .col(ColumnDef::new(AuditLogs::BeforeValue).json())
+            .col(ColumnDef::new(AuditLogs::AfterValue).json())
+            .col(ColumnDef::new(AuditLogs::IpAddress).string())
+            .col(ColumnDef::new(AuditLogs::RequestId).string())
+            .col(ColumnDef::new(AuditLogs::CreatedAt).timestamp_with_time_zone().not_null())
+            .to_owned()).await
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(AuditLogs::Table).to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+pub enum AuditLogs {
+    Table ,
+    Id ,
+    UserId ,        // 操作したユーザーId
+    UserName ,      // 操作したユーザー名
+    Action ,        // 操作
+    Target ,        // 対象(商品番号など)
+    BeforeValue ,   // 変更前の値
+    AfterValue ,    // 変更後の値
+    IpAddress ,     // 接続元IPアドレス
+    RequestId ,     // リクエストId
+    CreatedAt       // 記録日時
+}
\ No newline at end of file
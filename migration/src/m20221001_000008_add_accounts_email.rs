@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{DbBackend, Statement};
+use crate::Accounts;
+
+///
+/// アカウントテーブルにメールアドレス(パスワード再設定の送信先)を追加する
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            // マイグレーション導入前に起動時に作成されたテーブルには、既に列が存在する場合がある
+            DbBackend::Postgres => manager.get_connection()
+                .execute(Statement::from_string(DbBackend::Postgres ,
+                    String::from(r#"ALTER TABLE "accounts" ADD COLUMN IF NOT EXISTS "email" varchar"#))).await
+                .map(|_| ()) ,
+            _ => manager.alter_table(Table::alter()
+                .table(Accounts::Table)
+                .add_column(ColumnDef::new(AccountsEmail::Email).string())
+                .to_owned()).await
+        }
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(Table::alter()
+            .table(Accounts::Table)
+            .drop_column(AccountsEmail::Email)
+            .to_owned()).await
+    }
+}
+
+#[derive(Iden)]
+pub enum AccountsEmail {
+    Email   // メールアドレス
+}
\ No newline at end of file
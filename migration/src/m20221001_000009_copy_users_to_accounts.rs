@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::Statement;
+
+///
+/// app_commonsのユーザーテーブルのユーザーをアカウントテーブルに移行する
+/// ユーザーIdをアカウントIdとし、パスワードは既存のハッシュのまま移行する
+/// (初回ログイン時に既存の認証サービスで照合し、Argon2のハッシュに置き換える)
+///
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+impl Migration {
+    // 同じIdまたはユーザー名のアカウントが登録済みのユーザーは移行しない
+    const COPY_USERS: &'static str = r#"INSERT INTO "accounts"
+        ("id", "user_name", "email", "password_hash", "roles", "enabled", "must_change_password", "created_at", "updated_at")
+        SELECT "u"."user_id", "u"."user_name", "u"."mail", "u"."password", '', TRUE, FALSE, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+        FROM "user" "u"
+        WHERE NOT EXISTS (SELECT 1 FROM "accounts" "a" WHERE "a"."id" = "u"."user_id" OR "a"."user_name" = "u"."user_name")"#;
+    // 移行したまま、パスワードが置き換えられていないアカウントを削除する
+    const DELETE_COPIED: &'static str = r#"DELETE FROM "accounts"
+        WHERE "password_hash" IN (SELECT "u"."password" FROM "user" "u" WHERE "u"."user_id" = "accounts"."id")"#;
+}
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection()
+            .execute(Statement::from_string(manager.get_database_backend() , String::from(Self::COPY_USERS))).await
+            .map(|_| ())
+    }
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection()
+            .execute(Statement::from_string(manager.get_database_backend() , String::from(Self::DELETE_COPIED))).await
+            .map(|_| ())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+
+///
+/// アカウント エンティティ
+///
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "accounts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id:                   String ,               // ユーザーId(Uuid)
+    #[sea_orm(unique)]
+    pub user_name:            String ,               // ユーザー名
+    pub email:                Option<String> ,       // メールアドレス(パスワード再設定の送信先)
+    pub password_hash:        String ,               // パスワード(Argon2のPHC文字列)
+    pub roles:                String ,               // ロール(カンマ区切り)
+    pub enabled:              bool ,                 // 有効/無効
+    pub must_change_password: bool ,                 // 次回ログイン時にパスワード変更を要求する
+    pub created_at:           DateTimeWithTimeZone , // 登録日時
+    pub updated_at:           DateTimeWithTimeZone   // 更新日時
+}
+impl Model {
+    // ロールの一覧を取得する
+    pub fn role_list(&self) -> Vec<String> {
+        self.roles.split(',').map(str::trim).filter(|role| !role.is_empty()).map(String::from).collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
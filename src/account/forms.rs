@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use validator::{Validate, ValidationError, ValidationErrors};
+use app_commons::presentation::forms::{LoginForm, ProductRegisterForm, ProductSearchForm};
+use crate::i18n::{LocalizedMessage, MessageArg};
+
+///
+/// 入力値の検証エラー
+/// AppValidatorと同様に項目名とメッセージの組で保持する
+/// メッセージはメッセージカタログのIdと引数とし、画面生成時に翻訳する
+///
+#[derive(Debug, Default)]
+pub struct FormErrors {
+    pub errors: HashMap<String, LocalizedMessage>
+}
+impl From<ValidationErrors> for FormErrors {
+    fn from(errors: ValidationErrors) -> Self {
+        let errors = errors.field_errors().into_iter()
+            .filter_map(|(field, errors)| errors.first()
+                .and_then(|error| error.message.as_ref())
+                .map(|message| (field.to_string(), LocalizedMessage::new(message.to_string()))))
+            .collect();
+        Self { errors }
+    }
+}
+impl FormErrors {
+    ///
+    /// 失敗した検証規則と引数からメッセージIdを生成する
+    /// メッセージを指定していない、あるいはメッセージIdではない検証エラーに利用する
+    ///
+    pub fn from_rules(errors: ValidationErrors) -> Self {
+        let errors = errors.field_errors().into_iter()
+            .filter_map(|(field, errors)| errors.first()
+                .map(|error| (field.to_string(), Self::rule_message(error))))
+            .collect();
+        Self { errors }
+    }
+    // 検証規則に対応するメッセージ
+    fn rule_message(error: &ValidationError) -> LocalizedMessage {
+        let min = Self::param(error, "min");
+        let max = Self::param(error, "max");
+        match (error.code.as_ref(), min, max) {
+            ("length", Some(MessageArg::Number(1)), None) | ("required", _, _) => LocalizedMessage::new("validation-required") ,
+            ("length", Some(min), Some(max)) => LocalizedMessage::new("validation-length").arg("min", min).arg("max", max) ,
+            ("length", Some(min), None) => LocalizedMessage::new("validation-length-min").arg("min", min) ,
+            ("length", None, Some(max)) => LocalizedMessage::new("validation-length-max").arg("max", max) ,
+            ("range", Some(min), Some(max)) => LocalizedMessage::new("validation-range").arg("min", min).arg("max", max) ,
+            ("range", Some(min), None) => LocalizedMessage::new("validation-range-min").arg("min", min) ,
+            ("range", None, Some(max)) => LocalizedMessage::new("validation-range-max").arg("max", max) ,
+            ("email", _, _) => LocalizedMessage::new("validation-email-format") ,
+            _ => LocalizedMessage::new("validation-invalid")
+        }
+    }
+    // 検証規則の数値の引数 (範囲の上限、下限は小数で格納されるため、整数は整数として渡す)
+    fn param(error: &ValidationError, name: &str) -> Option<MessageArg> {
+        error.params.get(name).and_then(|value| value.as_f64()).map(|value| match value.fract() == 0.0 {
+            true => MessageArg::Number(value as i64) ,
+            false => MessageArg::Text(value.to_string())
+        })
+    }
+}
+
+///
+/// 入力値の検証
+/// app_commonsのフォームと同じ形式で検証エラーを返す
+///
+pub trait FormValidator: Validate {
+    fn validate_form(&self) -> Result<(), FormErrors> {
+        self.validate().map_err(FormErrors::from)
+    }
+}
+
+///
+/// app_commonsのフォームの検証
+/// app_commonsの検証メッセージは日本語の文字列のため利用せず、失敗した規則からメッセージIdを生成する
+///
+pub trait RuleValidator: Validate {
+    fn validate_form(&self) -> Result<(), FormErrors> {
+        self.validate().map_err(FormErrors::from_rules)
+    }
+}
+impl RuleValidator for LoginForm {}
+impl RuleValidator for ProductSearchForm {}
+impl RuleValidator for ProductRegisterForm {}
+
+// 未入力の項目をNoneとする
+fn empty_as_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty()))
+}
+
+///
+/// アカウント登録 入力値
+///
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
+pub struct AccountCreateForm {
+    #[validate(length(min = 1, max = 20, message = "validation-user-name-length"))]
+    pub user_name:        String ,
+    #[validate(email(message = "validation-email-format"))]
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub email:            Option<String> ,
+    #[validate(length(min = 1, message = "validation-password-required"))]
+    #[serde(skip_serializing)]
+    pub password:         String ,
+    #[validate(must_match(other = "password", message = "validation-password-confirm"))]
+    #[serde(skip_serializing)]
+    pub password_confirm: String ,
+    pub admin:            Option<String> ,   // 管理者(チェックボックス)
+    pub must_change_password: Option<String> // 次回ログイン時にパスワードを変更させる(チェックボックス)
+}
+impl FormValidator for AccountCreateForm {}
+
+///
+/// アカウント更新 入力値
+///
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
+pub struct AccountUpdateForm {
+    #[validate(email(message = "validation-email-format"))]
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub email:                Option<String> ,   // メールアドレス
+    pub admin:                Option<String> ,   // 管理者(チェックボックス)
+    pub enabled:              Option<String> ,   // 有効(チェックボックス)
+    pub must_change_password: Option<String>     // 次回ログイン時にパスワードを変更させる(チェックボックス)
+}
+impl FormValidator for AccountUpdateForm {}
+
+///
+/// パスワード設定 入力値
+/// 管理者によるリセット、初回ログイン時の変更で利用する
+/// 長さ、文字種はPasswordPolicyで検証する
+///
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct PasswordForm {
+    #[validate(length(min = 1, message = "validation-password-required"))]
+    pub password:         String ,
+    #[validate(must_match(other = "password", message = "validation-password-confirm"))]
+    pub password_confirm: String
+}
+impl FormValidator for PasswordForm {}
+
+///
+/// パスワード変更 入力値
+/// ログイン中のユーザー自身が変更する
+///
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct PasswordChangeForm {
+    #[validate(length(min = 1, message = "validation-current-password-required"))]
+    pub current_password: String ,
+    #[validate(length(min = 1, message = "validation-password-required"))]
+    pub password:         String ,
+    #[validate(must_match(other = "password", message = "validation-password-confirm"))]
+    pub password_confirm: String
+}
+impl FormValidator for PasswordChangeForm {}
+
+///
+/// パスワード再設定要求 入力値
+///
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
+pub struct PasswordResetRequestForm {
+    #[validate(length(min = 1, message = "validation-email-required") , email(message = "validation-email-format"))]
+    pub email: String
+}
+impl FormValidator for PasswordResetRequestForm {}
+
+///
+/// 二要素認証コード 入力値
+/// TOTPのコード、またはリカバリーコード
+///
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct MfaCodeForm {
+    #[validate(length(min = 1, message = "validation-code-required"))]
+    pub code: String
+}
+impl FormValidator for MfaCodeForm {}
\ No newline at end of file
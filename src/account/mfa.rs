@@ -0,0 +1,133 @@
+use chrono::Utc;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::{Result, WebAppError};
+use crate::account::mfa_setting;
+use crate::account::store::AccountStore;
+use crate::account::totp::Totp;
+use crate::handler::view_helper::SessionKey;
+
+///
+/// 二要素認証待ちの状態
+/// パスワード認証後、コードを検証するまでSessionに保持する
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaPending {
+    pub account_id: String ,
+    pub expires_at: i64 ,     // 有効期限(UNIXタイムスタンプ)
+    pub failures:   u32       // コードの検証失敗回数
+}
+impl MfaPending {
+    pub const SESSION_KEY: SessionKey<MfaPending> = SessionKey::new("login" , "mfa_pending");
+    // 有効期間(秒)
+    const LIFETIME_SECONDS: i64 = 300;
+    // 許容する検証失敗回数
+    pub const MAX_FAILURES: u32 = 5;
+    pub fn new(account_id: &str) -> Self {
+        Self { account_id: account_id.to_string(), expires_at: Utc::now().timestamp() + Self::LIFETIME_SECONDS, failures: 0 }
+    }
+    pub fn is_expired(&self) -> bool {
+        Utc::now().timestamp() > self.expires_at
+    }
+}
+
+///
+/// 二要素認証 サービス
+///
+pub struct MfaService;
+impl MfaService {
+    pub const ISSUER: &'static str = "web_sample";
+    // 登録中の共有鍵を格納するSessionのキー
+    pub const ENROLMENT_KEY: SessionKey<String> = SessionKey::new("mfa" , "enrolment_secret").ttl(600);
+    // リカバリーコードの個数と文字数
+    const RECOVERY_CODE_COUNT: usize = 10;
+    const RECOVERY_CODE_LENGTH: usize = 10;
+    const RECOVERY_CODE_CHARS: &'static [u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    ///
+    /// アカウントの二要素認証設定を取得する 未登録の場合はNone
+    ///
+    pub async fn find(store: &dyn AccountStore, account_id: &str) -> Result<Option<mfa_setting::Model>> {
+        store.find_mfa(account_id).await
+    }
+    ///
+    /// 二要素認証を有効にする
+    /// 生成したリカバリーコードを返す(表示はこの一度のみ)
+    ///
+    pub async fn enable(store: &dyn AccountStore, account_id: &str, secret: &str, step: i64) -> Result<Vec<String>> {
+        let (codes, hashes) = Self::generate_recovery_codes();
+        let now = Utc::now();
+        store.save_mfa(mfa_setting::Model {
+            account_id: account_id.to_string() ,
+            secret: secret.to_string() ,
+            recovery_codes: serde_json::json!(hashes) ,
+            last_step: Some(step) ,
+            created_at: now.into() ,
+            updated_at: now.into()
+        }).await?;
+        Ok(codes)
+    }
+    ///
+    /// 二要素認証を無効にする
+    ///
+    pub async fn disable(store: &dyn AccountStore, account_id: &str) -> Result<()> {
+        store.delete_mfa(account_id).await
+    }
+    ///
+    /// リカバリーコードを再生成する
+    ///
+    pub async fn regenerate_recovery_codes(store: &dyn AccountStore, model: mfa_setting::Model) -> Result<Vec<String>> {
+        let (codes, hashes) = Self::generate_recovery_codes();
+        store.update_mfa(mfa_setting::Model {
+            recovery_codes: serde_json::json!(hashes) ,
+            updated_at: Utc::now().into() ,
+            ..model
+        }).await?;
+        Ok(codes)
+    }
+    ///
+    /// TOTPのコード、またはリカバリーコードを検証する
+    /// 使用したステップ、リカバリーコードは再利用できないよう記録する
+    /// 並行したリクエストでは、条件付きの更新に成功した一方のみ受け付ける
+    ///
+    pub async fn verify(store: &dyn AccountStore, model: mfa_setting::Model, code: &str) -> Result<bool> {
+        let totp = Totp::from_secret(&model.secret)
+            .ok_or_else(|| WebAppError::InternalError(format!("invalid TOTP secret for {}", model.account_id)))?;
+        if let Some(step) = totp.verify(code, Utc::now().timestamp(), model.last_step) {
+            return store.advance_mfa_step(&model.account_id, step).await;
+        }
+        let hash = Self::hash(code);
+        let mut hashes: Vec<String> = serde_json::from_value(model.recovery_codes.clone()).unwrap_or_default();
+        match hashes.iter().position(|value| *value == hash) {
+            Some(index) => {
+                hashes.remove(index);
+                store.replace_recovery_codes(&model.account_id, &model.recovery_codes, serde_json::json!(hashes), Utc::now()).await
+            },
+            None => Ok(false)
+        }
+    }
+    ///
+    /// 未使用のリカバリーコードの個数
+    ///
+    pub fn remaining_recovery_codes(model: &mfa_setting::Model) -> usize {
+        model.recovery_codes.as_array().map_or(0, Vec::len)
+    }
+
+    // リカバリーコードとそのハッシュを生成する
+    fn generate_recovery_codes() -> (Vec<String>, Vec<String>) {
+        let mut rng = rand::thread_rng();
+        let codes = (0..Self::RECOVERY_CODE_COUNT).map(|_| {
+            let code = (0..Self::RECOVERY_CODE_LENGTH)
+                .map(|_| Self::RECOVERY_CODE_CHARS[rng.gen_range(0..Self::RECOVERY_CODE_CHARS.len())] as char)
+                .collect::<String>();
+            format!("{}-{}", &code[..Self::RECOVERY_CODE_LENGTH / 2], &code[Self::RECOVERY_CODE_LENGTH / 2..])
+        }).collect::<Vec<_>>();
+        let hashes = codes.iter().map(|code| Self::hash(code)).collect();
+        (codes, hashes)
+    }
+    // 区切り文字と大文字小文字を無視したSHA-256ハッシュ
+    fn hash(code: &str) -> String {
+        let normalized = code.trim().to_ascii_lowercase().replace('-', "");
+        Sha256::digest(normalized.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+
+///
+/// 二要素認証の設定 エンティティ
+/// 登録済みのアカウントのみ行が存在する
+///
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "account_mfa_settings")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub account_id:     String ,                // ユーザーId
+    pub secret:         String ,                // TOTPの共有鍵(Base32)
+    pub recovery_codes: Json ,                  // 未使用のリカバリーコードのSHA-256ハッシュ
+    pub last_step:      Option<i64> ,           // 最後に使用したTOTPのステップ(再利用防止)
+    pub created_at:     DateTimeWithTimeZone ,  // 登録日時
+    pub updated_at:     DateTimeWithTimeZone    // 更新日時
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -0,0 +1,238 @@
+pub mod entity;
+pub mod forms;
+pub mod policy;
+pub mod revocation;
+pub mod reset_token;
+pub mod reset;
+pub mod totp;
+pub mod mfa_setting;
+pub mod mfa;
+pub mod store;
+
+use actix_web::web;
+use app_commons::presentation::forms::LoginForm;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::password_hash::SaltString;
+use chrono::Utc;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use serde::Serialize;
+use crate::{Result, WebAppError};
+use self::entity as account;
+use self::forms::{AccountCreateForm, AccountUpdateForm};
+use self::store::AccountStore;
+use crate::provider::ServiceProvider;
+
+///
+/// ロール
+///
+pub struct Role;
+impl Role {
+    pub const ADMIN: &'static str = "admin";   // 管理者
+}
+
+///
+/// パスワードのハッシュ化と照合(Argon2id)
+/// Argon2の計算はワーカースレッドを塞がないようにweb::blockで実行する
+///
+pub struct Password;
+impl Password {
+    // Argon2のPHC文字列の接頭辞
+    const ARGON2_PREFIX: &'static str = "$argon2";
+    // パスワードをハッシュ化してPHC文字列を返す
+    pub async fn hash(password: &str) -> Result<String> {
+        let password = password.to_string();
+        web::block(move || {
+            let salt = SaltString::generate(&mut rand::rngs::OsRng);
+            Argon2::default().hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|error| WebAppError::InternalError(error.to_string()))
+        }).await.map_err(|error| WebAppError::InternalError(error.to_string()))?
+    }
+    // パスワードとハッシュを照合する
+    pub async fn verify(password: &str, hash: &str) -> Result<bool> {
+        let (password, hash) = (password.to_string(), hash.to_string());
+        web::block(move || match PasswordHash::new(&hash) {
+            Ok(hash) => Argon2::default().verify_password(password.as_bytes(), &hash).is_ok() ,
+            Err(_) => false
+        }).await.map_err(|error| WebAppError::InternalError(error.to_string()))
+    }
+    // 既存のユーザーテーブルから移行した、Argon2以外のハッシュか判定する
+    pub fn is_legacy(hash: &str) -> bool {
+        !hash.starts_with(Self::ARGON2_PREFIX)
+    }
+    // 指定した長さの英数字のパスワードを生成する
+    pub fn generate(length: usize) -> String {
+        rand::thread_rng().sample_iter(&Alphanumeric).take(length).map(char::from).collect()
+    }
+}
+
+///
+/// アカウントの認証結果
+///
+#[derive(Debug)]
+pub enum Authentication {
+    Succeeded(account::Model) ,              // 認証成功
+    PasswordChangeRequired(account::Model) , // 認証成功(パスワード変更が必要)
+    Disabled ,                               // 無効なアカウント
+    Failed                                   // 未登録、パスワード不一致
+}
+
+///
+/// アカウント(画面表示、監査ログ用)
+/// パスワードのハッシュは含めない
+///
+#[derive(Debug, Serialize)]
+pub struct AccountView {
+    id:                   String ,
+    user_name:            String ,
+    email:                Option<String> ,
+    admin:                bool ,
+    enabled:              bool ,
+    must_change_password: bool ,
+    created_at:           String ,
+    updated_at:           String
+}
+impl From<&account::Model> for AccountView {
+    fn from(model: &account::Model) -> Self {
+        Self {
+            id: model.id.clone() ,
+            user_name: model.user_name.clone() ,
+            email: model.email.clone() ,
+            admin: model.role_list().iter().any(|role| role == Role::ADMIN) ,
+            enabled: model.enabled ,
+            must_change_password: model.must_change_password ,
+            created_at: model.created_at.format("%Y-%m-%d %H:%M:%S").to_string() ,
+            updated_at: model.updated_at.format("%Y-%m-%d %H:%M:%S").to_string()
+        }
+    }
+}
+
+///
+/// アカウント サービス
+///
+pub struct AccountService;
+impl AccountService {
+    ///
+    /// ユーザー名とパスワードで認証する
+    /// 既存のユーザーテーブルから移行したアカウントは既存の認証サービスで照合し、
+    /// 成功した場合はパスワードをArgon2のハッシュに置き換える
+    ///
+    pub async fn authenticate(store: &dyn AccountStore, provider: &dyn ServiceProvider, form: &LoginForm)
+        -> Result<Authentication> {
+        let mut model = match store.find_by_name(&form.name).await? {
+            Some(model) => model ,
+            None => return Ok(Authentication::Failed)
+        };
+        if Password::is_legacy(&model.password_hash) {
+            if !Self::verify_legacy(provider, form, &model).await? {
+                return Ok(Authentication::Failed);
+            }
+            model = store.update(account::Model {
+                password_hash: Password::hash(&form.password).await? ,
+                updated_at: Utc::now().into() ,
+                ..model
+            }).await?;
+        } else if !Password::verify(&form.password, &model.password_hash).await? {
+            return Ok(Authentication::Failed);
+        }
+        Ok(if !model.enabled {
+            Authentication::Disabled
+        } else if model.must_change_password {
+            Authentication::PasswordChangeRequired(model)
+        } else {
+            Authentication::Succeeded(model)
+        })
+    }
+    ///
+    /// 全アカウントをユーザー名順に取得する
+    ///
+    pub async fn list(store: &dyn AccountStore) -> Result<Vec<AccountView>> {
+        store.list().await.map(|models| models.iter().map(AccountView::from).collect())
+    }
+    ///
+    /// ユーザーIdでアカウントを取得する
+    ///
+    pub async fn find(store: &dyn AccountStore, id: &str) -> Result<Option<account::Model>> {
+        store.find(id).await
+    }
+    ///
+    /// ユーザー名でアカウントを取得する
+    ///
+    pub async fn find_by_name(store: &dyn AccountStore, user_name: &str) -> Result<Option<account::Model>> {
+        store.find_by_name(user_name).await
+    }
+    ///
+    /// メールアドレスでアカウントを取得する
+    ///
+    pub async fn find_by_email(store: &dyn AccountStore, email: &str) -> Result<Option<account::Model>> {
+        store.find_by_email(email).await
+    }
+    ///
+    /// ユーザー名が登録済みか検証する
+    ///
+    pub async fn exists(store: &dyn AccountStore, user_name: &str) -> Result<bool> {
+        store.find_by_name(user_name).await.map(|model| model.is_some())
+    }
+    ///
+    /// アカウントを登録する
+    ///
+    pub async fn create(store: &dyn AccountStore, form: &AccountCreateForm) -> Result<account::Model> {
+        let now = Utc::now();
+        let model = account::Model {
+            id: uuid::Uuid::new_v4().to_string() ,
+            user_name: form.user_name.trim().to_string() ,
+            email: form.email.clone() ,
+            password_hash: Password::hash(&form.password).await? ,
+            roles: Self::roles(form.admin.is_some()) ,
+            enabled: true ,
+            must_change_password: form.must_change_password.is_some() ,
+            created_at: now.into() ,
+            updated_at: now.into()
+        };
+        store.insert(model).await
+    }
+    ///
+    /// メールアドレス、ロール、有効/無効、パスワード変更要求を更新する
+    ///
+    pub async fn update(store: &dyn AccountStore, model: account::Model, form: &AccountUpdateForm) -> Result<account::Model> {
+        store.update(account::Model {
+            email: form.email.clone() ,
+            roles: Self::roles(form.admin.is_some()) ,
+            enabled: form.enabled.is_some() ,
+            must_change_password: form.must_change_password.is_some() ,
+            updated_at: Utc::now().into() ,
+            ..model
+        }).await
+    }
+    ///
+    /// パスワードを設定する
+    /// 管理者によるリセットの場合は次回ログイン時の変更を要求する
+    ///
+    pub async fn set_password(store: &dyn AccountStore, model: account::Model, password: &str,
+                              must_change_password: bool) -> Result<account::Model> {
+        store.update(account::Model {
+            password_hash: Password::hash(password).await? ,
+            must_change_password ,
+            updated_at: Utc::now().into() ,
+            ..model
+        }).await
+    }
+    ///
+    /// アカウントを削除する
+    ///
+    pub async fn delete(store: &dyn AccountStore, model: account::Model) -> Result<()> {
+        store.delete(&model.id).await
+    }
+
+    // 既存の認証サービスで照合する(認証失敗はfalse、内部エラーはErr)
+    async fn verify_legacy(provider: &dyn ServiceProvider, form: &LoginForm, model: &account::Model) -> Result<bool> {
+        match provider.authenticate(form).await {
+            Ok(user) => Ok(user.user_id == model.id) ,
+            Err(error) => WebAppError::error_message(error).map(|_| false)
+        }
+    }
+    fn roles(admin: bool) -> String {
+        if admin { String::from(Role::ADMIN) } else { String::new() }
+    }
+}
\ No newline at end of file
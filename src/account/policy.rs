@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use crate::account::Password;
+use crate::account::forms::FormErrors;
+use crate::config::EnvConfig;
+use crate::i18n::LocalizedMessage;
+use crate::startup::StartupError;
+
+///
+/// パスワードに含める文字種
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterClass {
+    Upper ,     // 英大文字
+    Lower ,     // 英小文字
+    Digit ,     // 数字
+    Symbol      // 記号
+}
+impl CharacterClass {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "upper" => Some(CharacterClass::Upper) ,
+            "lower" => Some(CharacterClass::Lower) ,
+            "digit" => Some(CharacterClass::Digit) ,
+            "symbol" => Some(CharacterClass::Symbol) ,
+            _ => None
+        }
+    }
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharacterClass::Upper => c.is_ascii_uppercase() ,
+            CharacterClass::Lower => c.is_ascii_lowercase() ,
+            CharacterClass::Digit => c.is_ascii_digit() ,
+            CharacterClass::Symbol => c.is_ascii_punctuation()
+        }
+    }
+    fn as_str(&self) -> &'static str {
+        match self {
+            CharacterClass::Upper => "upper" ,
+            CharacterClass::Lower => "lower" ,
+            CharacterClass::Digit => "digit" ,
+            CharacterClass::Symbol => "symbol"
+        }
+    }
+}
+
+///
+/// パスワードポリシー
+///
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    min_length: usize ,
+    max_length: usize ,
+    classes:    Vec<CharacterClass> ,     // 必須の文字種
+    breached:   Arc<HashSet<String>>      // 漏洩済みパスワード
+}
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self { min_length: 8, max_length: 64, classes: vec![CharacterClass::Lower, CharacterClass::Digit], breached: Arc::default() }
+    }
+}
+impl PasswordPolicy {
+    const MIN_LENGTH_KEY: &'static str = "PASSWORD_MIN_LENGTH";
+    const MAX_LENGTH_KEY: &'static str = "PASSWORD_MAX_LENGTH";
+    // 必須の文字種(upper,lower,digit,symbolをカンマ区切り)
+    const CLASSES_KEY: &'static str = "PASSWORD_REQUIRED_CLASSES";
+    // 漏洩済みパスワードの一覧ファイル(1行1パスワード、#で始まる行は無視する)
+    const BREACHED_LIST_KEY: &'static str = "PASSWORD_BREACHED_LIST";
+    // 生成するパスワードの長さと試行回数
+    const GENERATED_LENGTH: usize = 16;
+    const GENERATE_ATTEMPTS: usize = 100;
+    ///
+    /// 環境変数からポリシーを生成する
+    ///
+    pub fn from_env() -> Result<Self, StartupError> {
+        let default = Self::default();
+        let min_length = EnvConfig::u64(Self::MIN_LENGTH_KEY, default.min_length as u64)? as usize;
+        let max_length = EnvConfig::u64(Self::MAX_LENGTH_KEY, default.max_length as u64)? as usize;
+        if min_length == 0 || min_length > max_length {
+            return Err(StartupError::Config(format!("{}は1以上{}以下を指定してください", Self::MIN_LENGTH_KEY, Self::MAX_LENGTH_KEY)));
+        }
+        let classes = EnvConfig::string(Self::CLASSES_KEY, "lower,digit").split(',')
+            .map(str::trim).filter(|class| !class.is_empty())
+            .map(|class| CharacterClass::parse(class).ok_or_else(|| StartupError::Config(
+                format!("{}の値'{}'はupper,lower,digit,symbolのいずれかを指定してください", Self::CLASSES_KEY, class))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let breached = match EnvConfig::string(Self::BREACHED_LIST_KEY, "").as_str() {
+            "" => HashSet::new() ,
+            path => Self::load_breached(path)?
+        };
+        Ok(Self { min_length, max_length, classes, breached: Arc::new(breached) })
+    }
+    ///
+    /// パスワードがポリシーを満たすか検証する
+    /// 満たさない場合はエラーメッセージを返す
+    ///
+    pub fn check(&self, password: &str, user_name: &str) -> Result<(), LocalizedMessage> {
+        let length = password.chars().count();
+        if length < self.min_length || length > self.max_length {
+            return Err(LocalizedMessage::new("password-policy-length").arg("min", self.min_length).arg("max", self.max_length));
+        }
+        // 不足している文字種を1つずつ通知する
+        if let Some(missing) = self.classes.iter().find(|class| !password.chars().any(|c| class.matches(c))) {
+            return Err(LocalizedMessage::new("password-policy-class").arg("class", missing.as_str()));
+        }
+        if password.eq_ignore_ascii_case(user_name.trim()) {
+            return Err(LocalizedMessage::new("password-policy-user-name"));
+        }
+        if self.breached.contains(password) {
+            return Err(LocalizedMessage::new("password-policy-breached"));
+        }
+        Ok(())
+    }
+    ///
+    /// 入力値の検証結果にパスワードポリシーの検証結果を加える
+    ///
+    pub fn apply(&self, result: Result<(), FormErrors>, password: &str, user_name: &str) -> Result<(), FormErrors> {
+        let mut errors = result.err().unwrap_or_default();
+        if !errors.errors.contains_key("password") {
+            if let Err(message) = self.check(password, user_name) {
+                errors.errors.insert(String::from("password"), message);
+            }
+        }
+        if errors.errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    ///
+    /// ポリシーを満たす英数字のパスワードを生成する
+    /// 記号を必須とする場合など、生成できない場合はNoneを返す
+    ///
+    pub fn generate(&self, user_name: &str) -> Option<String> {
+        let length = Self::GENERATED_LENGTH.clamp(self.min_length, self.max_length);
+        (0..Self::GENERATE_ATTEMPTS).map(|_| Password::generate(length))
+            .find(|password| self.check(password, user_name).is_ok())
+    }
+
+    fn load_breached(path: &str) -> Result<HashSet<String>, StartupError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|error| StartupError::Config(format!("{}: {}", path, error)))?;
+        Ok(content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,104 @@
+use chrono::{Duration, Utc};
+use fluent_bundle::FluentArgs;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use crate::Result;
+use crate::account::AccountService;
+use crate::account::entity as account;
+use crate::account::reset_token;
+use crate::account::store::AccountStore;
+use crate::config::EnvConfig;
+use crate::i18n::Translator;
+use crate::mail::Mail;
+use crate::startup::StartupError;
+
+///
+/// パスワード再設定の設定
+///
+#[derive(Debug, Clone)]
+pub struct PasswordResetConfig {
+    ttl_minutes: i64 ,      // トークンの有効期間(分)
+    base_url:    String     // メールに記載するURLの起点
+}
+impl PasswordResetConfig {
+    const TTL_KEY: &'static str = "PASSWORD_RESET_TTL_MINUTES";
+    const BASE_URL_KEY: &'static str = "APP_BASE_URL";
+    pub const RESET_PATH: &'static str = "/web_sample/password/reset/";
+    pub fn from_env() -> std::result::Result<Self, StartupError> {
+        let ttl_minutes = EnvConfig::u64(Self::TTL_KEY, 30)?;
+        if ttl_minutes == 0 {
+            return Err(StartupError::Config(format!("{}は1以上を指定してください", Self::TTL_KEY)));
+        }
+        Ok(Self {
+            ttl_minutes: ttl_minutes as i64 ,
+            base_url: EnvConfig::string(Self::BASE_URL_KEY, "https://localhost:8081").trim_end_matches('/').to_string()
+        })
+    }
+    ///
+    /// 再設定用のリンクを記載したメールを要求した画面の表示言語で生成する
+    ///
+    pub fn mail(&self, translator: &Translator, lang: &str, to: &str, account: &account::Model, token: &str) -> Mail {
+        let mut args = FluentArgs::new();
+        args.set("name" , account.user_name.clone());
+        args.set("url" , format!("{}{}{}", self.base_url, Self::RESET_PATH, token));
+        args.set("minutes" , self.ttl_minutes);
+        Mail {
+            to: to.to_string() ,
+            subject: translator.message(lang, "reset-mail-subject", None) ,
+            body: format!("{}\n", translator.message(lang, "reset-mail-body", Some(&args)))
+        }
+    }
+}
+
+///
+/// パスワード再設定 サービス
+///
+pub struct PasswordResetService;
+impl PasswordResetService {
+    // トークンのバイト数
+    const TOKEN_BYTES: usize = 32;
+    ///
+    /// トークンを発行する
+    /// 返したトークンはメールにのみ記載し、データベースにはハッシュ値を保持する
+    ///
+    pub async fn issue(store: &dyn AccountStore, account: &account::Model, config: &PasswordResetConfig) -> Result<String> {
+        let mut bytes = [0u8; Self::TOKEN_BYTES];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token = base64::encode_config(bytes, base64::URL_SAFE_NO_PAD);
+        let now = Utc::now();
+        store.insert_reset_token(reset_token::Model {
+            id: 0 ,
+            account_id: account.id.clone() ,
+            token_hash: Self::hash(&token) ,
+            expires_at: (now + Duration::minutes(config.ttl_minutes)).into() ,
+            used_at: None ,
+            created_at: now.into()
+        }).await?;
+        Ok(token)
+    }
+    ///
+    /// 未使用かつ有効期限内のトークンと対象のアカウントを取得する
+    ///
+    pub async fn find_valid(store: &dyn AccountStore, token: &str) -> Result<Option<(reset_token::Model, account::Model)>> {
+        let model = match store.find_reset_token(&Self::hash(token), Utc::now()).await? {
+            Some(model) => model ,
+            None => return Ok(None)
+        };
+        match AccountService::find(store, &model.account_id).await? {
+            Some(account) if account.enabled => Ok(Some((model, account))) ,
+            _ => Ok(None)
+        }
+    }
+    ///
+    /// トークンを使用済みにする
+    /// 同時に利用された場合は一方のみ成功し、アカウントの他の未使用トークンも無効にする
+    ///
+    pub async fn consume(store: &dyn AccountStore, model: &reset_token::Model) -> Result<bool> {
+        store.consume_reset_token(model, Utc::now()).await
+    }
+
+    // トークンのSHA-256ハッシュ
+    fn hash(token: &str) -> String {
+        Sha256::digest(token.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+///
+/// パスワード再設定トークン エンティティ
+/// トークンはハッシュ値のみを保持する
+///
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "password_reset_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id:         i64 ,
+    pub account_id: String ,                        // ユーザーId
+    #[sea_orm(unique)]
+    pub token_hash: String ,                        // トークンのSHA-256ハッシュ
+    pub expires_at: DateTimeWithTimeZone ,          // 有効期限
+    pub used_at:    Option<DateTimeWithTimeZone> ,  // 使用日時(未使用の場合はNone)
+    pub created_at: DateTimeWithTimeZone            // 発行日時
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use redis::aio::ConnectionManager;
+use crate::session_store::AppSessionStore;
+
+///
+/// トークン失効日時を保持するストア
+/// ユーザー毎に、この日時より前に発行されたトークンを無効とする
+///
+#[async_trait]
+pub trait RevocationStore: Send + Sync {
+    // 失効日時(UNIXタイムスタンプのミリ秒)を取得する
+    async fn revoked_before(&self, user_id: &str) -> Result<Option<i64>, String>;
+    // 失効日時を設定する ttlはトークンの有効期間(秒)
+    async fn revoke(&self, user_id: &str, before: i64, ttl: u64) -> Result<(), String>;
+    // トークン(jti)が個別に失効済みか検証する
+    async fn is_token_revoked(&self, token_id: &str) -> Result<bool, String>;
+    // トークン(jti)を個別に失効させる ttlはトークンの残りの有効期間(秒)
+    async fn revoke_token(&self, token_id: &str, ttl: u64) -> Result<(), String>;
+}
+
+///
+/// インメモリストア(単一ノード、テスト用)
+///
+#[derive(Default)]
+pub struct MemoryRevocationStore {
+    revoked: Mutex<HashMap<String, i64>> ,
+    tokens:  Mutex<HashMap<String, i64>>    // 失効させたトークンと有効期限(UNIXタイムスタンプ)
+}
+#[async_trait]
+impl RevocationStore for MemoryRevocationStore {
+    async fn revoked_before(&self, user_id: &str) -> Result<Option<i64>, String> {
+        Ok(self.revoked.lock().unwrap_or_else(PoisonError::into_inner).get(user_id).copied())
+    }
+    async fn revoke(&self, user_id: &str, before: i64, _ttl: u64) -> Result<(), String> {
+        self.revoked.lock().unwrap_or_else(PoisonError::into_inner).insert(user_id.to_string(), before);
+        Ok(())
+    }
+    async fn is_token_revoked(&self, token_id: &str) -> Result<bool, String> {
+        Ok(self.tokens.lock().unwrap_or_else(PoisonError::into_inner).contains_key(token_id))
+    }
+    async fn revoke_token(&self, token_id: &str, ttl: u64) -> Result<(), String> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tokens = self.tokens.lock().unwrap_or_else(PoisonError::into_inner);
+        // 有効期限が過ぎたトークンは失効を記録する必要がない
+        tokens.retain(|_, expires_at| *expires_at > now);
+        tokens.insert(token_id.to_string(), now + ttl as i64);
+        Ok(())
+    }
+}
+
+///
+/// Redisストア(複数レプリカ間で失効を共有する)
+///
+pub struct RedisRevocationStore {
+    connection: ConnectionManager
+}
+impl RedisRevocationStore {
+    const KEY_PREFIX: &'static str = "token_revoked_ms:";
+    const TOKEN_KEY_PREFIX: &'static str = "token_revoked_jti:";
+    pub async fn new(client: redis::Client) -> redis::RedisResult<Self> {
+        Ok(Self { connection: ConnectionManager::new(client).await? })
+    }
+}
+#[async_trait]
+impl RevocationStore for RedisRevocationStore {
+    async fn revoked_before(&self, user_id: &str) -> Result<Option<i64>, String> {
+        let mut connection = self.connection.clone();
+        connection.get(format!("{}{}", Self::KEY_PREFIX, user_id)).await.map_err(|error| error.to_string())
+    }
+    async fn revoke(&self, user_id: &str, before: i64, ttl: u64) -> Result<(), String> {
+        let mut connection = self.connection.clone();
+        connection.set_ex(format!("{}{}", Self::KEY_PREFIX, user_id), before, ttl).await
+            .map_err(|error| error.to_string())
+    }
+    async fn is_token_revoked(&self, token_id: &str) -> Result<bool, String> {
+        let mut connection = self.connection.clone();
+        connection.exists(format!("{}{}", Self::TOKEN_KEY_PREFIX, token_id)).await.map_err(|error| error.to_string())
+    }
+    async fn revoke_token(&self, token_id: &str, ttl: u64) -> Result<(), String> {
+        let mut connection = self.connection.clone();
+        connection.set_ex(format!("{}{}", Self::TOKEN_KEY_PREFIX, token_id), 1, ttl.max(1)).await
+            .map_err(|error| error.to_string())
+    }
+}
+
+///
+/// トークンの失効
+/// パスワード変更時などに、それ以前に発行された全てのトークンを無効にし、ユーザーのセッションを削除する
+///
+#[derive(Clone)]
+pub struct TokenRevocation {
+    store:    Arc<dyn RevocationStore> ,
+    sessions: Option<AppSessionStore>    // セッションを削除するストア
+}
+impl TokenRevocation {
+    pub fn new(store: Arc<dyn RevocationStore>) -> Self {
+        Self { store , sessions: None }
+    }
+    ///
+    /// 失効時にユーザーのセッションを削除するセッションストアを設定する
+    ///
+    pub fn sessions(self, sessions: AppSessionStore) -> Self {
+        Self { sessions: Some(sessions) , ..self }
+    }
+    ///
+    /// 現在まで(同じミリ秒を含む)に発行されたユーザーのトークンを失効させ、セッションを削除する
+    /// 失効日時を返す 以降に発行するトークンは失効日時より後の発行日時とする
+    ///
+    pub async fn revoke(&self, user_id: &str, ttl: u64) -> Result<i64, String> {
+        let before = chrono::Utc::now().timestamp_millis();
+        self.store.revoke(user_id, before, ttl).await?;
+        if let Some(sessions) = &self.sessions {
+            sessions.purge_user(user_id).await.map_err(|error| error.to_string())?;
+        }
+        Ok(before)
+    }
+    ///
+    /// トークンを個別に失効させる(ログアウト)
+    ///
+    pub async fn revoke_token(&self, token_id: &str, ttl: u64) -> Result<(), String> {
+        self.store.revoke_token(token_id, ttl).await
+    }
+    ///
+    /// 指定日時(ミリ秒)に発行されたトークンが失効済みか検証する
+    /// token_idが空でない場合は個別の失効も検証する
+    ///
+    pub async fn is_revoked(&self, user_id: &str, issued_at: i64, token_id: &str) -> Result<bool, String> {
+        if self.store.revoked_before(user_id).await?.map_or(false, |before| issued_at <= before) {
+            return Ok(true);
+        }
+        match token_id.is_empty() {
+            true => Ok(false) ,
+            false => self.store.is_token_revoked(token_id).await
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value as Json;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+use sea_orm::sea_query::Expr;
+use crate::{Result, WebAppError};
+use crate::account::entity as account;
+use crate::account::{mfa_setting, reset_token};
+use super::AccountStore;
+
+///
+/// SeaORM(PostgreSQL)によるアカウントのストア
+///
+pub struct DatabaseAccountStore {
+    pool: Arc<DatabaseConnection>
+}
+impl DatabaseAccountStore {
+    pub fn new(pool: Arc<DatabaseConnection>) -> Self {
+        Self { pool }
+    }
+
+    // 全ての列を更新対象とするActiveModel
+    fn account(model: account::Model) -> account::ActiveModel {
+        account::ActiveModel {
+            id: Set(model.id) ,
+            user_name: Set(model.user_name) ,
+            email: Set(model.email) ,
+            password_hash: Set(model.password_hash) ,
+            roles: Set(model.roles) ,
+            enabled: Set(model.enabled) ,
+            must_change_password: Set(model.must_change_password) ,
+            created_at: Set(model.created_at) ,
+            updated_at: Set(model.updated_at)
+        }
+    }
+    fn mfa_setting(model: mfa_setting::Model) -> mfa_setting::ActiveModel {
+        mfa_setting::ActiveModel {
+            account_id: Set(model.account_id) ,
+            secret: Set(model.secret) ,
+            recovery_codes: Set(model.recovery_codes) ,
+            last_step: Set(model.last_step) ,
+            created_at: Set(model.created_at) ,
+            updated_at: Set(model.updated_at)
+        }
+    }
+    fn db_error(error: DbErr) -> WebAppError {
+        WebAppError::InternalError(error.to_string())
+    }
+}
+#[async_trait(?Send)]
+impl AccountStore for DatabaseAccountStore {
+    async fn list(&self) -> Result<Vec<account::Model>> {
+        account::Entity::find().order_by_asc(account::Column::UserName).all(self.pool.as_ref()).await
+            .map_err(Self::db_error)
+    }
+    async fn find(&self, id: &str) -> Result<Option<account::Model>> {
+        account::Entity::find_by_id(id.to_string()).one(self.pool.as_ref()).await.map_err(Self::db_error)
+    }
+    async fn find_by_name(&self, user_name: &str) -> Result<Option<account::Model>> {
+        account::Entity::find().filter(account::Column::UserName.eq(user_name)).one(self.pool.as_ref()).await
+            .map_err(Self::db_error)
+    }
+    async fn find_by_email(&self, email: &str) -> Result<Option<account::Model>> {
+        account::Entity::find().filter(account::Column::Email.eq(email)).one(self.pool.as_ref()).await
+            .map_err(Self::db_error)
+    }
+    async fn insert(&self, model: account::Model) -> Result<account::Model> {
+        Self::account(model).insert(self.pool.as_ref()).await.map_err(Self::db_error)
+    }
+    async fn update(&self, model: account::Model) -> Result<account::Model> {
+        Self::account(model).update(self.pool.as_ref()).await.map_err(Self::db_error)
+    }
+    async fn delete(&self, id: &str) -> Result<()> {
+        account::Entity::delete_by_id(id.to_string()).exec(self.pool.as_ref()).await
+            .map(|_| ()).map_err(Self::db_error)
+    }
+    async fn find_mfa(&self, account_id: &str) -> Result<Option<mfa_setting::Model>> {
+        mfa_setting::Entity::find_by_id(account_id.to_string()).one(self.pool.as_ref()).await.map_err(Self::db_error)
+    }
+    async fn save_mfa(&self, model: mfa_setting::Model) -> Result<()> {
+        self.delete_mfa(&model.account_id).await?;
+        Self::mfa_setting(model).insert(self.pool.as_ref()).await.map(|_| ()).map_err(Self::db_error)
+    }
+    async fn update_mfa(&self, model: mfa_setting::Model) -> Result<()> {
+        Self::mfa_setting(model).update(self.pool.as_ref()).await.map(|_| ()).map_err(Self::db_error)
+    }
+    async fn advance_mfa_step(&self, account_id: &str, step: i64) -> Result<bool> {
+        let updated = mfa_setting::Entity::update_many()
+            .col_expr(mfa_setting::Column::LastStep, Expr::value(step))
+            .filter(mfa_setting::Column::AccountId.eq(account_id))
+            .filter(Condition::any()
+                .add(mfa_setting::Column::LastStep.is_null())
+                .add(mfa_setting::Column::LastStep.lt(step)))
+            .exec(self.pool.as_ref()).await.map_err(Self::db_error)?;
+        Ok(updated.rows_affected > 0)
+    }
+    async fn replace_recovery_codes(&self, account_id: &str, previous: &Json, codes: Json, now: DateTime<Utc>) -> Result<bool> {
+        // json型は等値比較できないため、文字列にして比較する
+        let updated = mfa_setting::Entity::update_many()
+            .col_expr(mfa_setting::Column::RecoveryCodes, Expr::value(codes))
+            .col_expr(mfa_setting::Column::UpdatedAt, Expr::value(now))
+            .filter(mfa_setting::Column::AccountId.eq(account_id))
+            .filter(Expr::cust_with_values(r#"CAST("recovery_codes" AS TEXT) = ?"#, vec![previous.to_string()]))
+            .exec(self.pool.as_ref()).await.map_err(Self::db_error)?;
+        Ok(updated.rows_affected > 0)
+    }
+    async fn delete_mfa(&self, account_id: &str) -> Result<()> {
+        mfa_setting::Entity::delete_by_id(account_id.to_string()).exec(self.pool.as_ref()).await
+            .map(|_| ()).map_err(Self::db_error)
+    }
+    async fn insert_reset_token(&self, model: reset_token::Model) -> Result<()> {
+        let model = reset_token::ActiveModel {
+            account_id: Set(model.account_id) ,
+            token_hash: Set(model.token_hash) ,
+            expires_at: Set(model.expires_at) ,
+            used_at: Set(model.used_at) ,
+            created_at: Set(model.created_at) ,
+            ..Default::default()
+        };
+        model.insert(self.pool.as_ref()).await.map(|_| ()).map_err(Self::db_error)
+    }
+    async fn find_reset_token(&self, token_hash: &str, now: DateTime<Utc>) -> Result<Option<reset_token::Model>> {
+        reset_token::Entity::find()
+            .filter(reset_token::Column::TokenHash.eq(token_hash))
+            .filter(reset_token::Column::UsedAt.is_null())
+            .filter(reset_token::Column::ExpiresAt.gt(now))
+            .one(self.pool.as_ref()).await.map_err(Self::db_error)
+    }
+    async fn consume_reset_token(&self, model: &reset_token::Model, now: DateTime<Utc>) -> Result<bool> {
+        let consumed = reset_token::Entity::update_many()
+            .col_expr(reset_token::Column::UsedAt, Expr::value(now))
+            .filter(reset_token::Column::Id.eq(model.id))
+            .filter(reset_token::Column::UsedAt.is_null())
+            .exec(self.pool.as_ref()).await.map_err(Self::db_error)?;
+        if consumed.rows_affected == 0 {
+            return Ok(false);
+        }
+        reset_token::Entity::update_many()
+            .col_expr(reset_token::Column::UsedAt, Expr::value(now))
+            .filter(reset_token::Column::AccountId.eq(model.account_id.as_str()))
+            .filter(reset_token::Column::UsedAt.is_null())
+            .exec(self.pool.as_ref()).await.map_err(Self::db_error)?;
+        Ok(true)
+    }
+}
\ No newline at end of file
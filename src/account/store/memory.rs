@@ -0,0 +1,156 @@
+use std::sync::{PoisonError, RwLock};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value as Json;
+use crate::{Result, WebAppError};
+use crate::account::entity as account;
+use crate::account::{mfa_setting, reset_token, Password};
+use super::AccountStore;
+
+///
+/// インメモリのアカウントのストア(テスト、デモ用)
+///
+#[derive(Default)]
+pub struct MemoryAccountStore {
+    accounts:     RwLock<Vec<account::Model>> ,
+    mfa_settings: RwLock<Vec<mfa_setting::Model>> ,
+    reset_tokens: RwLock<Vec<reset_token::Model>>
+}
+impl MemoryAccountStore {
+    // ユーザー名が重複した場合のメッセージ
+    const DUPLICATE_MESSAGE: &'static str = "duplicate user_name";
+    ///
+    /// アカウントを持たないストアを生成する
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+    ///
+    /// アカウントを追加する
+    ///
+    pub fn account(self, model: account::Model) -> Self {
+        self.accounts.write().unwrap_or_else(PoisonError::into_inner).push(model);
+        self
+    }
+    ///
+    /// ユーザー名とパスワードからアカウントを生成して追加する
+    ///
+    pub async fn user(self, user_name: &str, password: &str) -> Result<Self> {
+        let now = Utc::now();
+        Ok(self.account(account::Model {
+            id: uuid::Uuid::new_v4().to_string() ,
+            user_name: user_name.to_string() ,
+            email: None ,
+            password_hash: Password::hash(password).await? ,
+            roles: String::new() ,
+            enabled: true ,
+            must_change_password: false ,
+            created_at: now.into() ,
+            updated_at: now.into()
+        }))
+    }
+
+    fn find_account(&self, predicate: impl Fn(&account::Model) -> bool) -> Option<account::Model> {
+        self.accounts.read().unwrap_or_else(PoisonError::into_inner).iter().find(|model| predicate(model)).cloned()
+    }
+}
+#[async_trait(?Send)]
+impl AccountStore for MemoryAccountStore {
+    async fn list(&self) -> Result<Vec<account::Model>> {
+        let mut accounts = self.accounts.read().unwrap_or_else(PoisonError::into_inner).clone();
+        accounts.sort_by(|left, right| left.user_name.cmp(&right.user_name));
+        Ok(accounts)
+    }
+    async fn find(&self, id: &str) -> Result<Option<account::Model>> {
+        Ok(self.find_account(|model| model.id == id))
+    }
+    async fn find_by_name(&self, user_name: &str) -> Result<Option<account::Model>> {
+        Ok(self.find_account(|model| model.user_name == user_name))
+    }
+    async fn find_by_email(&self, email: &str) -> Result<Option<account::Model>> {
+        Ok(self.find_account(|model| model.email.as_deref() == Some(email)))
+    }
+    async fn insert(&self, model: account::Model) -> Result<account::Model> {
+        let mut accounts = self.accounts.write().unwrap_or_else(PoisonError::into_inner);
+        if accounts.iter().any(|account| account.id == model.id || account.user_name == model.user_name) {
+            return Err(WebAppError::InternalError(String::from(Self::DUPLICATE_MESSAGE)));
+        }
+        accounts.push(model.clone());
+        Ok(model)
+    }
+    async fn update(&self, model: account::Model) -> Result<account::Model> {
+        let mut accounts = self.accounts.write().unwrap_or_else(PoisonError::into_inner);
+        match accounts.iter_mut().find(|account| account.id == model.id) {
+            Some(account) => *account = model.clone() ,
+            None => return Err(WebAppError::InternalError(format!("account not found: {}", model.id)))
+        }
+        Ok(model)
+    }
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.accounts.write().unwrap_or_else(PoisonError::into_inner).retain(|account| account.id != id);
+        Ok(())
+    }
+    async fn find_mfa(&self, account_id: &str) -> Result<Option<mfa_setting::Model>> {
+        Ok(self.mfa_settings.read().unwrap_or_else(PoisonError::into_inner).iter()
+            .find(|setting| setting.account_id == account_id).cloned())
+    }
+    async fn save_mfa(&self, model: mfa_setting::Model) -> Result<()> {
+        let mut settings = self.mfa_settings.write().unwrap_or_else(PoisonError::into_inner);
+        settings.retain(|setting| setting.account_id != model.account_id);
+        settings.push(model);
+        Ok(())
+    }
+    async fn update_mfa(&self, model: mfa_setting::Model) -> Result<()> {
+        let mut settings = self.mfa_settings.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(setting) = settings.iter_mut().find(|setting| setting.account_id == model.account_id) {
+            *setting = model;
+        }
+        Ok(())
+    }
+    async fn advance_mfa_step(&self, account_id: &str, step: i64) -> Result<bool> {
+        let mut settings = self.mfa_settings.write().unwrap_or_else(PoisonError::into_inner);
+        match settings.iter_mut().find(|setting| setting.account_id == account_id) {
+            Some(setting) if setting.last_step.map_or(true, |last_step| last_step < step) => {
+                setting.last_step = Some(step);
+                Ok(true)
+            },
+            _ => Ok(false)
+        }
+    }
+    async fn replace_recovery_codes(&self, account_id: &str, previous: &Json, codes: Json, now: DateTime<Utc>) -> Result<bool> {
+        let mut settings = self.mfa_settings.write().unwrap_or_else(PoisonError::into_inner);
+        match settings.iter_mut().find(|setting| setting.account_id == account_id) {
+            Some(setting) if setting.recovery_codes == *previous => {
+                setting.recovery_codes = codes;
+                setting.updated_at = now.into();
+                Ok(true)
+            },
+            _ => Ok(false)
+        }
+    }
+    async fn delete_mfa(&self, account_id: &str) -> Result<()> {
+        self.mfa_settings.write().unwrap_or_else(PoisonError::into_inner).retain(|setting| setting.account_id != account_id);
+        Ok(())
+    }
+    async fn insert_reset_token(&self, model: reset_token::Model) -> Result<()> {
+        let mut tokens = self.reset_tokens.write().unwrap_or_else(PoisonError::into_inner);
+        let id = tokens.iter().map(|token| token.id).max().unwrap_or(0) + 1;
+        tokens.push(reset_token::Model { id , ..model });
+        Ok(())
+    }
+    async fn find_reset_token(&self, token_hash: &str, now: DateTime<Utc>) -> Result<Option<reset_token::Model>> {
+        Ok(self.reset_tokens.read().unwrap_or_else(PoisonError::into_inner).iter()
+            .find(|token| token.token_hash == token_hash && token.used_at.is_none() && token.expires_at > now)
+            .cloned())
+    }
+    async fn consume_reset_token(&self, model: &reset_token::Model, now: DateTime<Utc>) -> Result<bool> {
+        let mut tokens = self.reset_tokens.write().unwrap_or_else(PoisonError::into_inner);
+        if !tokens.iter().any(|token| token.id == model.id && token.used_at.is_none()) {
+            return Ok(false);
+        }
+        tokens.iter_mut()
+            .filter(|token| token.account_id == model.account_id && token.used_at.is_none())
+            .for_each(|token| token.used_at = Some(now.into()));
+        Ok(true)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value as Json;
+use crate::Result;
+use crate::account::entity as account;
+use crate::account::{mfa_setting, reset_token};
+
+mod database;
+mod memory;
+pub use database::DatabaseAccountStore;
+pub use memory::MemoryAccountStore;
+
+///
+/// アカウント、二要素認証の設定、パスワード再設定トークンのストア
+/// ハンドラはweb::Data<dyn AccountStore>で利用し、永続化層の実装に依存しない
+/// Futureはワーカースレッド内で完結するためSendを要求しない
+///
+#[async_trait(?Send)]
+pub trait AccountStore: Send + Sync {
+    // 全アカウントをユーザー名順に取得する
+    async fn list(&self) -> Result<Vec<account::Model>>;
+    // ユーザーIdでアカウントを取得する
+    async fn find(&self, id: &str) -> Result<Option<account::Model>>;
+    // ユーザー名でアカウントを取得する
+    async fn find_by_name(&self, user_name: &str) -> Result<Option<account::Model>>;
+    // メールアドレスでアカウントを取得する
+    async fn find_by_email(&self, email: &str) -> Result<Option<account::Model>>;
+    // アカウントを登録する
+    async fn insert(&self, model: account::Model) -> Result<account::Model>;
+    // アカウントを更新する
+    async fn update(&self, model: account::Model) -> Result<account::Model>;
+    // アカウントを削除する
+    async fn delete(&self, id: &str) -> Result<()>;
+    // 二要素認証の設定を取得する 未登録の場合はNone
+    async fn find_mfa(&self, account_id: &str) -> Result<Option<mfa_setting::Model>>;
+    // 二要素認証の設定を登録する 登録済みの設定は置き換える
+    async fn save_mfa(&self, model: mfa_setting::Model) -> Result<()>;
+    // 二要素認証の設定を更新する
+    async fn update_mfa(&self, model: mfa_setting::Model) -> Result<()>;
+    // 記録済みのステップより新しい場合のみ、使用したTOTPのステップを記録する
+    // 並行したリクエストで同じステップを受け付けないよう、条件付きで更新して結果を返す
+    async fn advance_mfa_step(&self, account_id: &str, step: i64) -> Result<bool>;
+    // リカバリーコードの一覧が読み取った値から変更されていない場合のみ置き換える
+    // 並行したリクエストで同じリカバリーコードを使用できないよう、条件付きで更新して結果を返す
+    async fn replace_recovery_codes(&self, account_id: &str, previous: &Json, codes: Json, now: DateTime<Utc>) -> Result<bool>;
+    // 二要素認証の設定を削除する
+    async fn delete_mfa(&self, account_id: &str) -> Result<()>;
+    // パスワード再設定トークンを登録する
+    async fn insert_reset_token(&self, model: reset_token::Model) -> Result<()>;
+    // 未使用かつ有効期限内のパスワード再設定トークンをハッシュ値で取得する
+    async fn find_reset_token(&self, token_hash: &str, now: DateTime<Utc>) -> Result<Option<reset_token::Model>>;
+    // パスワード再設定トークンを使用済みにする
+    // 未使用の場合のみ成功し、アカウントの他の未使用トークンも無効にする
+    async fn consume_reset_token(&self, model: &reset_token::Model, now: DateTime<Utc>) -> Result<bool>;
+}
\ No newline at end of file
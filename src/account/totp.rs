@@ -0,0 +1,85 @@
+use hmac::{Hmac, Mac};
+use qrcode::QrCode;
+use qrcode::render::svg;
+use rand::RngCore;
+use sha1::Sha1;
+
+///
+/// TOTP(RFC 6238) HMAC-SHA1、6桁、30秒
+///
+pub struct Totp {
+    secret: Vec<u8>
+}
+impl Totp {
+    const DIGITS: u32 = 6;
+    const STEP_SECONDS: i64 = 30;
+    // 時刻のずれとして許容するステップ数(前後)
+    const SKEW: i64 = 1;
+    // 共有鍵のバイト数
+    const SECRET_BYTES: usize = 20;
+    const ALPHABET: base32::Alphabet = base32::Alphabet::RFC4648 { padding: false };
+
+    ///
+    /// 共有鍵を生成する(Base32)
+    ///
+    pub fn generate_secret() -> String {
+        let mut bytes = [0u8; Self::SECRET_BYTES];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        base32::encode(Self::ALPHABET, &bytes)
+    }
+    ///
+    /// Base32の共有鍵から生成する
+    ///
+    pub fn from_secret(secret: &str) -> Option<Self> {
+        base32::decode(Self::ALPHABET, secret).filter(|secret| !secret.is_empty()).map(|secret| Self { secret })
+    }
+    ///
+    /// コードを検証する
+    /// 一致した場合はそのステップを返す 使用済みのステップ以前のコードは受け付けない
+    ///
+    pub fn verify(&self, code: &str, now: i64, last_step: Option<i64>) -> Option<i64> {
+        let code = code.trim();
+        if code.len() != Self::DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let current = now / Self::STEP_SECONDS;
+        (current - Self::SKEW..=current + Self::SKEW)
+            .filter(|step| last_step.map_or(true, |last| *step > last))
+            .find(|step| self.code(*step) == code)
+    }
+    ///
+    /// 認証アプリに登録するURIを生成する
+    ///
+    pub fn provisioning_uri(secret: &str, issuer: &str, user_name: &str) -> String {
+        format!("otpauth://totp/{0}:{1}?secret={2}&issuer={0}&algorithm=SHA1&digits={3}&period={4}",
+            Self::encode(issuer), Self::encode(user_name), secret, Self::DIGITS, Self::STEP_SECONDS)
+    }
+    ///
+    /// URIのQRコードをSVGのdata URIとして生成する
+    ///
+    pub fn qr_code(uri: &str) -> Result<String, String> {
+        let image = QrCode::new(uri.as_bytes()).map_err(|error| error.to_string())?
+            .render::<svg::Color>()
+            .min_dimensions(200, 200)
+            .build();
+        Ok(format!("data:image/svg+xml;base64,{}", base64::encode(image)))
+    }
+
+    // 指定されたステップのコード
+    fn code(&self, step: i64) -> String {
+        let mut mac = Hmac::<Sha1>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        // 動的切り捨て(RFC 4226)
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let value = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+        format!("{:0width$}", value % 10u32.pow(Self::DIGITS), width = Self::DIGITS as usize)
+    }
+    // URIのラベルをパーセントエンコードする
+    fn encode(value: &str) -> String {
+        value.bytes().map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string() ,
+            _ => format!("%{:02X}", byte)
+        }).collect()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use actix_web::web::Bytes;
+use mime::Mime;
+use sha2::{Digest, Sha256};
+
+///
+/// 静的ファイル
+/// 内容のハッシュと圧縮済みの内容を保持する
+/// ディスクから読み込んだファイルは圧縮済みの内容を持たない
+///
+pub struct StaticAsset {
+    pub content_type: Mime ,
+    pub hash:         String ,          // 内容のハッシュ(ETag、ファイル名に利用)
+    pub identity:     Bytes ,           // 非圧縮
+    pub gzip:         Option<Bytes> ,   // gzip圧縮
+    pub brotli:       Option<Bytes>     // brotli圧縮
+}
+
+// build.rsが生成する(論理パス,非圧縮,gzip,brotli)の一覧
+const EMBEDDED: &[(&str, &[u8], &[u8], &[u8])] = include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+///
+/// 静的ファイル群
+/// ファイル名に内容のハッシュを付与したURLで配信する
+///
+#[derive(Clone, Default)]
+pub struct StaticAssets {
+    assets:      Arc<HashMap<String, Arc<StaticAsset>>> ,  // 論理パスと静的ファイル
+    fingerprint: Arc<HashMap<String, String>> ,            // ハッシュ付きパスと論理パス
+    disk_dir:    Option<Arc<PathBuf>>                      // 埋め込まれていないファイルを読み込むディレクトリ
+}
+impl StaticAssets {
+    pub const URL_PREFIX: &'static str = "/static/";
+    // ファイル名に付与するハッシュの桁数
+    const HASH_LENGTH: usize = 16;
+
+    ///
+    /// バイナリに埋め込まれた静的ファイルを読み込む
+    /// テンプレートが参照するファイルのみを、ビルド時に圧縮して埋め込んでいる
+    ///
+    pub fn embedded() -> Self {
+        let mut assets = HashMap::new();
+        let mut fingerprint = HashMap::new();
+        for &(path, identity, gzip, brotli) in EMBEDDED {
+            let asset = StaticAsset {
+                content_type: Self::content_type(path) ,
+                hash: Self::hash(identity) ,
+                identity: Bytes::from_static(identity) ,
+                gzip: Some(Bytes::from_static(gzip)) ,
+                brotli: Some(Bytes::from_static(brotli))
+            };
+            fingerprint.insert(Self::fingerprinted(path, &asset.hash), path.to_string());
+            assets.insert(path.to_string(), Arc::new(asset));
+        }
+        Self { assets: Arc::new(assets), fingerprint: Arc::new(fingerprint), disk_dir: None }
+    }
+    ///
+    /// 埋め込まれていないファイルを指定したディレクトリから配信する
+    /// 開発環境で、テンプレートの再読み込み後に新しく参照されたファイルをビルドせずに配信するために利用する
+    ///
+    pub fn disk_fallback(mut self, dir: &str) -> Self {
+        self.disk_dir = Some(Arc::new(PathBuf::from(dir)));
+        self
+    }
+    ///
+    /// 論理パスから配信用URLを生成する
+    /// 存在しないファイルはハッシュを付与しない
+    ///
+    pub fn url(&self, path: &str) -> String {
+        match self.assets.get(path) {
+            Some(asset) => format!("{}{}", Self::URL_PREFIX, Self::fingerprinted(path, &asset.hash)),
+            None => format!("{}{}", Self::URL_PREFIX, path)
+        }
+    }
+    ///
+    /// 要求されたパスの静的ファイルを取得する
+    /// ハッシュ付きのパスで要求された場合はtrueを返す
+    ///
+    pub fn find(&self, path: &str) -> Option<(Arc<StaticAsset>, bool)> {
+        match self.fingerprint.get(path) {
+            Some(logical) => self.assets.get(logical).map(|asset| (asset.clone(), true)) ,
+            None => self.assets.get(path).map(|asset| (asset.clone(), false))
+        }
+    }
+    ///
+    /// 埋め込まれていないファイルをディスクから読み込む
+    /// ディレクトリが設定されていない場合と、ディレクトリ外を指すパスはNoneを返す
+    ///
+    pub fn read_disk(&self, path: &str) -> Option<StaticAsset> {
+        let dir = self.disk_dir.as_ref()?;
+        let relative = Path::new(path);
+        if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+            return None;
+        }
+        let identity = std::fs::read(dir.join(relative)).ok()?;
+        Some(StaticAsset {
+            content_type: Self::content_type(path) ,
+            hash: Self::hash(&identity) ,
+            identity: Bytes::from(identity) ,
+            gzip: None ,
+            brotli: None
+        })
+    }
+
+    // 内容のSHA-256ハッシュ(先頭16桁)
+    fn hash(content: &[u8]) -> String {
+        Sha256::digest(content).iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()[..Self::HASH_LENGTH].to_string()
+    }
+    // 拡張子の前にハッシュを挿入する css/a.min.css -> css/a.min.<hash>.css
+    fn fingerprinted(path: &str, hash: &str) -> String {
+        match path.rfind('.') {
+            Some(index) if !path[index..].contains('/') =>
+                format!("{}.{}{}", &path[..index], hash, &path[index..]) ,
+            _ => format!("{}.{}", path, hash)
+        }
+    }
+    fn content_type(path: &str) -> Mime {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some("css") => mime::TEXT_CSS_UTF_8 ,
+            Some("js") => mime::APPLICATION_JAVASCRIPT_UTF_8 ,
+            Some("map") => mime::APPLICATION_JSON ,
+            Some("svg") => mime::IMAGE_SVG ,
+            Some("png") => mime::IMAGE_PNG ,
+            _ => mime::APPLICATION_OCTET_STREAM
+        }
+    }
+}
+
+///
+/// Teraの関数 asset(path="css/bootstrap.min.css")
+/// ハッシュ付きの配信用URLを返す
+///
+pub struct AssetUrlFunction(pub StaticAssets);
+impl tera::Function for AssetUrlFunction {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        match args.get("path").and_then(|path| path.as_str()) {
+            Some(path) => Ok(tera::Value::String(self.0.url(path))) ,
+            None => Err(tera::Error::msg("asset() requires a `path` argument"))
+        }
+    }
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+
+///
+/// 監査ログ エンティティ
+///
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "audit_logs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id:           i64 ,
+    pub user_id:      Option<String> ,       // 操作したユーザーId
+    pub user_name:    Option<String> ,       // 操作したユーザー名
+    pub action:       String ,               // 操作
+    pub target:       Option<String> ,       // 対象(商品番号など)
+    pub before_value: Option<Json> ,         // 変更前の値
+    pub after_value:  Option<Json> ,         // 変更後の値
+    pub ip_address:   Option<String> ,       // 接続元IPアドレス
+    pub request_id:   Option<String> ,       // リクエストId
+    pub created_at:   DateTimeWithTimeZone   // 記録日時
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
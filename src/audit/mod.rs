@@ -0,0 +1,241 @@
+pub mod entity;
+pub mod store;
+
+use std::future::{ready, Ready};
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use actix_web::dev::Payload;
+use chrono::{NaiveDate, TimeZone, Utc};
+use log::error;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::{Deserialize, Serialize};
+use crate::{Result, WebAppError};
+use crate::middleware::forwarded::ClientInfo;
+use crate::middleware::request_id::RequestId;
+use self::entity as audit_log;
+use self::store::AuditStore;
+
+///
+/// 監査対象の操作
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    LoginSucceeded ,         // ログイン成功
+    LoginFailed ,            // ログイン失敗
+    ProductRegistered ,      // 商品登録
+    AccountCreated ,         // アカウント登録
+    AccountUpdated ,         // アカウント更新
+    AccountDeleted ,         // アカウント削除
+    PasswordReset ,          // 管理者によるパスワードリセット
+    PasswordResetRequested , // パスワード再設定メールの送信
+    PasswordChanged ,        // パスワード変更
+    MfaEnabled ,             // 二要素認証の有効化
+    MfaDisabled              // 二要素認証の無効化
+}
+impl AuditAction {
+    pub const ALL: [AuditAction; 11] = [
+        AuditAction::LoginSucceeded , AuditAction::LoginFailed , AuditAction::ProductRegistered ,
+        AuditAction::AccountCreated , AuditAction::AccountUpdated , AuditAction::AccountDeleted ,
+        AuditAction::PasswordReset , AuditAction::PasswordResetRequested , AuditAction::PasswordChanged ,
+        AuditAction::MfaEnabled , AuditAction::MfaDisabled
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::LoginSucceeded => "login_succeeded" ,
+            AuditAction::LoginFailed => "login_failed" ,
+            AuditAction::ProductRegistered => "product_registered" ,
+            AuditAction::AccountCreated => "account_created" ,
+            AuditAction::AccountUpdated => "account_updated" ,
+            AuditAction::AccountDeleted => "account_deleted" ,
+            AuditAction::PasswordReset => "password_reset" ,
+            AuditAction::PasswordResetRequested => "password_reset_requested" ,
+            AuditAction::PasswordChanged => "password_changed" ,
+            AuditAction::MfaEnabled => "mfa_enabled" ,
+            AuditAction::MfaDisabled => "mfa_disabled"
+        }
+    }
+}
+
+///
+/// 監査ログに記録するリクエスト情報
+///
+#[derive(Debug, Clone)]
+pub struct AuditInfo {
+    ip_address: Option<String> ,
+    request_id: Option<String>
+}
+impl AuditInfo {
+    // 管理コマンドから操作した場合のリクエスト情報
+    pub fn command() -> Self {
+        Self { ip_address: None , request_id: None }
+    }
+    // 操作の記録内容を生成する
+    pub fn entry(&self, action: AuditAction) -> AuditEntry {
+        AuditEntry {
+            action ,
+            user_id: None ,
+            user_name: None ,
+            target: None ,
+            before: None ,
+            after: None ,
+            ip_address: self.ip_address.clone() ,
+            request_id: self.request_id.clone()
+        }
+    }
+}
+impl FromRequest for AuditInfo {
+    type Error = WebAppError;
+    type Future = Ready<Result<Self>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(Self {
+            ip_address: ClientInfo::remote_ip(req) ,
+            request_id: req.extensions().get::<RequestId>().map(|request_id| request_id.0.clone())
+        }))
+    }
+}
+
+///
+/// 監査ログの記録内容
+///
+#[derive(Debug)]
+pub struct AuditEntry {
+    action:     AuditAction ,
+    user_id:    Option<String> ,
+    user_name:  Option<String> ,
+    target:     Option<String> ,
+    before:     Option<serde_json::Value> ,
+    after:      Option<serde_json::Value> ,
+    ip_address: Option<String> ,
+    request_id: Option<String>
+}
+impl AuditEntry {
+    // 操作したユーザー
+    pub fn user(mut self, user_id: &str, user_name: &str) -> Self {
+        self.user_id = Some(user_id.to_string());
+        self.user_name = Some(user_name.to_string());
+        self
+    }
+    // 認証前の操作で入力されたユーザー名
+    pub fn user_name(mut self, user_name: &str) -> Self {
+        self.user_name = Some(user_name.to_string());
+        self
+    }
+    // 操作の対象
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+    // 変更前の値
+    pub fn before<T: Serialize>(mut self, value: &T) -> Self {
+        self.before = serde_json::to_value(value).ok();
+        self
+    }
+    // 変更後の値
+    pub fn after<T: Serialize>(mut self, value: &T) -> Self {
+        self.after = serde_json::to_value(value).ok();
+        self
+    }
+}
+
+///
+/// 監査ログの検索条件
+///
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditFilter {
+    pub user_name: Option<String> ,
+    pub action:    Option<String> ,
+    pub from:      Option<String> ,   // 開始日(YYYY-MM-DD)
+    pub to:        Option<String> ,   // 終了日(YYYY-MM-DD)
+    pub page:      Option<usize>
+}
+impl AuditFilter {
+    // 空文字の条件は指定なしとする
+    fn value(value: &Option<String>) -> Option<&str> {
+        value.as_deref().map(str::trim).filter(|value| !value.is_empty())
+    }
+    // 日付の開始日時
+    fn date(value: &Option<String>) -> Option<DateTimeWithTimeZone> {
+        Self::value(value)
+            .and_then(|value| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok())
+            .map(|date| Utc.from_utc_datetime(&date.and_hms(0, 0, 0)).into())
+    }
+}
+
+///
+/// 監査ログ(画面表示用)
+///
+#[derive(Debug, Serialize)]
+pub struct AuditLogView {
+    id:           i64 ,
+    created_at:   String ,
+    user_id:      Option<String> ,
+    user_name:    Option<String> ,
+    action:       String ,
+    target:       Option<String> ,
+    before_value: Option<String> ,
+    after_value:  Option<String> ,
+    ip_address:   Option<String> ,
+    request_id:   Option<String>
+}
+impl From<audit_log::Model> for AuditLogView {
+    fn from(model: audit_log::Model) -> Self {
+        Self {
+            id: model.id ,
+            created_at: model.created_at.format("%Y-%m-%d %H:%M:%S%z").to_string() ,
+            user_id: model.user_id ,
+            user_name: model.user_name ,
+            action: model.action ,
+            target: model.target ,
+            before_value: model.before_value.map(|value| value.to_string()) ,
+            after_value: model.after_value.map(|value| value.to_string()) ,
+            ip_address: model.ip_address ,
+            request_id: model.request_id
+        }
+    }
+}
+
+///
+/// 監査ログの検索結果
+///
+#[derive(Debug, Serialize)]
+pub struct AuditPage {
+    logs:  Vec<AuditLogView> ,
+    page:  usize ,     // 現在のページ(1始まり)
+    pages: usize       // 総ページ数
+}
+
+///
+/// 監査ログ サービス
+///
+pub struct AuditService;
+impl AuditService {
+    const PAGE_SIZE: usize = 20;
+    ///
+    /// 監査ログを記録する
+    /// 記録に失敗しても操作自体は完了しているため、エラーはログ出力のみとする
+    ///
+    pub async fn record(store: &dyn AuditStore, entry: AuditEntry) {
+        let action = entry.action.as_str();
+        let model = audit_log::Model {
+            id: 0 ,
+            user_id: entry.user_id ,
+            user_name: entry.user_name ,
+            action: action.to_string() ,
+            target: entry.target ,
+            before_value: entry.before ,
+            after_value: entry.after ,
+            ip_address: entry.ip_address ,
+            request_id: entry.request_id ,
+            created_at: Utc::now().into()
+        };
+        if let Err(error) = store.insert(model).await {
+            error!("audit log write failed ({}): {:?}", action, error);
+        }
+    }
+    ///
+    /// 条件に一致する監査ログを新しい順に取得する
+    ///
+    pub async fn search(store: &dyn AuditStore, filter: &AuditFilter) -> Result<AuditPage> {
+        store.search(filter, Self::PAGE_SIZE).await
+    }
+}
\ No newline at end of file
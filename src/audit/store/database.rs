@@ -0,0 +1,63 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Duration;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+              QueryOrder, Set};
+use crate::{Result, WebAppError};
+use crate::audit::{AuditFilter, AuditLogView, AuditPage};
+use crate::audit::entity as audit_log;
+use super::AuditStore;
+
+///
+/// SeaORM(PostgreSQL)による監査ログのストア
+///
+pub struct DatabaseAuditStore {
+    pool: Arc<DatabaseConnection>
+}
+impl DatabaseAuditStore {
+    pub fn new(pool: Arc<DatabaseConnection>) -> Self {
+        Self { pool }
+    }
+
+    fn db_error(error: DbErr) -> WebAppError {
+        WebAppError::InternalError(error.to_string())
+    }
+}
+#[async_trait(?Send)]
+impl AuditStore for DatabaseAuditStore {
+    async fn insert(&self, model: audit_log::Model) -> Result<()> {
+        let model = audit_log::ActiveModel {
+            user_id: Set(model.user_id) ,
+            user_name: Set(model.user_name) ,
+            action: Set(model.action) ,
+            target: Set(model.target) ,
+            before_value: Set(model.before_value) ,
+            after_value: Set(model.after_value) ,
+            ip_address: Set(model.ip_address) ,
+            request_id: Set(model.request_id) ,
+            created_at: Set(model.created_at) ,
+            ..Default::default()
+        };
+        model.insert(self.pool.as_ref()).await.map(|_| ()).map_err(Self::db_error)
+    }
+    async fn search(&self, filter: &AuditFilter, page_size: usize) -> Result<AuditPage> {
+        let mut query = audit_log::Entity::find().order_by_desc(audit_log::Column::Id);
+        if let Some(user_name) = AuditFilter::value(&filter.user_name) {
+            query = query.filter(audit_log::Column::UserName.contains(user_name));
+        }
+        if let Some(action) = AuditFilter::value(&filter.action) {
+            query = query.filter(audit_log::Column::Action.eq(action));
+        }
+        if let Some(from) = AuditFilter::date(&filter.from) {
+            query = query.filter(audit_log::Column::CreatedAt.gte(from));
+        }
+        if let Some(to) = AuditFilter::date(&filter.to) {
+            query = query.filter(audit_log::Column::CreatedAt.lt(to + Duration::days(1)));
+        }
+        let paginator = query.paginate(self.pool.as_ref(), page_size);
+        let pages = paginator.num_pages().await.map_err(Self::db_error)?;
+        let page = filter.page.unwrap_or(1).clamp(1, pages.max(1));
+        let logs = paginator.fetch_page(page - 1).await.map_err(Self::db_error)?;
+        Ok(AuditPage { logs: logs.into_iter().map(AuditLogView::from).collect(), page, pages })
+    }
+}
\ No newline at end of file
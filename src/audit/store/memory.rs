@@ -0,0 +1,46 @@
+use std::sync::{PoisonError, RwLock};
+use async_trait::async_trait;
+use chrono::Duration;
+use crate::Result;
+use crate::audit::{AuditFilter, AuditLogView, AuditPage};
+use crate::audit::entity as audit_log;
+use super::AuditStore;
+
+///
+/// インメモリの監査ログのストア(テスト、デモ用)
+///
+#[derive(Default)]
+pub struct MemoryAuditStore {
+    logs: RwLock<Vec<audit_log::Model>>
+}
+impl MemoryAuditStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+#[async_trait(?Send)]
+impl AuditStore for MemoryAuditStore {
+    async fn insert(&self, model: audit_log::Model) -> Result<()> {
+        let mut logs = self.logs.write().unwrap_or_else(PoisonError::into_inner);
+        let id = logs.last().map_or(0, |log| log.id) + 1;
+        logs.push(audit_log::Model { id , ..model });
+        Ok(())
+    }
+    async fn search(&self, filter: &AuditFilter, page_size: usize) -> Result<AuditPage> {
+        let user_name = AuditFilter::value(&filter.user_name);
+        let action = AuditFilter::value(&filter.action);
+        let from = AuditFilter::date(&filter.from);
+        let to = AuditFilter::date(&filter.to).map(|to| to + Duration::days(1));
+        let logs = self.logs.read().unwrap_or_else(PoisonError::into_inner).iter().rev()
+            .filter(|log| user_name.map_or(true, |name| log.user_name.as_deref().map_or(false, |value| value.contains(name))))
+            .filter(|log| action.map_or(true, |action| log.action == action))
+            .filter(|log| from.map_or(true, |from| log.created_at >= from))
+            .filter(|log| to.map_or(true, |to| log.created_at < to))
+            .cloned()
+            .collect::<Vec<_>>();
+        let pages = (logs.len() + page_size - 1) / page_size;
+        let page = filter.page.unwrap_or(1).clamp(1, pages.max(1));
+        let logs = logs.into_iter().skip((page - 1) * page_size).take(page_size).map(AuditLogView::from).collect();
+        Ok(AuditPage { logs, page, pages })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use crate::Result;
+use super::{AuditFilter, AuditPage};
+use super::entity as audit_log;
+
+mod database;
+mod memory;
+pub use database::DatabaseAuditStore;
+pub use memory::MemoryAuditStore;
+
+///
+/// 監査ログのストア
+/// ハンドラはweb::Data<dyn AuditStore>で利用し、永続化層の実装に依存しない
+/// Futureはワーカースレッド内で完結するためSendを要求しない
+///
+#[async_trait(?Send)]
+pub trait AuditStore: Send + Sync {
+    // 監査ログを記録する 番号はストアが採番する
+    async fn insert(&self, model: audit_log::Model) -> Result<()>;
+    // 条件に一致する監査ログを新しい順に1ページ分取得する
+    async fn search(&self, filter: &AuditFilter, page_size: usize) -> Result<AuditPage>;
+}
\ No newline at end of file
@@ -0,0 +1,396 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use log::warn;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::DatabaseConnection;
+use crate::account::AccountService;
+use crate::account::forms::{AccountCreateForm, FormErrors, FormValidator};
+use crate::account::policy::PasswordPolicy;
+use crate::account::store::{AccountStore, DatabaseAccountStore};
+use crate::account::revocation::{RedisRevocationStore, TokenRevocation};
+use crate::audit::{AuditAction, AuditInfo, AuditService};
+use crate::audit::store::{AuditStore, DatabaseAuditStore};
+use crate::i18n::{LocalizedMessage, Translator};
+use crate::jwt::WebJwt;
+use crate::provider::{DatabaseServiceProvider, ProviderError, ServiceProvider};
+use crate::routes::{DEFAULT_ROUTE, ROUTES};
+use crate::seed::SeedData;
+use crate::session_store::purge_redis_sessions;
+use crate::startup::{Startup, StartupError};
+
+///
+/// コマンドライン引数
+/// サブコマンドを指定しない場合はサーバーを起動する
+///
+#[derive(Debug, Parser)]
+#[clap(name = "web_sample", version, about = "商品検索、商品登録のサンプルWebアプリケーション")]
+pub struct Cli {
+    #[clap(long, help = "PostgreSQL、Redisを利用せずインメモリのデータで起動する")]
+    pub demo:    bool ,
+    #[clap(subcommand)]
+    pub command: Option<Command>
+}
+
+///
+/// サブコマンド
+///
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[clap(flatten)]
+    Database(DatabaseCommand) ,
+    #[clap(about = "Redisに格納されたユーザーのセッションを削除し、発行済みのトークンを失効させる")]
+    PurgeSessions {
+        #[clap(help = "ユーザー名")]
+        user_name: String
+    } ,
+    #[clap(about = "設定値、テンプレート、証明書、データベースとRedisへの接続を検証する")]
+    Verify ,
+    #[clap(about = "登録されているルートを表示する")]
+    Routes
+}
+///
+/// DATABASE_URLのデータベースに接続するサブコマンド
+///
+#[derive(Debug, Subcommand)]
+pub enum DatabaseCommand {
+    #[clap(about = "データベースのマイグレーションを実行する")]
+    Migrate {
+        #[clap(subcommand)]
+        action: MigrateAction
+    } ,
+    #[clap(about = "YAMLまたはCSVファイルから商品カテゴリと商品を登録する")]
+    Seed {
+        #[clap(help = "初期データのファイル(.yaml .yml .csv)")]
+        file: PathBuf
+    } ,
+    #[clap(about = "アカウントを登録、パスワードをリセットする")]
+    User {
+        #[clap(subcommand)]
+        action: UserAction
+    } ,
+    #[clap(about = "商品を一覧表示、インポート、エクスポートする")]
+    Products {
+        #[clap(subcommand)]
+        action: ProductAction
+    } ,
+    #[clap(about = "商品カテゴリを一覧表示、インポート、エクスポートする")]
+    Categories {
+        #[clap(subcommand)]
+        action: CategoryAction
+    }
+}
+///
+/// マイグレーションの操作
+///
+#[derive(Debug, Subcommand)]
+pub enum MigrateAction {
+    #[clap(about = "未適用のマイグレーションを適用する")]
+    Up {
+        #[clap(long, help = "適用する件数(省略時は全て)")]
+        steps: Option<u32>
+    } ,
+    #[clap(about = "適用済みのマイグレーションを新しい順に取り消す")]
+    Down {
+        #[clap(long, default_value_t = 1, help = "取り消す件数")]
+        steps: u32
+    } ,
+    #[clap(about = "マイグレーションの適用状況を表示する")]
+    Status
+}
+///
+/// アカウントの操作
+/// パスワードを省略した場合はパスワードポリシーを満たすパスワードを生成して表示する
+///
+#[derive(Debug, Subcommand)]
+pub enum UserAction {
+    #[clap(about = "アカウントを登録する")]
+    Create {
+        #[clap(help = "ユーザー名")]
+        user_name: String ,
+        #[clap(long, help = "メールアドレス")]
+        email: Option<String> ,
+        #[clap(long, help = "パスワード(省略時は生成する)")]
+        password: Option<String> ,
+        #[clap(long, help = "管理者とする")]
+        admin: bool ,
+        #[clap(long, help = "次回ログイン時にパスワードを変更させる")]
+        must_change_password: bool
+    } ,
+    #[clap(about = "パスワードをリセットし、発行済みのトークンを失効させる")]
+    ResetPassword {
+        #[clap(help = "ユーザー名")]
+        user_name: String ,
+        #[clap(long, help = "新しいパスワード(省略時は生成する)")]
+        password: Option<String>
+    }
+}
+///
+/// 商品の操作
+///
+#[derive(Debug, Subcommand)]
+pub enum ProductAction {
+    #[clap(about = "商品を一覧表示する")]
+    List {
+        #[clap(long, default_value = "", help = "商品名に含まれるキーワード")]
+        keyword: String
+    } ,
+    #[clap(about = "YAMLまたはCSVファイルから未登録の商品カテゴリと商品を登録する")]
+    Import {
+        #[clap(help = "商品のファイル(.yaml .yml .csv)")]
+        file: PathBuf
+    } ,
+    #[clap(about = "登録済みの商品カテゴリと商品をYAMLまたはCSVファイルに書き込む")]
+    Export {
+        #[clap(help = "書き込むファイル(.yaml .yml .csv)")]
+        file: PathBuf
+    }
+}
+///
+/// 商品カテゴリの操作
+///
+#[derive(Debug, Subcommand)]
+pub enum CategoryAction {
+    #[clap(about = "商品カテゴリを一覧表示する")]
+    List ,
+    #[clap(about = "YAMLまたはCSVファイルから未登録の商品カテゴリを登録する")]
+    Import {
+        #[clap(help = "商品カテゴリのファイル(.yaml .yml .csv)")]
+        file: PathBuf
+    } ,
+    #[clap(about = "登録済みの商品カテゴリをYAMLまたはCSVファイルに書き込む")]
+    Export {
+        #[clap(help = "書き込むファイル(.yaml .yml .csv)")]
+        file: PathBuf
+    }
+}
+
+impl Command {
+    ///
+    /// サブコマンドを実行する
+    /// サーバーと同じ環境変数(.env)の設定を利用する
+    ///
+    pub async fn run(self) -> Result<(), Vec<StartupError>> {
+        let result = match self {
+            // 起動時と同様に検出した全てのエラーを返す
+            Command::Verify => return Self::verify().await ,
+            Command::Routes => {
+                Self::print_routes();
+                Ok(())
+            },
+            Command::PurgeSessions { user_name } => Self::purge_sessions(&user_name).await ,
+            Command::Database(command) => command.run().await
+        };
+        result.map_err(|error| vec![error])
+    }
+
+    // ユーザーのセッションを削除し、トークンを失効させる
+    async fn purge_sessions(user_name: &str) -> Result<(), StartupError> {
+        let client = Startup::redis().await?;
+        let user_ids = purge_redis_sessions(&client, |user| user.user_name == user_name).await
+            .map_err(|error| StartupError::Redis(error.to_string()))?;
+        if user_ids.is_empty() {
+            println!("ユーザー名:{}のセッションはありません", user_name);
+            return Ok(());
+        }
+        revoke_tokens(client, &user_ids).await?;
+        println!("ユーザー名:{}のセッションを削除し、トークンを失効させました", user_name);
+        Ok(())
+    }
+
+    // サーバーの起動と同じ手順で依存リソースを検証する(マイグレーションの適用とファイルの監視は行わない)
+    async fn verify() -> Result<(), Vec<StartupError>> {
+        let resources = Startup::verify().await?;
+        println!("設定値、テンプレート、静的ファイル、証明書、データベースとRedisへの接続を検証しました");
+        // LISTEN_MODE=plainの場合は証明書を利用しない
+        if resources.tls.is_some() {
+            let not_after = Startup::certificate_not_after().map_err(|error| vec![error])?;
+            println!("証明書の有効期限: {}", not_after);
+        }
+        Ok(())
+    }
+
+    // set_config()で登録するルートを表示する
+    fn print_routes() {
+        for route in ROUTES.iter().chain(std::iter::once(&DEFAULT_ROUTE)) {
+            println!("{:<6} {:<45} {}", route.method, route.path, route.handler);
+        }
+    }
+}
+impl DatabaseCommand {
+    // DATABASE_URLのデータベースに対してサブコマンドを実行する
+    async fn run(self) -> Result<(), StartupError> {
+        let db = Arc::new(Startup::connect().await?);
+        // 商品と商品カテゴリはサーバーと同じAppServiceProviderで参照する
+        let provider = DatabaseServiceProvider::new(db.clone());
+        match self {
+            DatabaseCommand::Migrate { action } => action.run(&db).await ,
+            DatabaseCommand::User { action } => action.run(&DatabaseAccountStore::new(db.clone()), &DatabaseAuditStore::new(db.clone())).await ,
+            DatabaseCommand::Seed { file } |
+            DatabaseCommand::Products { action: ProductAction::Import { file } } => Self::import(&db, SeedData::load(&file)?, &file).await ,
+            DatabaseCommand::Categories { action: CategoryAction::Import { file } } => {
+                // 商品が記述されていても商品カテゴリだけを登録する
+                let mut data = SeedData::load(&file)?;
+                data.products.clear();
+                Self::import(&db, data, &file).await
+            },
+            DatabaseCommand::Products { action: ProductAction::List { keyword } } => Self::list_products(&provider, &keyword).await ,
+            DatabaseCommand::Products { action: ProductAction::Export { file } } => {
+                let data = SeedData::export(&provider).await.map_err(app_error)?;
+                data.save(&file)?;
+                println!("商品カテゴリを{}件、商品を{}件書き込みました", data.categories.len(), data.products.len());
+                Ok(())
+            },
+            DatabaseCommand::Categories { action: CategoryAction::List } => Self::list_categories(&provider).await ,
+            DatabaseCommand::Categories { action: CategoryAction::Export { file } } => {
+                let mut data = SeedData::export(&provider).await.map_err(app_error)?;
+                data.products.clear();
+                data.save(&file)?;
+                println!("商品カテゴリを{}件書き込みました", data.categories.len());
+                Ok(())
+            }
+        }
+    }
+
+    // 未登録の商品カテゴリと商品を登録する
+    async fn import(db: &DatabaseConnection, data: SeedData, file: &Path) -> Result<(), StartupError> {
+        let report = data.apply(db).await
+            .map_err(|error| StartupError::Database(format!("{}: {}", file.display(), error)))?;
+        println!("商品カテゴリを{}件、商品を{}件登録しました", report.categories, report.products);
+        Ok(())
+    }
+
+    // キーワードを含む商品を表示する
+    async fn list_products(provider: &dyn ServiceProvider, keyword: &str) -> Result<(), StartupError> {
+        let products = match provider.search(&SeedData::search_form(keyword).map_err(app_error)?).await {
+            Ok(products) => products ,
+            // 該当する商品がない
+            Err(ProviderError::SearchError(_)) => Vec::new() ,
+            Err(error) => return Err(app_error(error))
+        };
+        for product in products.iter() {
+            let category = product.category.as_ref().map(|category| category.name.as_str()).unwrap_or_default();
+            println!("{:>6} {:<30} {:>8} {}", product.id, product.name, product.price, category);
+        }
+        println!("{}件", products.len());
+        Ok(())
+    }
+
+    // 商品カテゴリを表示する
+    async fn list_categories(provider: &dyn ServiceProvider) -> Result<(), StartupError> {
+        let categories = provider.categories().await.map_err(app_error)?;
+        for category in categories.iter() {
+            println!("{:>6} {}", category.id, category.name);
+        }
+        println!("{}件", categories.len());
+        Ok(())
+    }
+}
+impl MigrateAction {
+    async fn run(self, db: &DatabaseConnection) -> Result<(), StartupError> {
+        let result = match self {
+            MigrateAction::Up { steps } => Migrator::up(db, steps).await ,
+            MigrateAction::Down { steps } => Migrator::down(db, Some(steps)).await ,
+            MigrateAction::Status => Migrator::get_migration_with_status(db).await.map(|migrations| {
+                for migration in migrations.iter() {
+                    println!("{:<8} {}", migration.status().to_string(), migration.name());
+                }
+            })
+        };
+        result.map_err(|error| StartupError::Database(format!("マイグレーション: {}", error)))
+    }
+}
+impl UserAction {
+    // 生成できない場合のメッセージ
+    const GENERATE_FAILED: &'static str = "パスワードポリシーを満たすパスワードを生成できません。--passwordで指定してください";
+
+    async fn run(self, accounts: &dyn AccountStore, audit_log: &dyn AuditStore) -> Result<(), StartupError> {
+        let policy = PasswordPolicy::from_env()?;
+        let translator = Translator::load()?;
+        let message = |message: &LocalizedMessage| translator.localize(Translator::DEFAULT_LOCALE, message);
+        let audit = AuditInfo::command();
+        match self {
+            UserAction::Create { user_name, email, password, admin, must_change_password } => {
+                let (password, generated) = Self::password(&policy, password, &user_name)?;
+                let form = AccountCreateForm {
+                    user_name: user_name.trim().to_string() ,
+                    email ,
+                    password: password.clone() ,
+                    password_confirm: password.clone() ,
+                    admin: admin.then(|| String::from("on")) ,
+                    must_change_password: must_change_password.then(|| String::from("on"))
+                };
+                // 入力値とパスワードポリシーの検証
+                policy.apply(form.validate_form(), &form.password, &form.user_name)
+                    .map_err(|errors| StartupError::Config(Self::form_errors(&errors, message)))?;
+                if AccountService::exists(accounts, &form.user_name).await.map_err(internal_error)? {
+                    return Err(StartupError::Config(message(&LocalizedMessage::new("account-exists").arg("name", form.user_name.as_str()))));
+                }
+                let new_account = AccountService::create(accounts, &form).await.map_err(internal_error)?;
+                AuditService::record(audit_log, audit.entry(AuditAction::AccountCreated)
+                    .target(format!("account:{}", new_account.user_name))).await;
+                println!("{}", message(&LocalizedMessage::new("account-created").arg("name", new_account.user_name.as_str())));
+                if generated {
+                    println!("パスワード: {}", password);
+                }
+                Ok(())
+            },
+            UserAction::ResetPassword { user_name, password } => {
+                let model = AccountService::find_by_name(accounts, user_name.trim()).await.map_err(internal_error)?
+                    .ok_or_else(|| StartupError::Config(format!("ユーザー名:{}のアカウントがありません", user_name.trim())))?;
+                let (password, generated) = Self::password(&policy, password, &model.user_name)?;
+                policy.check(&password, &model.user_name).map_err(|error| StartupError::Config(message(&error)))?;
+                let updated = AccountService::set_password(accounts, model, &password, true).await.map_err(internal_error)?;
+                AuditService::record(audit_log, audit.entry(AuditAction::PasswordReset)
+                    .target(format!("account:{}", updated.user_name))).await;
+                // Redisを利用しない構成ではトークンの失効をサーバーと共有できない
+                match Startup::redis().await {
+                    Ok(client) => revoke_tokens(client, &[updated.id.clone()]).await? ,
+                    Err(error) => warn!("発行済みのトークンを失効できません: {}", error)
+                }
+                println!("{}", message(&LocalizedMessage::new("account-password-reset").arg("name", updated.user_name.as_str())));
+                if generated {
+                    println!("パスワード: {}", password);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // 指定されたパスワード、または生成したパスワードと、生成したかを返す
+    fn password(policy: &PasswordPolicy, password: Option<String>, user_name: &str) -> Result<(String, bool), StartupError> {
+        match password {
+            Some(password) => Ok((password, false)) ,
+            None => policy.generate(user_name).map(|password| (password, true))
+                .ok_or_else(|| StartupError::Config(String::from(Self::GENERATE_FAILED)))
+        }
+    }
+    // 検証エラーを項目名とメッセージの組で連結する
+    fn form_errors(errors: &FormErrors, message: impl Fn(&LocalizedMessage) -> String) -> String {
+        let mut errors = errors.errors.iter()
+            .map(|(field, error)| format!("{}: {}", field, message(error)))
+            .collect::<Vec<String>>();
+        errors.sort();
+        errors.join(", ")
+    }
+}
+
+// Redisのトークン失効ストアでユーザーのトークンを失効させる
+async fn revoke_tokens(client: redis::Client, user_ids: &[String]) -> Result<(), StartupError> {
+    let store = RedisRevocationStore::new(client).await
+        .map_err(|error| StartupError::Redis(error.to_string()))?;
+    let revocation = TokenRevocation::new(Arc::new(store));
+    for user_id in user_ids {
+        WebJwt::revoke(&revocation, user_id).await
+            .map_err(|error| StartupError::Redis(format!("{:?}", error)))?;
+    }
+    Ok(())
+}
+// サービスプロバイダのエラー
+fn app_error(error: ProviderError) -> StartupError {
+    StartupError::Database(error.to_string())
+}
+// アカウント サービスのエラー
+fn internal_error(error: crate::WebAppError) -> StartupError {
+    StartupError::Database(format!("{:?}", error))
+}
\ No newline at end of file
@@ -0,0 +1,117 @@
+use crate::startup::{Startup, StartupError};
+
+///
+/// 実行環境
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppEnvironment {
+    Development ,   // 開発環境
+    Production      // 本番環境
+}
+
+///
+/// 待ち受けの方式
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenMode {
+    Tls ,     // TLS(既定)
+    Plain     // 平文のHTTP(TLSを上流のプロキシで終端する)
+}
+
+///
+/// 待ち受けの設定
+///
+#[derive(Debug, Clone)]
+pub struct ListenSettings {
+    pub mode:          ListenMode ,
+    pub host:          String ,
+    pub port:          u16 ,
+    pub redirect_port: Option<u16>    // HTTPSにリダイレクトするHTTPのポート
+}
+impl ListenSettings {
+    // tls または plain
+    const MODE_KEY: &'static str = "LISTEN_MODE";
+    const HOST_KEY: &'static str = "LISTEN_HOST";
+    const PORT_KEY: &'static str = "LISTEN_PORT";
+    // 未設定または0の場合はHTTPを待ち受けない
+    const REDIRECT_PORT_KEY: &'static str = "HTTP_REDIRECT_PORT";
+    ///
+    /// 環境変数から待ち受けの設定を取得する
+    ///
+    pub fn from_env() -> Result<Self, StartupError> {
+        let mode = match EnvConfig::string(Self::MODE_KEY, "tls").as_str() {
+            "tls" => ListenMode::Tls ,
+            "plain" => ListenMode::Plain ,
+            other => return Err(StartupError::Config(
+                format!("{}の値'{}'はtlsまたはplainを指定してください", Self::MODE_KEY, other)))
+        };
+        let host = EnvConfig::string(Self::HOST_KEY, Startup::HOST);
+        let port = Self::port(Self::PORT_KEY, Startup::PORT as u64)?;
+        let redirect_port = match Self::port(Self::REDIRECT_PORT_KEY, 0)? {
+            0 => None ,
+            redirect_port if mode == ListenMode::Plain => return Err(StartupError::Config(
+                format!("{}={}はLISTEN_MODE=tlsの場合のみ指定できます", Self::REDIRECT_PORT_KEY, redirect_port))) ,
+            redirect_port if redirect_port == port => return Err(StartupError::Config(
+                format!("{}は{}と異なるポートを指定してください", Self::REDIRECT_PORT_KEY, Self::PORT_KEY))) ,
+            redirect_port => Some(redirect_port)
+        };
+        Ok(Self { mode, host, port, redirect_port })
+    }
+    ///
+    /// ブラウザから接続するURL
+    ///
+    pub fn url(&self, path: &str) -> String {
+        let scheme = match self.mode {
+            ListenMode::Tls => "https" ,
+            ListenMode::Plain => "http"
+        };
+        format!("{}://localhost:{}{}", scheme, self.port, path)
+    }
+    fn port(key: &str, default: u64) -> Result<u16, StartupError> {
+        u16::try_from(EnvConfig::u64(key, default)?)
+            .map_err(|_| StartupError::Config(format!("{}は0から65535の範囲で指定してください", key)))
+    }
+}
+
+///
+/// 環境変数から設定値を取得する
+///
+pub struct EnvConfig;
+impl EnvConfig {
+    const APP_ENV_KEY: &'static str = "APP_ENV";
+    ///
+    /// 実行環境を取得する
+    /// 未設定の場合は本番環境とする
+    ///
+    pub fn environment() -> Result<AppEnvironment, StartupError> {
+        match std::env::var(Self::APP_ENV_KEY) {
+            Ok(value) => match value.as_str() {
+                "development" => Ok(AppEnvironment::Development) ,
+                "production" => Ok(AppEnvironment::Production) ,
+                _ => Err(StartupError::Config(
+                    format!("{}の値'{}'はdevelopmentまたはproductionを指定してください", Self::APP_ENV_KEY, value)))
+            },
+            Err(_) => Ok(AppEnvironment::Production)
+        }
+    }
+    // 文字列の設定値を取得する
+    pub fn string(key: &str, default: &str) -> String {
+        std::env::var(key).unwrap_or_else(|_| String::from(default))
+    }
+    // 数値の設定値を取得する
+    pub fn u64(key: &str, default: u64) -> Result<u64, StartupError> {
+        match std::env::var(key) {
+            Ok(value) => value.parse::<u64>().map_err(|_|
+                StartupError::Config(format!("{}の値'{}'は数値ではありません", key, value))) ,
+            Err(_) => Ok(default)
+        }
+    }
+    // 真偽値の設定値を取得する
+    pub fn bool(key: &str, default: bool) -> Result<bool, StartupError> {
+        match std::env::var(key) {
+            Ok(value) => value.parse::<bool>().map_err(|_|
+                StartupError::Config(format!("{}の値'{}'はtrueまたはfalseを指定してください", key, value))) ,
+            Err(_) => Ok(default)
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+use actix_web::cookie::time::OffsetDateTime;
+use rcgen::{Certificate, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, RcgenError, SanType};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use crate::account::Password;
+use crate::startup::StartupError;
+
+///
+/// デモモード
+/// PostgreSQL、Redisを利用せず、インメモリのデータと起動毎に生成する自己署名証明書で実行する
+///
+pub struct Demo;
+impl Demo {
+    // ログインするユーザー名 パスワードは起動毎に生成する
+    pub const USER_NAME: &'static str = "demo";
+    const PASSWORD_LENGTH: usize = 12;
+    // データベース接続(ヘルスチェック、マイグレーション)はインメモリのSQLiteとする
+    const DATABASE_URL: &'static str = "sqlite::memory:";
+    // 接続を破棄するとデータが失われるため、プロセスの実行中は保持する
+    const CONNECTION_LIFETIME: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+    // 自己署名証明書の有効期間(日)
+    const CERTIFICATE_DAYS: u64 = 30;
+
+    ///
+    /// ログインパスワードを生成する
+    ///
+    pub fn password() -> String {
+        Password::generate(Self::PASSWORD_LENGTH)
+    }
+
+    ///
+    /// インメモリのSQLiteに接続する
+    /// 接続毎に別のデータベースになるため、接続は1本に限定する
+    ///
+    pub async fn connect_database() -> Result<DatabaseConnection, StartupError> {
+        let mut options = ConnectOptions::new(String::from(Self::DATABASE_URL));
+        options.max_connections(1)
+            .min_connections(1)
+            .idle_timeout(Self::CONNECTION_LIFETIME)
+            .max_lifetime(Self::CONNECTION_LIFETIME);
+        Database::connect(options).await
+            .map_err(|error| StartupError::Database(format!("{}: {}", Self::DATABASE_URL, error)))
+    }
+
+    ///
+    /// localhostの自己署名証明書と秘密鍵(ECDSA P-256)をPEMで生成する
+    /// TLSのバックエンドに関わらず同じ証明書を生成する
+    ///
+    pub fn certificate() -> Result<(Vec<u8>, Vec<u8>), StartupError> {
+        let build = || -> Result<(Vec<u8>, Vec<u8>), RcgenError> {
+            let certificate = Certificate::from_params(Self::certificate_params())?;
+            Ok((certificate.serialize_pem()?.into_bytes(), certificate.serialize_private_key_pem().into_bytes()))
+        };
+        build().map_err(|error| StartupError::Certificate(format!("自己署名証明書を生成できません: {}", error)))
+    }
+    // localhostと127.0.0.1を対象とするサーバー証明書の設定
+    fn certificate_params() -> CertificateParams {
+        let mut params = CertificateParams::new(vec![String::from("localhost")]);
+        params.subject_alt_names.push(SanType::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        let mut name = DistinguishedName::new();
+        name.push(DnType::CommonName, "localhost");
+        params.distinguished_name = name;
+        let now = OffsetDateTime::now_utc();
+        params.not_before = now;
+        params.not_after = now + Duration::from_secs(60 * 60 * 24 * Self::CERTIFICATE_DAYS);
+        params.serial_number = Some(rand::random());
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params
+    }
+
+    ///
+    /// 接続先とログイン情報を標準出力に表示する
+    ///
+    pub fn print_credentials(url: &str, password: &str) {
+        println!("========================================================");
+        println!(" デモモードで起動します(データは終了時に破棄されます)");
+        println!(" URL       : {}", url);
+        println!(" ユーザー名: {}", Self::USER_NAME);
+        println!(" パスワード: {}", password);
+        if url.starts_with("https://") {
+            println!(" 証明書は自己署名のため、ブラウザの警告を許可してください");
+        }
+        println!("========================================================");
+    }
+}
\ No newline at end of file
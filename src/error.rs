@@ -2,8 +2,9 @@ use std::fmt::{Display, Formatter};
 use actix_web::{HttpResponse, ResponseError};
 use log::{error,info};
 use thiserror::Error;
-use app_commons::error::AppError;
 use crate::handler::view_helper::UiHelper;
+use crate::i18n::LocalizedMessage;
+use crate::provider::ProviderError;
 use crate::Result;
 
 ///
@@ -11,18 +12,20 @@ use crate::Result;
 ///
 #[derive(Debug , Error)]
 pub enum WebAppError {
-    InternalError(String) ,     // 内部エラー
-    AuthorizationError(String)  // 利用認可エラー
+    InternalError(String) ,       // 内部エラー
+    AuthorizationError(String) ,  // 利用認可エラー
+    ForbiddenError(String) ,      // 権限エラー
+    RenderError(String , String , String)  // テンプレートレンダリングエラー(テンプレート名,エラー内容,表示言語)
 }
 impl WebAppError {
-    // AppErrorからメッセージを取得する
-    pub fn error_message(error: AppError) -> Result<String> {
+    // サービスプロバイダのエラーから画面に出力するメッセージを取得する
+    pub fn error_message(error: ProviderError) -> Result<LocalizedMessage> {
         match error {
             // 内部エラーはWebAppErrorに変換して通知する
-            AppError::InternalError(..) => Err(Self::InternalError(error.to_string())),
-            AppError::AuthenticateError(msg) |
-            AppError::RegisterError(msg) |
-            AppError::SearchError(msg) => Ok(msg)
+            ProviderError::InternalError(msg) => Err(Self::InternalError(msg)),
+            ProviderError::AuthenticateError(message) |
+            ProviderError::RegisterError(message) |
+            ProviderError::SearchError(message) => Ok(message)
         }
     }
 }
@@ -42,8 +45,30 @@ impl ResponseError for WebAppError {
             WebAppError::AuthorizationError(msg) =>{
                 info!("{:?}" , msg);
                 "/web_sample/login" // ログイン認証へリダイレクトする
+            },
+            WebAppError::ForbiddenError(msg) => {
+                info!("{:?}" , msg);
+                "/web_sample/menu" // 権限がない場合はメニューへリダイレクトする
+            },
+            WebAppError::RenderError(template , msg , lang) => {
+                error!("template {} : {}" , template , msg);
+                // エラー画面もTeraで生成するため、組み込みのエラー画面を返す
+                return UiHelper::fallback_error_resp(lang);
             }
         };
         UiHelper::found(path , None)
     }
 }
+
+///
+/// エラーの発生原因(source)を連結した文字列を生成する
+///
+pub fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(" caused by: {}", cause));
+        source = cause.source();
+    }
+    message
+}
\ No newline at end of file
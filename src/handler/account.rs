@@ -0,0 +1,214 @@
+use actix_session::Session;
+use actix_web::{HttpResponse, Responder, web};
+use crate::account::{AccountService, Password};
+use crate::account::entity as account;
+use crate::account::forms::{FormValidator, MfaCodeForm, PasswordChangeForm};
+use crate::account::mfa::MfaService;
+use crate::account::policy::PasswordPolicy;
+use crate::account::revocation::TokenRevocation;
+use crate::account::totp::Totp;
+use crate::account::store::AccountStore;
+use crate::audit::{AuditAction, AuditInfo, AuditService};
+use crate::audit::store::AuditStore;
+use crate::handler::view_helper::{SessionHelper, UiHelper, View};
+use crate::i18n::LocalizedMessage;
+use crate::jwt::{SessionUser, WebClaims, WebJwt};
+use crate::{Result, WebAppError};
+
+///
+/// アカウント リクエストハンドラ
+/// ログイン中のユーザー自身の操作
+///
+pub struct AccountHandler;
+impl AccountHandler {
+    // HTML PATH
+    const PASSWORD_PATH: &'static str = "pages/account/password.html";
+    const MFA_PATH: &'static str = "pages/account/mfa.html";
+    const LOGIN_REDIRECT: &'static str = "/web_sample/login";
+    ///
+    /// パスワード変更
+    /// 変更画面要求への応答
+    ///
+    pub async fn password_enter(_claims: WebClaims , view: View) -> Result<impl Responder> {
+        UiHelper::create_resp(&view , &tera::Context::new() , Self::PASSWORD_PATH)
+    }
+    ///
+    /// パスワード変更
+    /// 現在のパスワードを確認して変更し、他の全てのトークンとセッションを失効させる
+    ///
+    pub async fn password_change(
+        claims: WebClaims ,
+        form: web::Form<PasswordChangeForm> ,
+        session: Session ,
+        view: View ,
+        audit: AuditInfo ,
+        jwt: web::Data<WebJwt> ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        policy: web::Data<PasswordPolicy> ,
+        revocation: web::Data<TokenRevocation>) -> Result<impl Responder> {
+        let mut context = tera::Context::new();
+        let account = match AccountService::find(&accounts , claims.user_id()).await? {
+            Some(account) => account ,
+            None => return Self::deleted_resp()
+        };
+        // 入力値とパスワードポリシーの検証
+        let mut errors = policy.apply(form.validate_form(), &form.password, &account.user_name)
+            .err().unwrap_or_default();
+        if !form.current_password.is_empty()
+            && !Password::verify(&form.current_password , &account.password_hash).await? {
+            errors.errors.insert(String::from("current_password") , LocalizedMessage::new("password-current-mismatch"));
+        } else if form.current_password == form.password && !errors.errors.contains_key("password") {
+            errors.errors.insert(String::from("password") , LocalizedMessage::new("password-same-as-current"));
+        }
+        if !errors.errors.is_empty() {
+            context.insert("errors" , &view.errors(&errors.errors));
+            return UiHelper::create_resp(&view , &context , Self::PASSWORD_PATH);
+        }
+        let account = AccountService::set_password(&accounts , account , &form.password , false).await?;
+        // 変更前に発行された全てのトークンとセッションを失効させ、このリクエストには新しいトークンを発行する
+        let revoked_before = WebJwt::revoke(&revocation , &account.id).await?;
+        // 削除されたこのリクエストのセッションは、ログイン中のユーザーを格納し直して新しいセッションとする
+        SessionHelper::insert(&session , &SessionUser::SESSION_KEY , &SessionUser::new(&account.id , &account.user_name))?;
+        AuditService::record(&audit_log, audit.entry(AuditAction::PasswordChanged)
+            .user(&account.id, &account.user_name)).await;
+        context.insert("changed" , &true);
+        let mut response = UiHelper::create_resp(&view , &context , Self::PASSWORD_PATH)?;
+        response.add_cookie(&jwt.cookie(&WebClaims::from_account(&account).issued_after(revoked_before))?)
+            .map_err(|error| WebAppError::InternalError(error.to_string()))?;
+        Ok(response)
+    }
+    ///
+    /// 二要素認証
+    /// 登録状況、未登録の場合は登録用のQRコードを表示する
+    ///
+    pub async fn mfa_enter(
+        claims: WebClaims ,
+        session: Session ,
+        view: View ,
+        accounts: web::Data<dyn AccountStore>) -> Result<impl Responder> {
+        match AccountService::find(&accounts , claims.user_id()).await? {
+            Some(account) => Self::mfa_resp(&view , &session , &accounts , &account , tera::Context::new()).await ,
+            None => Self::deleted_resp()
+        }
+    }
+    ///
+    /// 二要素認証
+    /// 認証アプリのコードを確認して有効にし、リカバリーコードを表示する
+    ///
+    pub async fn mfa_enable(
+        claims: WebClaims ,
+        form: web::Form<MfaCodeForm> ,
+        session: Session ,
+        view: View ,
+        audit: AuditInfo ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore>) -> Result<impl Responder> {
+        let account = match AccountService::find(&accounts , claims.user_id()).await? {
+            Some(account) => account ,
+            None => return Self::deleted_resp()
+        };
+        let mut context = tera::Context::new();
+        let secret = SessionHelper::get(&session , &MfaService::ENROLMENT_KEY)?;
+        let step = secret.as_deref()
+            .and_then(Totp::from_secret)
+            .and_then(|totp| totp.verify(&form.code , chrono::Utc::now().timestamp() , None));
+        let (secret, step) = match (secret, step) {
+            (Some(secret), Some(step)) => (secret, step) ,
+            _ => {
+                context.insert("error" , &view.message("mfa-code-mismatch"));
+                return Self::mfa_resp(&view , &session , &accounts , &account , context).await;
+            }
+        };
+        let recovery_codes = MfaService::enable(&accounts , &account.id , &secret , step).await?;
+        SessionHelper::remove(&session , &MfaService::ENROLMENT_KEY);
+        AuditService::record(&audit_log, audit.entry(AuditAction::MfaEnabled)
+            .user(&account.id, &account.user_name)).await;
+        context.insert("recovery_codes" , &recovery_codes);
+        Self::mfa_resp(&view , &session , &accounts , &account , context).await
+    }
+    ///
+    /// 二要素認証
+    /// コードを確認して無効にする
+    ///
+    pub async fn mfa_disable(
+        claims: WebClaims ,
+        form: web::Form<MfaCodeForm> ,
+        session: Session ,
+        view: View ,
+        audit: AuditInfo ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore>) -> Result<impl Responder> {
+        let account = match AccountService::find(&accounts , claims.user_id()).await? {
+            Some(account) => account ,
+            None => return Self::deleted_resp()
+        };
+        let mut context = tera::Context::new();
+        if let Some(setting) = MfaService::find(&accounts , &account.id).await? {
+            if !MfaService::verify(&accounts , setting , &form.code).await? {
+                context.insert("error" , &view.message("mfa-code-mismatch"));
+                return Self::mfa_resp(&view , &session , &accounts , &account , context).await;
+            }
+            MfaService::disable(&accounts , &account.id).await?;
+            AuditService::record(&audit_log, audit.entry(AuditAction::MfaDisabled)
+                .user(&account.id, &account.user_name)).await;
+        }
+        Self::mfa_resp(&view , &session , &accounts , &account , context).await
+    }
+    ///
+    /// 二要素認証
+    /// コードを確認してリカバリーコードを再生成する
+    ///
+    pub async fn mfa_recovery(
+        claims: WebClaims ,
+        form: web::Form<MfaCodeForm> ,
+        session: Session ,
+        view: View ,
+        accounts: web::Data<dyn AccountStore>) -> Result<impl Responder> {
+        let account = match AccountService::find(&accounts , claims.user_id()).await? {
+            Some(account) => account ,
+            None => return Self::deleted_resp()
+        };
+        let mut context = tera::Context::new();
+        if let Some(setting) = MfaService::find(&accounts , &account.id).await? {
+            // 検証でコードを使用済みにするため、最新の設定を取得し直して再生成する
+            if MfaService::verify(&accounts , setting , &form.code).await? {
+                if let Some(setting) = MfaService::find(&accounts , &account.id).await? {
+                    context.insert("recovery_codes" , &MfaService::regenerate_recovery_codes(&accounts , setting).await?);
+                }
+            } else {
+                context.insert("error" , &view.message("mfa-code-mismatch"));
+            }
+        }
+        Self::mfa_resp(&view , &session , &accounts , &account , context).await
+    }
+
+    // 二要素認証画面を生成する 未登録の場合は登録用の共有鍵をSessionに保持する
+    async fn mfa_resp(view: &View , session: &Session , accounts: &dyn AccountStore ,
+                      account: &account::Model , mut context: tera::Context) -> Result<HttpResponse> {
+        match MfaService::find(accounts , &account.id).await? {
+            Some(setting) => {
+                context.insert("enabled" , &true);
+                context.insert("remaining" , &MfaService::remaining_recovery_codes(&setting));
+            },
+            None => {
+                let secret = match SessionHelper::get(session , &MfaService::ENROLMENT_KEY)? {
+                    Some(secret) => secret ,
+                    None => {
+                        let secret = Totp::generate_secret();
+                        SessionHelper::insert(session , &MfaService::ENROLMENT_KEY , &secret)?;
+                        secret
+                    }
+                };
+                let uri = Totp::provisioning_uri(&secret , MfaService::ISSUER , &account.user_name);
+                context.insert("qr_code" , &Totp::qr_code(&uri).map_err(WebAppError::InternalError)?);
+                context.insert("secret" , &secret);
+            }
+        }
+        UiHelper::create_resp(view , &context , Self::MFA_PATH)
+    }
+    // トークンの発行後にアカウントが削除された場合はログイン画面にリダイレクトする
+    fn deleted_resp() -> Result<HttpResponse> {
+        Ok(UiHelper::found(Self::LOGIN_REDIRECT , Some(WebJwt::removal_cookie())))
+    }
+}
\ No newline at end of file
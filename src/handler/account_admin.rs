@@ -0,0 +1,218 @@
+use actix_session::Session;
+use actix_web::{Responder, web};
+use crate::account::{AccountService, AccountView};
+use crate::account::entity as account;
+use crate::account::forms::{AccountCreateForm, AccountUpdateForm, FormValidator, PasswordForm};
+use crate::account::mfa::MfaService;
+use crate::account::policy::PasswordPolicy;
+use crate::account::revocation::TokenRevocation;
+use crate::account::store::AccountStore;
+use crate::audit::{AuditAction, AuditInfo, AuditService};
+use crate::audit::store::AuditStore;
+use crate::handler::view_helper::{FlashLevel, SessionHelper, UiHelper, View};
+use crate::i18n::LocalizedMessage;
+use crate::jwt::{AdminClaims, WebJwt};
+use crate::Result;
+
+///
+/// アカウント管理 リクエストハンドラ
+///
+pub struct AccountAdminHandler;
+impl AccountAdminHandler {
+    // HTML Redirect PATH
+    const LIST_PATH: &'static str = "pages/admin/accounts/list.html";
+    const ENTER_PATH: &'static str = "pages/admin/accounts/enter.html";
+    const EDIT_PATH: &'static str = "pages/admin/accounts/edit.html";
+    const LIST_REDIRECT: &'static str = "/web_sample/admin/accounts";
+    ///
+    /// アカウント管理
+    /// アカウント一覧
+    ///
+    pub async fn list(
+        _claims: AdminClaims ,
+        view: View ,
+        accounts: web::Data<dyn AccountStore>) -> Result<impl Responder> {
+        let mut context = tera::Context::new();
+        context.insert("accounts" , &AccountService::list(&accounts).await?);
+        UiHelper::create_resp(&view , &context , Self::LIST_PATH)
+    }
+    ///
+    /// アカウント管理
+    /// 登録画面要求への応答
+    ///
+    pub async fn enter(_claims: AdminClaims , view: View) -> Result<impl Responder> {
+        UiHelper::create_resp(&view , &tera::Context::new() , Self::ENTER_PATH)
+    }
+    ///
+    /// アカウント管理
+    /// 入力値検証と登録処理
+    ///
+    pub async fn create(
+        claims: AdminClaims ,
+        session: Session ,
+        form: web::Form<AccountCreateForm> ,
+        view: View ,
+        audit: AuditInfo ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        policy: web::Data<PasswordPolicy>) -> Result<impl Responder> {
+        let mut context = tera::Context::new();
+        context.insert("form" , &form);
+        // 入力値とパスワードポリシーの検証
+        if let Err(error) = policy.apply(form.validate_form(), &form.password, &form.user_name) {
+            context.insert("errors" , &view.errors(&error.errors));
+            return UiHelper::create_resp(&view , &context , Self::ENTER_PATH);
+        }
+        // ユーザー名の重複確認
+        if AccountService::exists(&accounts , form.user_name.trim()).await? {
+            context.insert("exists" , &view.message(LocalizedMessage::new("account-exists").arg("name", form.user_name.trim())));
+            return UiHelper::create_resp(&view , &context , Self::ENTER_PATH);
+        }
+        let new_account = AccountService::create(&accounts , &form).await?;
+        AuditService::record(&audit_log, audit.entry(AuditAction::AccountCreated)
+            .user(claims.0.user_id(), claims.0.user_name())
+            .target(Self::target(&new_account))
+            .after(&AccountView::from(&new_account))).await;
+        SessionHelper::flash(&session , FlashLevel::Success , LocalizedMessage::new("account-created").arg("name", new_account.user_name.as_str()))?;
+        Ok(UiHelper::found(Self::LIST_REDIRECT , None))
+    }
+    ///
+    /// アカウント管理
+    /// 編集画面要求への応答
+    ///
+    pub async fn edit(
+        _claims: AdminClaims ,
+        id: web::Path<String> ,
+        view: View ,
+        accounts: web::Data<dyn AccountStore>) -> Result<impl Responder> {
+        match AccountService::find(&accounts , &id).await? {
+            Some(model) => Self::edit_resp(&view , &model , tera::Context::new()) ,
+            None => Ok(UiHelper::found(Self::LIST_REDIRECT , None))
+        }
+    }
+    ///
+    /// アカウント管理
+    /// メールアドレス、ロール、有効/無効、パスワード変更要求の更新
+    ///
+    pub async fn update(
+        claims: AdminClaims ,
+        session: Session ,
+        id: web::Path<String> ,
+        form: web::Form<AccountUpdateForm> ,
+        view: View ,
+        audit: AuditInfo ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        revocation: web::Data<TokenRevocation>) -> Result<impl Responder> {
+        let model = match AccountService::find(&accounts , &id).await? {
+            Some(model) => model ,
+            None => return Ok(UiHelper::found(Self::LIST_REDIRECT , None))
+        };
+        // 入力値の検証
+        if let Err(error) = form.validate_form() {
+            let mut context = tera::Context::new();
+            context.insert("errors" , &view.errors(&error.errors));
+            return Self::edit_resp(&view , &model , context);
+        }
+        // 自身を無効化、または管理者から外すことはできない
+        if model.id == claims.0.user_id() && (form.enabled.is_none() || form.admin.is_none()) {
+            let mut context = tera::Context::new();
+            context.insert("error" , &view.message("account-self-disable"));
+            return Self::edit_resp(&view , &model , context);
+        }
+        let before = AccountView::from(&model);
+        let before_roles = model.roles.clone();
+        let updated = AccountService::update(&accounts , model , &form).await?;
+        // 無効化、ロールの変更は発行済みのトークンに反映されないため失効させる
+        if !updated.enabled || updated.roles != before_roles {
+            WebJwt::revoke(&revocation , &updated.id).await?;
+        }
+        AuditService::record(&audit_log, audit.entry(AuditAction::AccountUpdated)
+            .user(claims.0.user_id(), claims.0.user_name())
+            .target(Self::target(&updated))
+            .before(&before)
+            .after(&AccountView::from(&updated))).await;
+        SessionHelper::flash(&session , FlashLevel::Success , LocalizedMessage::new("account-updated").arg("name", updated.user_name.as_str()))?;
+        Ok(UiHelper::found(Self::LIST_REDIRECT , None))
+    }
+    ///
+    /// アカウント管理
+    /// パスワードのリセット 次回ログイン時に変更を要求する
+    ///
+    pub async fn reset_password(
+        claims: AdminClaims ,
+        session: Session ,
+        id: web::Path<String> ,
+        form: web::Form<PasswordForm> ,
+        view: View ,
+        audit: AuditInfo ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        policy: web::Data<PasswordPolicy> ,
+        revocation: web::Data<TokenRevocation>) -> Result<impl Responder> {
+        let model = match AccountService::find(&accounts , &id).await? {
+            Some(model) => model ,
+            None => return Ok(UiHelper::found(Self::LIST_REDIRECT , None))
+        };
+        // 入力値とパスワードポリシーの検証
+        if let Err(error) = policy.apply(form.validate_form(), &form.password, &model.user_name) {
+            let mut context = tera::Context::new();
+            context.insert("errors" , &view.errors(&error.errors));
+            return Self::edit_resp(&view , &model , context);
+        }
+        let updated = AccountService::set_password(&accounts , model , &form.password , true).await?;
+        WebJwt::revoke(&revocation , &updated.id).await?;
+        AuditService::record(&audit_log, audit.entry(AuditAction::PasswordReset)
+            .user(claims.0.user_id(), claims.0.user_name())
+            .target(Self::target(&updated))).await;
+        SessionHelper::flash(&session , FlashLevel::Success , LocalizedMessage::new("account-password-reset").arg("name", updated.user_name.as_str()))?;
+        Ok(UiHelper::found(Self::LIST_REDIRECT , None))
+    }
+    ///
+    /// アカウント管理
+    /// アカウントの削除
+    ///
+    pub async fn delete(
+        claims: AdminClaims ,
+        session: Session ,
+        id: web::Path<String> ,
+        view: View ,
+        audit: AuditInfo ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        revocation: web::Data<TokenRevocation>) -> Result<impl Responder> {
+        let model = match AccountService::find(&accounts , &id).await? {
+            Some(model) => model ,
+            None => return Ok(UiHelper::found(Self::LIST_REDIRECT , None))
+        };
+        // 自身のアカウントは削除できない
+        if model.id == claims.0.user_id() {
+            let mut context = tera::Context::new();
+            context.insert("error" , &view.message("account-self-delete"));
+            return Self::edit_resp(&view , &model , context);
+        }
+        let before = AccountView::from(&model);
+        let target = Self::target(&model);
+        let user_id = model.id.clone();
+        let user_name = model.user_name.clone();
+        MfaService::disable(&accounts , &user_id).await?;
+        AccountService::delete(&accounts , model).await?;
+        WebJwt::revoke(&revocation , &user_id).await?;
+        AuditService::record(&audit_log, audit.entry(AuditAction::AccountDeleted)
+            .user(claims.0.user_id(), claims.0.user_name())
+            .target(target)
+            .before(&before)).await;
+        SessionHelper::flash(&session , FlashLevel::Success , LocalizedMessage::new("account-deleted").arg("name", user_name.as_str()))?;
+        Ok(UiHelper::found(Self::LIST_REDIRECT , None))
+    }
+
+    // 編集画面を生成する
+    fn edit_resp(view: &View , model: &account::Model , mut context: tera::Context) -> Result<actix_web::HttpResponse> {
+        context.insert("account" , &AccountView::from(model));
+        UiHelper::create_resp(view , &context , Self::EDIT_PATH)
+    }
+    // 監査ログの対象
+    fn target(model: &account::Model) -> String {
+        format!("account:{}", model.user_name)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,32 @@
+use actix_web::{Responder, web};
+use crate::audit::{AuditAction, AuditFilter, AuditService};
+use crate::audit::store::AuditStore;
+use crate::handler::view_helper::{UiHelper, View};
+use crate::jwt::AdminClaims;
+use crate::Result;
+
+///
+/// 監査ログ閲覧 リクエストハンドラ
+///
+pub struct AuditLogHandler;
+impl AuditLogHandler {
+    // HTML PATH
+    const VIEW_PATH: &'static str = "pages/admin/audit.html";
+    ///
+    /// 監査ログ一覧 GET
+    /// 検索条件で絞り込み、新しい順に表示する
+    ///
+    pub async fn list(
+        _claims: AdminClaims ,
+        filter: web::Query<AuditFilter> ,
+        view: View ,
+        audit_log: web::Data<dyn AuditStore>) -> Result<impl Responder> {
+        let page = AuditService::search(&audit_log, &filter).await?;
+        let actions = AuditAction::ALL.iter().map(AuditAction::as_str).collect::<Vec<&str>>();
+        let mut context = tera::Context::new();
+        context.insert("filter" , &filter.into_inner());
+        context.insert("actions" , &actions);
+        context.insert("page" , &page);
+        UiHelper::create_resp(&view , &context , Self::VIEW_PATH)
+    }
+}
\ No newline at end of file
@@ -1,14 +1,19 @@
-use std::sync::Arc;
-use actix_web::{Responder, web};
-use sea_orm::DatabaseConnection;
-use tera::Tera;
+use actix_session::Session;
+use actix_web::{HttpResponse, Responder, web};
 use app_commons::presentation::forms::LoginForm;
-use app_commons::presentation::jwt::{ClaimsGenerator, JWT_COOKIE_KEY, JwtEncoder};
-use app_commons::presentation::validate::AppValidator;
-use app_commons::application::sea_orm::provider_impl::AppServiceProvider;
-use crate::handler::view_helper::UiHelper;
-use crate::{Result, WebAppError};
-use crate::jwt::{WebClaims, WebJwt};
+use crate::account::{AccountService, Authentication};
+use crate::account::entity as account;
+use crate::account::forms::{FormValidator, MfaCodeForm, PasswordForm, RuleValidator};
+use crate::account::mfa::{MfaPending, MfaService};
+use crate::account::policy::PasswordPolicy;
+use crate::account::revocation::TokenRevocation;
+use crate::account::store::AccountStore;
+use crate::audit::{AuditAction, AuditInfo, AuditService};
+use crate::audit::store::AuditStore;
+use crate::handler::view_helper::{SessionHelper, SessionKey, UiHelper, View};
+use crate::provider::ServiceProvider;
+use crate::Result;
+use crate::jwt::{SessionUser, WebClaims, WebJwt};
 
 ///
 /// 認証 リクエストハンドラ
@@ -17,13 +22,22 @@ pub struct AuthenticateHandler;
 impl AuthenticateHandler {
     // HTML Redirect PATH
     const VIEW_PATH: &'static str = "pages/login/login.html";
+    const PASSWORD_PATH: &'static str = "pages/login/password.html";
     const MENU_REDIRECT: &'static str = "/web_sample/menu";
+    const LOGIN_REDIRECT: &'static str = "/web_sample/login";
+    const PASSWORD_REDIRECT: &'static str = "/web_sample/login/password";
+    const MFA_PATH: &'static str = "pages/login/mfa.html";
+    const MFA_REDIRECT: &'static str = "/web_sample/login/mfa";
+    // パスワード変更待ちのユーザーIdを格納するSessionのキー
+    const PASSWORD_CHANGE_KEY: SessionKey<String> = SessionKey::new("login" , "password_change_user").ttl(600);
+    // アカウントの認証失敗時のメッセージ
+    const FAILED_MESSAGE: &'static str = "login-failed";
     ///
     /// 認証
     /// ログイン画面要求
     ///
-    pub async fn enter(tera: web::Data<Tera>) -> Result<impl Responder>  {
-        Ok(UiHelper::create_resp(&tera , &tera::Context::new() ,Self::VIEW_PATH))
+    pub async fn enter(view: View) -> Result<impl Responder>  {
+        UiHelper::create_resp(&view , &tera::Context::new() ,Self::VIEW_PATH)
     }
     ///
     /// 認証
@@ -31,38 +45,194 @@ impl AuthenticateHandler {
     ///
     pub async fn authenticate(
         form: web::Form<LoginForm> ,
-        tera: web::Data<Tera> ,
-        pool: web::Data<Arc<DatabaseConnection>> ,
-        provider: web::Data<Arc<AppServiceProvider>>) -> Result<impl Responder> {
+        session: Session ,
+        view: View ,
+        audit: AuditInfo ,
+        jwt: web::Data<WebJwt> ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        provider: web::Data<dyn ServiceProvider>) -> Result<impl Responder> {
         // 入力値の検証
-        match form.validate_value() {
+        match form.validate_form() {
             Err(error) => {
                 let mut context = tera::Context::new();
                 // 検証エラーをContextに格納してログイン画面に遷移
-                context.insert("errors", &error.errors);
-                return Ok(UiHelper::create_resp(&tera, &context, Self::VIEW_PATH));
+                context.insert("errors", &view.errors(&error.errors));
+                return UiHelper::create_resp(&view, &context, Self::VIEW_PATH);
             }, Ok(_) => ()
         };
+        // 中断されたログインの状態を破棄する
+        SessionHelper::clear_namespace(&session , Self::PASSWORD_CHANGE_KEY.namespace());
         // 認証
-        match provider.authenticate_service.execute(&pool,&form).await{
-            Ok(user) => {
-                // JWTトークンを生成する
-                let token = WebJwt::encode(&WebClaims::generate(&user));
-                //　生成したトークンをCookieを生成する
-                let cookie = cookie::Cookie::build(JWT_COOKIE_KEY, token)
-                    // 有効期限を5分に設定する
-                    .max_age(cookie::time::Duration::minutes(5))
-                    // HTTPのみ有効にし、SL/TLSに限定する
-                    .http_only(true).secure(true).finish();
-                //　メニューにリダイレクトする
-                Ok(UiHelper::found(Self::MENU_REDIRECT , Some(cookie)))
+        match AccountService::authenticate(&accounts, &provider, &form).await? {
+            Authentication::Succeeded(account) =>
+                Self::complete_login(&session , &audit , &jwt , &accounts , &audit_log , &account).await ,
+            Authentication::PasswordChangeRequired(account) => {
+                // パスワードを変更するまでトークンは発行しない
+                SessionHelper::insert(&session , &Self::PASSWORD_CHANGE_KEY , &account.id)?;
+                Ok(UiHelper::found(Self::PASSWORD_REDIRECT , None))
             },
-            Err(error) => {
+            Authentication::Disabled | Authentication::Failed => {
+                // ログイン失敗を入力されたユーザー名で監査ログに記録する
+                AuditService::record(&audit_log, audit.entry(AuditAction::LoginFailed)
+                    .user_name(&form.name)).await;
                 // エラーメッセージをContextに格納してログイン画面に遷移
                 let mut context = tera::Context::new();
-                context.insert("error" , &WebAppError::error_message(error)?);
-                Ok(UiHelper::create_resp(&tera, &context, Self::VIEW_PATH))
+                context.insert("error" , &view.message(Self::FAILED_MESSAGE));
+                UiHelper::create_resp(&view, &context, Self::VIEW_PATH)
             }
         }
     }
-}
+    ///
+    /// 認証
+    /// 初回ログイン時のパスワード変更画面要求
+    ///
+    pub async fn password_enter(session: Session , view: View) -> Result<impl Responder> {
+        match SessionHelper::get(&session , &Self::PASSWORD_CHANGE_KEY)? {
+            Some(_) => UiHelper::create_resp(&view , &tera::Context::new() , Self::PASSWORD_PATH) ,
+            None => Ok(UiHelper::found(Self::LOGIN_REDIRECT , None))
+        }
+    }
+    ///
+    /// 認証
+    /// 初回ログイン時のパスワード変更
+    ///
+    pub async fn password_change(
+        form: web::Form<PasswordForm> ,
+        session: Session ,
+        view: View ,
+        audit: AuditInfo ,
+        jwt: web::Data<WebJwt> ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        policy: web::Data<PasswordPolicy>) -> Result<impl Responder> {
+        // パスワード変更待ちのアカウントを取得する
+        let account = match SessionHelper::get(&session , &Self::PASSWORD_CHANGE_KEY)? {
+            Some(id) => AccountService::find(&accounts , &id).await? ,
+            None => None
+        };
+        let account = match account {
+            Some(account) if account.enabled => account ,
+            _ => {
+                SessionHelper::remove(&session , &Self::PASSWORD_CHANGE_KEY);
+                return Ok(UiHelper::found(Self::LOGIN_REDIRECT , None));
+            }
+        };
+        // 入力値とパスワードポリシーの検証
+        if let Err(error) = policy.apply(form.validate_form(), &form.password, &account.user_name) {
+            let mut context = tera::Context::new();
+            context.insert("errors" , &view.errors(&error.errors));
+            return UiHelper::create_resp(&view , &context , Self::PASSWORD_PATH);
+        }
+        let account = AccountService::set_password(&accounts , account , &form.password , false).await?;
+        SessionHelper::remove(&session , &Self::PASSWORD_CHANGE_KEY);
+        AuditService::record(&audit_log, audit.entry(AuditAction::PasswordChanged)
+            .user(&account.id, &account.user_name)).await;
+        Self::complete_login(&session , &audit , &jwt , &accounts , &audit_log , &account).await
+    }
+    ///
+    /// 認証
+    /// 二要素認証のコード入力画面要求
+    ///
+    pub async fn mfa_enter(session: Session , view: View) -> Result<impl Responder> {
+        match Self::mfa_pending(&session)? {
+            Some(_) => UiHelper::create_resp(&view , &tera::Context::new() , Self::MFA_PATH) ,
+            None => Ok(UiHelper::found(Self::LOGIN_REDIRECT , None))
+        }
+    }
+    ///
+    /// 認証
+    /// 二要素認証のコードを検証し、トークンを発行する
+    ///
+    pub async fn mfa_verify(
+        form: web::Form<MfaCodeForm> ,
+        session: Session ,
+        view: View ,
+        audit: AuditInfo ,
+        jwt: web::Data<WebJwt> ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore>) -> Result<impl Responder> {
+        let mut pending = match Self::mfa_pending(&session)? {
+            Some(pending) => pending ,
+            None => return Ok(UiHelper::found(Self::LOGIN_REDIRECT , None))
+        };
+        let mut context = tera::Context::new();
+        // 入力値の検証
+        if let Err(error) = form.validate_form() {
+            context.insert("errors" , &view.errors(&error.errors));
+            return UiHelper::create_resp(&view , &context , Self::MFA_PATH);
+        }
+        let account = AccountService::find(&accounts , &pending.account_id).await?.filter(|account| account.enabled);
+        let setting = MfaService::find(&accounts , &pending.account_id).await?;
+        let (account, setting) = match (account, setting) {
+            (Some(account), Some(setting)) => (account, setting) ,
+            _ => {
+                SessionHelper::remove(&session , &MfaPending::SESSION_KEY);
+                return Ok(UiHelper::found(Self::LOGIN_REDIRECT , None));
+            }
+        };
+        if MfaService::verify(&accounts , setting , &form.code).await? {
+            SessionHelper::remove(&session , &MfaPending::SESSION_KEY);
+            return Self::issue_token(&session , &audit , &jwt , &audit_log , &account).await;
+        }
+        AuditService::record(&audit_log, audit.entry(AuditAction::LoginFailed)
+            .user(&account.id, &account.user_name)
+            .target("mfa")).await;
+        // 失敗回数が上限に達した場合はパスワード認証からやり直させる
+        pending.failures += 1;
+        if pending.failures >= MfaPending::MAX_FAILURES {
+            SessionHelper::remove(&session , &MfaPending::SESSION_KEY);
+            context.insert("error" , &view.message("login-mfa-locked"));
+            return UiHelper::create_resp(&view , &context , Self::VIEW_PATH);
+        }
+        SessionHelper::insert(&session , &MfaPending::SESSION_KEY , &pending)?;
+        context.insert("error" , &view.message("mfa-code-mismatch"));
+        UiHelper::create_resp(&view , &context , Self::MFA_PATH)
+    }
+    ///
+    /// ログアウト
+    /// トークンを失効させてセッションを破棄し、トークンを格納したCookieを削除する
+    /// トークンは認証と同じく失効を含めて検証し、有効な場合のみ失効させる
+    ///
+    pub async fn logout(
+        claims: Option<WebClaims> ,
+        session: Session ,
+        revocation: web::Data<TokenRevocation>) -> Result<impl Responder> {
+        if let Some(claims) = claims {
+            WebJwt::revoke_token(&revocation , &claims).await?;
+        }
+        session.purge();
+        Ok(UiHelper::found(Self::LOGIN_REDIRECT , Some(WebJwt::removal_cookie())))
+    }
+
+    // パスワード認証後の処理 二要素認証が有効な場合はコード入力へ、それ以外はトークンを発行する
+    async fn complete_login(session: &Session , audit: &AuditInfo , jwt: &WebJwt , accounts: &dyn AccountStore ,
+                            audit_log: &dyn AuditStore , account: &account::Model) -> Result<HttpResponse> {
+        if MfaService::find(accounts , &account.id).await?.is_some() {
+            SessionHelper::insert(session , &MfaPending::SESSION_KEY , &MfaPending::new(&account.id))?;
+            return Ok(UiHelper::found(Self::MFA_REDIRECT , None));
+        }
+        Self::issue_token(session , audit , jwt , audit_log , account).await
+    }
+    // ログイン成功を記録し、トークンを発行してメニューにリダイレクトする
+    async fn issue_token(session: &Session , audit: &AuditInfo , jwt: &WebJwt , audit_log: &dyn AuditStore ,
+                         account: &account::Model) -> Result<HttpResponse> {
+        AuditService::record(audit_log, audit.entry(AuditAction::LoginSucceeded)
+            .user(&account.id, &account.user_name)).await;
+        // 管理コマンドでユーザーのセッションを削除できるようにログイン中のユーザーを格納する
+        SessionHelper::insert(session , &SessionUser::SESSION_KEY , &SessionUser::new(&account.id , &account.user_name))?;
+        let cookie = jwt.cookie(&WebClaims::from_account(account))?;
+        Ok(UiHelper::found(Self::MENU_REDIRECT , Some(cookie)))
+    }
+    // 有効期限内の二要素認証待ちの状態を取得する
+    fn mfa_pending(session: &Session) -> Result<Option<MfaPending>> {
+        match SessionHelper::get(session , &MfaPending::SESSION_KEY)? {
+            Some(pending) if !pending.is_expired() => Ok(Some(pending)) ,
+            Some(_) => {
+                SessionHelper::remove(session , &MfaPending::SESSION_KEY);
+                Ok(None)
+            },
+            None => Ok(None)
+        }
+    }
+}
\ No newline at end of file
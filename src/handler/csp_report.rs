@@ -0,0 +1,34 @@
+use actix_web::{HttpResponse, web};
+use log::warn;
+
+///
+/// CSP違反レポート リクエストハンドラ
+///
+pub struct CspReportHandler;
+impl CspReportHandler {
+    // ログに出力するレポートの最大長
+    const MAX_LENGTH: usize = 4096;
+    ///
+    /// ブラウザから送信された違反レポートをログに出力する
+    /// 送信元は認証されないため、制御文字をエスケープしてログの改ざんを防ぐ
+    ///
+    pub async fn report(body: web::Bytes) -> HttpResponse {
+        let report = String::from_utf8_lossy(&body[..body.len().min(Self::MAX_LENGTH)]);
+        warn!("csp violation: {}", Self::escape_control(&report));
+        HttpResponse::NoContent().finish()
+    }
+    ///
+    /// 改行などの制御文字をエスケープする
+    ///
+    pub fn escape_control(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if c.is_control() {
+                escaped.extend(c.escape_default());
+            } else {
+                escaped.push(c);
+            }
+        }
+        escaped
+    }
+}
\ No newline at end of file
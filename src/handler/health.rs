@@ -0,0 +1,118 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use actix_web::{HttpResponse, Responder, web};
+use actix_web::rt::time::timeout;
+use log::warn;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, Statement};
+use serde::Serialize;
+use crate::session_store::AppSessionStore;
+
+///
+/// 依存サービスの状態
+/// エラーの詳細はログにのみ出力し、レスポンスには含めない
+///
+#[derive(Debug, Serialize)]
+pub struct DependencyStatus {
+    status:     &'static str ,   // UP、DOWNまたはTIMEOUT
+    elapsed_ms: u128             // 応答時間(ミリ秒)
+}
+impl DependencyStatus {
+    fn is_up(&self) -> bool {
+        self.status == HealthHandler::UP
+    }
+}
+
+///
+/// Readinessチェック結果
+///
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    status:   &'static str ,
+    database: DependencyStatus ,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redis:    Option<DependencyStatus> ,   // Redisを利用しない構成では出力しない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session:  Option<DependencyStatus>     // セッションをサーバー外に格納しない構成では出力しない
+}
+
+///
+/// ヘルスチェック リクエストハンドラ
+///
+pub struct HealthHandler;
+impl HealthHandler {
+    const UP: &'static str = "UP";
+    const DOWN: &'static str = "DOWN";
+    const TIMEOUT_STATUS: &'static str = "TIMEOUT";
+    // 依存サービス毎の応答待ち時間
+    const TIMEOUT: Duration = Duration::from_secs(2);
+    ///
+    /// Liveness
+    /// プロセスが応答可能であることのみを返す
+    ///
+    pub async fn live() -> impl Responder {
+        HttpResponse::Ok().json(serde_json::json!({ "status": Self::UP }))
+    }
+    ///
+    /// Readiness
+    /// PostgreSQL、Redis、セッションストア(クラスタ、Sentinelを含む)への疎通を確認する
+    ///
+    pub async fn ready(
+        pool: web::Data<Arc<DatabaseConnection>> ,
+        redis: web::Data<Option<redis::Client>> ,
+        sessions: web::Data<AppSessionStore>) -> impl Responder {
+        let database = Self::check("database", Self::ping_database(&pool)).await;
+        let redis = match redis.as_ref() {
+            Some(client) => Some(Self::check("redis", Self::ping_redis(client)).await) ,
+            None => None
+        };
+        let session = match sessions.is_remote() {
+            true => Some(Self::check("session", sessions.ping()).await) ,
+            false => None
+        };
+        let up = database.is_up()
+            && redis.as_ref().map_or(true, DependencyStatus::is_up)
+            && session.as_ref().map_or(true, DependencyStatus::is_up);
+        let report = ReadinessReport {
+            status: if up { Self::UP } else { Self::DOWN } ,
+            database ,
+            redis ,
+            session
+        };
+        if up {
+            HttpResponse::Ok().json(report)
+        } else {
+            // 依存サービスが利用できない場合は503を返す
+            HttpResponse::ServiceUnavailable().json(report)
+        }
+    }
+
+    // タイムアウト付きで疎通確認を実行し、結果を状態に変換する 異常時の詳細はログに出力する
+    async fn check<F, E>(name: &str, ping: F) -> DependencyStatus
+        where F: Future<Output = std::result::Result<(), E>> , E: Display {
+        let start = Instant::now();
+        let status = match timeout(Self::TIMEOUT, ping).await {
+            Ok(Ok(())) => Self::UP ,
+            Ok(Err(error)) => {
+                warn!("readiness check {} failed: {}" , name , error);
+                Self::DOWN
+            },
+            Err(_) => {
+                warn!("readiness check {} timed out after {}ms" , name , Self::TIMEOUT.as_millis());
+                Self::TIMEOUT_STATUS
+            }
+        };
+        DependencyStatus { status , elapsed_ms: start.elapsed().as_millis() }
+    }
+    // データベースへSELECT 1を発行する
+    async fn ping_database(pool: &DatabaseConnection) -> std::result::Result<(), DbErr> {
+        let statement = Statement::from_string(pool.get_database_backend(), String::from("SELECT 1"));
+        pool.execute(statement).await.map(|_| ())
+    }
+    // RedisへPINGを発行する
+    async fn ping_redis(client: &redis::Client) -> redis::RedisResult<()> {
+        let mut connection = client.get_async_connection().await?;
+        redis::cmd("PING").query_async::<_, String>(&mut connection).await.map(|_| ())
+    }
+}
@@ -0,0 +1,39 @@
+use actix_session::Session;
+use actix_web::{HttpRequest, Responder, web};
+use actix_web::http::header;
+use crate::handler::view_helper::{SessionHelper, UiHelper};
+use crate::i18n::{Locale, Translator};
+use crate::Result;
+
+///
+/// 表示言語 リクエストハンドラ
+///
+pub struct LocaleHandler;
+impl LocaleHandler {
+    const APP_PATH: &'static str = "/web_sample/";
+    const MENU_REDIRECT: &'static str = "/web_sample/menu";
+    ///
+    /// 表示言語の切り替え
+    /// 選択された言語をSessionに格納し、元の画面へリダイレクトする
+    ///
+    pub async fn change(
+        lang: web::Path<String> ,
+        request: HttpRequest ,
+        session: Session ,
+        translator: web::Data<Translator>) -> Result<impl Responder> {
+        if translator.is_supported(&lang) {
+            SessionHelper::insert(&session , &Locale::SESSION_KEY , &lang.into_inner())?;
+        }
+        Ok(UiHelper::found(&Self::back_path(&request) , None))
+    }
+
+    // Refererがアプリケーション内の画面であればそのパスを、それ以外はメニューを返す
+    fn back_path(request: &HttpRequest) -> String {
+        request.headers().get(header::REFERER)
+            .and_then(|referer| referer.to_str().ok())
+            .and_then(|referer| referer.split_once("://").map(|(_, rest)| rest))
+            .and_then(|rest| rest.find('/').map(|index| &rest[index..]))
+            .filter(|path| path.starts_with(Self::APP_PATH))
+            .map_or_else(|| String::from(Self::MENU_REDIRECT), String::from)
+    }
+}
\ No newline at end of file
@@ -3,3 +3,11 @@ pub mod view_commons;
 pub mod product_search;
 pub mod product_register;
 pub mod authenticate;
+pub mod health;
+pub mod static_files;
+pub mod csp_report;
+pub mod audit_log;
+pub mod account_admin;
+pub mod account;
+pub mod password_reset;
+pub mod locale;
\ No newline at end of file
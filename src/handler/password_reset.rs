@@ -0,0 +1,135 @@
+use actix_web::{Responder, rt, web};
+use log::error;
+use crate::account::AccountService;
+use crate::account::forms::{FormValidator, PasswordForm, PasswordResetRequestForm};
+use crate::account::policy::PasswordPolicy;
+use crate::account::reset::{PasswordResetConfig, PasswordResetService};
+use crate::account::revocation::TokenRevocation;
+use crate::account::store::AccountStore;
+use crate::audit::{AuditAction, AuditInfo, AuditService};
+use crate::audit::store::AuditStore;
+use crate::handler::view_helper::{UiHelper, View};
+use crate::i18n::Translator;
+use crate::jwt::WebJwt;
+use crate::mail::Mailer;
+use crate::Result;
+
+///
+/// パスワード再設定 リクエストハンドラ
+/// メールで送信した一度限りのリンクからパスワードを再設定する
+///
+pub struct PasswordResetHandler;
+impl PasswordResetHandler {
+    // HTML PATH
+    const FORGOT_PATH: &'static str = "pages/password/forgot.html";
+    const RESET_PATH: &'static str = "pages/password/reset.html";
+    ///
+    /// パスワード再設定
+    /// 再設定要求画面への応答
+    ///
+    pub async fn forgot_enter(view: View) -> Result<impl Responder> {
+        UiHelper::create_resp(&view , &tera::Context::new() , Self::FORGOT_PATH)
+    }
+    ///
+    /// パスワード再設定
+    /// 再設定用のリンクをメールで送信する
+    /// アカウントの有無に関わらず同じ応答を返し、応答時間からも判別できないよう送信処理の完了を待たない
+    ///
+    pub async fn forgot(
+        form: web::Form<PasswordResetRequestForm> ,
+        view: View ,
+        audit: AuditInfo ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        mailer: web::Data<dyn Mailer> ,
+        translator: web::Data<Translator> ,
+        config: web::Data<PasswordResetConfig>) -> Result<impl Responder> {
+        let mut context = tera::Context::new();
+        // 入力値の検証
+        if let Err(error) = form.validate_form() {
+            context.insert("form" , &form);
+            context.insert("errors" , &view.errors(&error.errors));
+            return UiHelper::create_resp(&view , &context , Self::FORGOT_PATH);
+        }
+        let email = form.email.trim().to_string();
+        let lang = view.locale().as_str().to_string();
+        rt::spawn(async move {
+            // 有効なアカウントに登録されたメールアドレスの場合はトークンを発行し、再設定用のリンクを送信する
+            let result: Result<()> = async {
+                let account = match AccountService::find_by_email(&accounts , &email).await?.filter(|account| account.enabled) {
+                    Some(account) => account ,
+                    None => return Ok(())
+                };
+                let token = PasswordResetService::issue(&accounts , &account , &config).await?;
+                // 失敗した場合もアカウントの存在を明かさないためログ出力のみとする
+                if let Err(error) = mailer.send(&config.mail(&translator , &lang , &email , &account , &token)).await {
+                    error!("password reset mail for account {} failed: {}", account.id, error);
+                }
+                AuditService::record(&audit_log, audit.entry(AuditAction::PasswordResetRequested)
+                    .user(&account.id, &account.user_name)).await;
+                Ok(())
+            }.await;
+            if let Err(error) = result {
+                error!("password reset request failed: {:?}", error);
+            }
+        });
+        context.insert("sent" , &true);
+        UiHelper::create_resp(&view , &context , Self::FORGOT_PATH)
+    }
+    ///
+    /// パスワード再設定
+    /// 再設定画面への応答
+    ///
+    pub async fn reset_enter(
+        token: web::Path<String> ,
+        view: View ,
+        accounts: web::Data<dyn AccountStore>) -> Result<impl Responder> {
+        let mut context = tera::Context::new();
+        if PasswordResetService::find_valid(&accounts , &token).await?.is_some() {
+            context.insert("token" , &token.into_inner());
+        } else {
+            context.insert("invalid" , &true);
+        }
+        UiHelper::create_resp(&view , &context , Self::RESET_PATH)
+    }
+    ///
+    /// パスワード再設定
+    /// トークンを使用済みにしてパスワードを設定し、発行済みのトークンを失効させる
+    ///
+    pub async fn reset(
+        token: web::Path<String> ,
+        form: web::Form<PasswordForm> ,
+        view: View ,
+        audit: AuditInfo ,
+        accounts: web::Data<dyn AccountStore> ,
+        audit_log: web::Data<dyn AuditStore> ,
+        policy: web::Data<PasswordPolicy> ,
+        revocation: web::Data<TokenRevocation>) -> Result<impl Responder> {
+        let mut context = tera::Context::new();
+        let (reset_token, account) = match PasswordResetService::find_valid(&accounts , &token).await? {
+            Some(found) => found ,
+            None => {
+                context.insert("invalid" , &true);
+                return UiHelper::create_resp(&view , &context , Self::RESET_PATH);
+            }
+        };
+        // 入力値とパスワードポリシーの検証
+        if let Err(error) = policy.apply(form.validate_form(), &form.password, &account.user_name) {
+            context.insert("token" , &token.into_inner());
+            context.insert("errors" , &view.errors(&error.errors));
+            return UiHelper::create_resp(&view , &context , Self::RESET_PATH);
+        }
+        // 同じトークンによる同時の再設定は一方のみ受け付ける
+        if !PasswordResetService::consume(&accounts , &reset_token).await? {
+            context.insert("invalid" , &true);
+            return UiHelper::create_resp(&view , &context , Self::RESET_PATH);
+        }
+        let account = AccountService::set_password(&accounts , account , &form.password , false).await?;
+        WebJwt::revoke(&revocation , &account.id).await?;
+        AuditService::record(&audit_log, audit.entry(AuditAction::PasswordChanged)
+            .user(&account.id, &account.user_name)
+            .target("password_reset")).await;
+        context.insert("completed" , &true);
+        UiHelper::create_resp(&view , &context , Self::RESET_PATH)
+    }
+}
\ No newline at end of file
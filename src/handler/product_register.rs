@@ -1,14 +1,14 @@
-use std::sync::Arc;
 use actix_session::Session;
 use actix_web::{Responder, web};
-use sea_orm::DatabaseConnection;
 use app_commons::application::transfers::{ProductDto , CategoryDto};
 use app_commons::presentation::forms::ProductRegisterForm;
-use app_commons::presentation::validate::AppValidator;
-use app_commons::application::sea_orm::provider_impl::AppServiceProvider;
 use crate::{Result, WebAppError};
+use crate::account::forms::RuleValidator;
+use crate::audit::{AuditAction, AuditInfo, AuditService};
+use crate::audit::store::AuditStore;
 use crate::jwt::WebClaims;
-use crate::handler::view_helper::{SessionHelper, UiHelper};
+use crate::handler::view_helper::{FlashLevel, SessionHelper, SessionKey, UiHelper, View};
+use crate::provider::ServiceProvider;
 
 ///
 /// 商品登録 リクエストハンドラ
@@ -20,6 +20,10 @@ impl ProductRegisterHandler {
     const FINISH_PATH: &'static str = "pages/register/finish.html";
     const ENTER_REDIRECT: &'static  str = "/web_sample/register/product";
     const FINISH_REDIRECT: &'static str = "/web_sample/register/product/finish";
+    // Session Key(結合テストでセッションを準備するため公開する)
+    pub const CATEGORIES_KEY: SessionKey<Vec<CategoryDto>> = SessionKey::new("register" , "categories").ttl(1800);
+    // 登録完了のフラッシュメッセージ(登録した商品を持つ)
+    pub const COMPLETED_MESSAGE: &'static str = "register-completed";
     ///
     /// 商品登録　
     /// 商品入力画面要求への応答
@@ -27,28 +31,27 @@ impl ProductRegisterHandler {
     pub async fn enter(
         _claims: WebClaims ,
         session: Session ,
-        tera: web::Data<tera::Tera> ,
-        pool: web::Data<Arc<DatabaseConnection>> ,
-        provider: web::Data<Arc<AppServiceProvider>>) -> Result<impl Responder> {
+        view: View ,
+        provider: web::Data<dyn ServiceProvider>) -> Result<impl Responder> {
         // セッションから商品カテゴリを取得する
-        let session_categories = SessionHelper::get::<Vec<CategoryDto>>(&session,"categories")?;
+        let session_categories = SessionHelper::get(&session , &Self::CATEGORIES_KEY)?;
         let categories = match session_categories {
             Some(categories) => categories ,
             None => {
                 // 永続化層から商品カテゴリを取得する
-                let categories = match provider.register_service.categories(&pool).await {
+                let categories = match provider.categories().await {
                     Ok(categories) => categories ,
                     Err(error) => return Err(WebAppError::InternalError(error.to_string()))
                 };
                 // セッションに商品カテゴリを登録
-                SessionHelper::insert::<Vec<CategoryDto>>(&session , "categories" , &categories)?;
+                SessionHelper::insert(&session , &Self::CATEGORIES_KEY , &categories)?;
                 categories
             }
         };
         // TeraのContextに商品カテゴリを登録する
         let mut context = tera::Context::new();
         context.insert("categories" , &categories);
-        Ok(UiHelper::create_resp(&tera , &context ,Self::ENTER_PATH))
+        UiHelper::create_resp(&view , &context ,Self::ENTER_PATH)
     }
 
     ///
@@ -56,35 +59,41 @@ impl ProductRegisterHandler {
     /// 入力値検証と登録処理
     ///
     pub async fn complete(
-        _claims: WebClaims ,
+        claims: WebClaims ,
         session: Session ,
         form: web::Form<ProductRegisterForm> ,
-        tera: web::Data<tera::Tera>  ,
-        pool: web::Data<Arc<DatabaseConnection>> ,
-        provider: web::Data<Arc<AppServiceProvider>>) -> Result<impl Responder> {
+        view: View  ,
+        audit: AuditInfo ,
+        audit_log: web::Data<dyn AuditStore> ,
+        provider: web::Data<dyn ServiceProvider>) -> Result<impl Responder> {
         // セッションからカテゴリを取得
-        let categories = match SessionHelper::get::<Vec<CategoryDto>>(&session,"categories")?{
+        let categories = match SessionHelper::get(&session , &Self::CATEGORIES_KEY)?{
             Some(categories) => categories ,
             None => //　入力画面にリダイレクトする
                 return Ok(UiHelper::found(Self::ENTER_REDIRECT, None))
         };
         // 入力値の検証
-        match form.validate_value() {
+        match form.validate_form() {
             Err(error) => {
                 let mut context = tera::Context::new();
                 // 検証エラー、Form、カテゴリをContextに格納
                 context.insert("form" , &form);
                 context.insert("categories" , &categories);
-                context.insert("errors", &error.errors);
+                context.insert("errors", &view.errors(&error.errors));
                 //　入力画面に遷移する
-                return Ok(UiHelper::create_resp(&tera, &context, Self::ENTER_PATH));
+                return UiHelper::create_resp(&view, &context, Self::ENTER_PATH);
             }, Ok(_) => ()
         };
         // 入力された商品を永続化する
-        match provider.register_service.execute(&pool , &form).await{
+        match provider.register(&form).await{
             Ok(new_product) => {
-                // 登録結果をSessionに格納する
-                SessionHelper::insert::<ProductDto>(&session , "new_product" , &new_product)?;
+                // 登録した商品を監査ログに記録する
+                AuditService::record(&audit_log, audit.entry(AuditAction::ProductRegistered)
+                    .user(claims.user_id(), claims.user_name())
+                    .target(format!("product:{}", new_product.id))
+                    .after(&new_product)).await;
+                // 登録結果を完了画面で一度だけ表示するフラッシュメッセージに格納する
+                SessionHelper::flash_with(&session , FlashLevel::Success , Self::COMPLETED_MESSAGE , &new_product)?;
                 // 登録結果へリダイレクト
                 Ok(UiHelper::found(Self::FINISH_REDIRECT , None))
             },
@@ -92,9 +101,9 @@ impl ProductRegisterHandler {
                 //　登録済みの場合、入力画面に戻る
                 let mut context = tera::Context::new();
                 context.insert("categories" , &categories);
-                context.insert("exists" , &WebAppError::error_message(error)?);
+                context.insert("exists" , &view.message(WebAppError::error_message(error)?));
                 context.insert("form" , &form);
-                Ok(UiHelper::create_resp(&tera, &context , Self::ENTER_PATH))
+                UiHelper::create_resp(&view, &context , Self::ENTER_PATH)
             }
         }
     }
@@ -103,17 +112,15 @@ impl ProductRegisterHandler {
     ///
     pub async fn finish(
         session: Session ,
-        tera: web::Data<tera::Tera>) -> Result<impl Responder> {
-        //  セッションから登録された商品情報を取得する
-        match SessionHelper::get::<ProductDto>(&session, "new_product")?{
+        view: View) -> Result<impl Responder> {
+        // 登録完了のフラッシュメッセージから登録した商品を取得する(メッセージは完了画面の出力時に削除される)
+        match SessionHelper::flash_payload::<ProductDto>(&session , Self::COMPLETED_MESSAGE)?{
             Some(new_product) => {
-                // 商品情報をセッションから削除する
-                SessionHelper::remove(&session , "new_product");
                 // TeraのContextに登録する
                 let mut context = tera::Context::new();
                 context.insert("new_product" , &new_product);
                 // 完了画面を返す
-                Ok(UiHelper::create_resp(&tera , &context,Self::FINISH_PATH))
+                UiHelper::create_resp(&view , &context,Self::FINISH_PATH)
             },
             None =>
                 // 入力画面にリダイレクトする
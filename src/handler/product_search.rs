@@ -1,11 +1,8 @@
-use std::sync::Arc;
 use actix_web::{Responder, web};
-use sea_orm::DatabaseConnection;
-use tera::Tera;
 use app_commons::presentation::forms::ProductSearchForm;
-use app_commons::presentation::validate::AppValidator;
-use app_commons::application::sea_orm::provider_impl::AppServiceProvider;
-use crate::handler::view_helper::UiHelper;
+use crate::account::forms::RuleValidator;
+use crate::handler::view_helper::{UiHelper, View};
+use crate::provider::ServiceProvider;
 use crate::{Result, WebAppError};
 use crate::jwt::WebClaims;
 ///
@@ -18,8 +15,8 @@ impl ProductSearchHandler {
     ///
     /// キーワード入力画面要求 GET
     ///
-    pub async fn enter(_claims: WebClaims , tera: web::Data<Tera>) -> Result<impl Responder> {
-        Ok(UiHelper::create_resp(&tera, &tera::Context::new(), Self::VIEW_PATH))
+    pub async fn enter(_claims: WebClaims , view: View) -> Result<impl Responder> {
+        UiHelper::create_resp(&view, &tera::Context::new(), Self::VIEW_PATH)
     }
     ///
     /// 検索要求　POST
@@ -27,27 +24,26 @@ impl ProductSearchHandler {
     pub async fn result(
         _claims: WebClaims ,
         form: web::Form<ProductSearchForm>,
-        tera: web::Data<Tera>,
-        pool: web::Data<Arc<DatabaseConnection>>,
-        provider: web::Data<Arc<AppServiceProvider>>) -> Result<impl Responder> {
+        view: View,
+        provider: web::Data<dyn ServiceProvider>) -> Result<impl Responder> {
 
         // 入力値の検証
-        match form.validate_value() {
+        match form.validate_form() {
             Err(error) => {
                 let mut context = tera::Context::new();
-                context.insert("errors", &error.errors);
-                return Ok(UiHelper::create_resp(&tera, &context, Self::VIEW_PATH));
+                context.insert("errors", &view.errors(&error.errors));
+                return UiHelper::create_resp(&view, &context, Self::VIEW_PATH);
             }, Ok(_) => ()
         };
         // 商品キーワード検索
         let mut context = tera::Context::new();
-        match provider.search_service.search(&pool , &form).await{
+        match provider.search(&form).await{
             // 結果をContextに格納
             Ok(results) => context.insert("results" , &results),
-            // エラーメッセージを取得してContextに格納する　InternalErrorはエラーレスポンス
+            // エラーメッセージを翻訳してContextに格納する　InternalErrorはエラーレスポンス
             Err(error) =>
-                context.insert("not found" , &WebAppError::error_message(error)?)
+                context.insert("notfound" , &view.message(WebAppError::error_message(error)?))
         };
-        Ok(UiHelper::create_resp(&tera, &context , Self::VIEW_PATH))
+        UiHelper::create_resp(&view, &context , Self::VIEW_PATH)
     }
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::http::header;
+use crate::assets::StaticAssets;
+
+///
+/// 静的ファイル リクエストハンドラ
+///
+pub struct StaticFileHandler;
+impl StaticFileHandler {
+    // ハッシュ付きのURLは内容が変わらないため1年間キャッシュさせる
+    const IMMUTABLE: &'static str = "public, max-age=31536000, immutable";
+    // ハッシュなしのURLは毎回検証させる
+    const REVALIDATE: &'static str = "public, no-cache";
+    ///
+    /// 静的ファイルの配信
+    /// Accept-Encodingに応じて圧縮済みの内容を返す
+    /// 埋め込まれていないファイルは、開発環境ではディスクから読み込んで非圧縮で返す
+    ///
+    pub async fn serve(
+        request: HttpRequest ,
+        path: web::Path<String> ,
+        assets: web::Data<StaticAssets>) -> HttpResponse {
+        let (asset, fingerprinted) = match assets.find(&path) {
+            Some(found) => found ,
+            None => {
+                let path = path.into_inner();
+                match web::block(move || assets.read_disk(&path)).await {
+                    Ok(Some(asset)) => (Arc::new(asset), false) ,
+                    _ => return HttpResponse::NotFound().finish()
+                }
+            }
+        };
+        let etag = format!("\"{}\"", asset.hash);
+        let cache_control = if fingerprinted { Self::IMMUTABLE } else { Self::REVALIDATE };
+        // 変更されていない場合は304を返す
+        let not_modified = request.headers().get(header::IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .map_or(false, |value| value.split(',').any(|tag| tag.trim() == etag));
+        if not_modified {
+            return HttpResponse::NotModified()
+                .insert_header((header::ETAG, etag))
+                .insert_header((header::CACHE_CONTROL, cache_control))
+                .finish();
+        }
+        let mut response = HttpResponse::Ok();
+        response.content_type(asset.content_type.clone())
+            .insert_header((header::ETAG, etag))
+            .insert_header((header::CACHE_CONTROL, cache_control))
+            .insert_header((header::VARY, "Accept-Encoding"));
+        match (&asset.brotli, &asset.gzip) {
+            (Some(brotli), _) if Self::accepts(&request, "br") =>
+                response.insert_header((header::CONTENT_ENCODING, "br")).body(brotli.clone()) ,
+            (_, Some(gzip)) if Self::accepts(&request, "gzip") =>
+                response.insert_header((header::CONTENT_ENCODING, "gzip")).body(gzip.clone()) ,
+            _ => response.body(asset.identity.clone())
+        }
+    }
+    // Accept-Encodingに指定された圧縮形式が含まれるか(q=0は除く)
+    fn accepts(request: &HttpRequest, encoding: &str) -> bool {
+        request.headers().get(header::ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .map_or(false, |value| value.split(',').any(|item| {
+                let mut parts = item.split(';').map(|part| part.trim());
+                parts.next() == Some(encoding) && !parts.any(|part| part == "q=0" || part == "q=0.0")
+            }))
+    }
+}
@@ -1,19 +1,19 @@
-use actix_web::{Responder, web};
-use tera::Tera;
-use crate::handler::view_helper::UiHelper;
+use actix_web::Responder;
+use crate::handler::view_helper::{UiHelper, View};
 use crate::jwt::WebClaims;
+use crate::Result;
 
 pub struct MenuHandler;
 impl  MenuHandler {
     pub const VIEW_PATH: &'static str =  "pages/menu/menu.html";
-    pub async fn menu(_claims: WebClaims , tera: web::Data<Tera>) -> impl Responder  {
-        UiHelper::create_resp(&tera,&tera::Context::new(),Self::VIEW_PATH)
+    pub async fn menu(_claims: WebClaims , view: View) -> Result<impl Responder>  {
+        UiHelper::create_resp(&view,&tera::Context::new(),Self::VIEW_PATH)
     }
 }
 pub struct ErrorHandler;
 impl ErrorHandler {
     pub const VIEW_PATH: &'static str =  "pages/error/error.html";
-    pub async fn error(_claims: WebClaims , tera: web::Data<tera::Tera>) -> impl Responder  {
-        UiHelper::create_resp(&tera,&tera::Context::new(),Self::VIEW_PATH)
+    pub async fn error(_claims: WebClaims , view: View) -> Result<impl Responder>  {
+        UiHelper::create_resp(&view,&tera::Context::new(),Self::VIEW_PATH)
     }
 }
\ No newline at end of file
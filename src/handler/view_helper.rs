@@ -1,21 +1,145 @@
-use actix_session::Session;
+use std::collections::HashMap;
+use std::future::{ready, Future};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use actix_session::{Session, SessionExt};
 use actix_web::cookie::Cookie;
-use actix_web::HttpResponse;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, HttpResponse, web};
+use actix_web::dev::Payload;
 use actix_web::http::header;
+use chrono::Utc;
+use log::warn;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
-use tera::{Context, Tera};
+use serde::{Deserialize, Serialize};
+use tera::Context;
 use crate::{Result, WebAppError};
+use crate::error::error_chain;
+use crate::i18n::{Locale, LocalizedMessage, Translator};
+use crate::jwt::{AdminUsers, CurrentUser, WebClaims};
+use crate::middleware::security_headers::CspNonce;
+use crate::template::TemplateEngine;
+
+///
+/// 画面生成に利用するリクエスト毎の情報
+///
+pub struct View {
+    tera:         web::Data<TemplateEngine> ,
+    nonce:        String ,                // CSPのnonce
+    locale:       Locale ,                // 表示言語
+    current_user: Option<CurrentUser> ,   // ログイン中のユーザー
+    request:      HttpRequest             // フラッシュメッセージ、メッセージカタログの取得に利用する
+}
+impl View {
+    ///
+    /// ログイン中のユーザーを取得する
+    ///
+    pub fn current_user(&self) -> Option<&CurrentUser> {
+        self.current_user.as_ref()
+    }
+    // 表示言語を取得する
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+    ///
+    /// メッセージ(メッセージIdと引数)を表示言語に翻訳する
+    ///
+    pub fn message(&self, message: impl Into<LocalizedMessage>) -> String {
+        let message = message.into();
+        match self.request.app_data::<web::Data<Translator>>() {
+            Some(translator) => translator.localize(self.locale.as_str(), &message) ,
+            None => message.id().to_string()
+        }
+    }
+    ///
+    /// 項目毎の検証エラーを表示言語に翻訳する
+    ///
+    pub fn errors(&self, errors: &HashMap<String, LocalizedMessage>) -> HashMap<String, String> {
+        errors.iter().map(|(field, message)| (field.clone() , self.message(message.clone()))).collect()
+    }
+    // 表示待ちのフラッシュメッセージを取り出して翻訳する
+    fn flashes(&self) -> Vec<RenderedFlash> {
+        SessionHelper::take_flashes(&self.request.get_session()).unwrap_or_default().into_iter()
+            .map(|flash| RenderedFlash { level: flash.level , message: self.message(flash.message) })
+            .collect()
+    }
+}
+impl FromRequest for View {
+    type Error = WebAppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let tera = match req.app_data::<web::Data<TemplateEngine>>() {
+            Some(tera) => tera.clone() ,
+            None => return Box::pin(ready(Err(WebAppError::InternalError(String::from("TemplateEngine is not registered.")))))
+        };
+        let nonce = req.extensions().get::<CspNonce>().map(|nonce| nonce.0.clone()).unwrap_or_default();
+        let locale = Locale::resolve(req);
+        let admin_users = req.app_data::<web::Data<AdminUsers>>().cloned();
+        // 検証済みのクレームを優先し、無ければ認証と同じく失効を含めてCookieのトークンを検証する
+        let verified = req.extensions().get::<WebClaims>().cloned();
+        let claims: <WebClaims as FromRequest>::Future = match verified {
+            Some(claims) => Box::pin(ready(Ok(claims))) ,
+            None => WebClaims::from_request(req, payload)
+        };
+        let request = req.clone();
+        Box::pin(async move {
+            let current_user = claims.await.ok()
+                .map(|claims| CurrentUser::new(&claims, admin_users.as_deref()));
+            Ok(Self { tera, nonce, locale, current_user, request })
+        })
+    }
+}
 
 ///
 /// HTMLレスポンス生成、リダイレクト操作
 ///
 pub struct UiHelper;
 impl UiHelper {
+    // Teraに依存しない最小限のエラー画面(表示言語毎)
+    const FALLBACK_ERROR_PAGES: [(&'static str, &'static str); 2] = [
+        ("ja" , r#"<!DOCTYPE html>
+<html lang="ja">
+<head><meta charset="UTF-8"><title>エラー</title></head>
+<body>
+    <h3>画面を表示できませんでした。</h3>
+    <p>しばらくしてから再度アクセスしてください。</p>
+    <a href="/web_sample/menu">メニューへ戻る</a>
+</body>
+</html>"#) ,
+        ("en" , r#"<!DOCTYPE html>
+<html lang="en">
+<head><meta charset="UTF-8"><title>Error</title></head>
+<body>
+    <h3>The page could not be displayed.</h3>
+    <p>Please try again later.</p>
+    <a href="/web_sample/menu">Back to the menu</a>
+</body>
+</html>"#)
+    ];
     // HTMLレスポンスを生成する
-    pub fn create_resp(tera: &Tera,context: &Context , path: &str) -> HttpResponse {
-        let body = tera.render(path, context).unwrap();
-        HttpResponse::Ok().content_type(mime::TEXT_HTML).body(body)
+    pub fn create_resp(view: &View,context: &Context , path: &str) -> Result<HttpResponse> {
+        // 全画面で共通の値をContextに格納する
+        let mut context = context.clone();
+        context.insert("csp_nonce" , &view.nonce);
+        context.insert("current_user" , &view.current_user());
+        context.insert("lang" , view.locale.as_str());
+        context.insert("flashes" , &view.flashes());
+        match view.tera.render(path, &context) {
+            Ok(body) => Ok(HttpResponse::Ok().content_type(mime::TEXT_HTML).body(body)) ,
+            // レンダリングエラーはテンプレート名とエラーの発生原因を通知する
+            Err(error) => Err(WebAppError::RenderError(path.to_string() , error_chain(&error) , view.locale.as_str().to_string()))
+        }
+    }
+    ///
+    /// テンプレートのレンダリングに失敗した場合のエラー画面を生成する
+    /// 表示言語のエラー画面が無い場合は既定の言語で表示する
+    ///
+    pub fn fallback_error_resp(lang: &str) -> HttpResponse {
+        let page = Self::FALLBACK_ERROR_PAGES.iter()
+            .find(|(page_lang, _)| *page_lang == lang)
+            .or_else(|| Self::FALLBACK_ERROR_PAGES.iter().find(|(page_lang, _)| *page_lang == Translator::DEFAULT_LOCALE))
+            .map_or("", |(_, page)| page);
+        HttpResponse::InternalServerError().content_type(mime::TEXT_HTML).body(page)
     }
     // リダイレクトする
     pub fn found(path: &str , cookie: Option<Cookie>) -> HttpResponse {
@@ -27,29 +151,185 @@ impl UiHelper {
     }
 }
 
+///
+/// フラッシュメッセージの種類
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashLevel {
+    Success ,   // 成功
+    Info ,      // 情報
+    Warning ,   // 警告
+    Error       // エラー
+}
+///
+/// リダイレクト先の画面で一度だけ表示するメッセージ
+/// 画面に出力する値(登録結果など)をメッセージと共に受け渡せる
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashMessage {
+    pub level:   FlashLevel ,
+    pub message: LocalizedMessage ,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>
+}
+// 表示言語に翻訳したフラッシュメッセージ(画面に出力する)
+#[derive(Debug, Serialize)]
+struct RenderedFlash {
+    level:   FlashLevel ,
+    message: String
+}
+
+///
+/// Sessionに格納する値のキー
+/// キーと値の型を結び付け、型の異なる値の格納や取得をコンパイルエラーにする
+/// キーは画面遷移毎の名前空間を持ち、必要に応じて有効期間を指定できる
+///
+pub struct SessionKey<T> {
+    namespace: &'static str ,    // 名前空間(画面遷移単位)
+    name:      &'static str ,    // 名前空間内の名前
+    version:   u32 ,             // 格納する値の形式のバージョン
+    ttl:       Option<i64> ,     // 有効期間(秒)
+    value:     PhantomData<fn() -> T>
+}
+impl<T> SessionKey<T> {
+    pub const fn new(namespace: &'static str , name: &'static str) -> Self {
+        Self { namespace , name , version: 1 , ttl: None , value: PhantomData }
+    }
+    ///
+    /// 格納する値の形式のバージョンを指定する
+    /// 値の型を変更した際に上げると、変更前に格納された値は破棄される
+    ///
+    pub const fn version(self , version: u32) -> Self {
+        Self { version , ..self }
+    }
+    // 有効期間(秒)を指定する
+    pub const fn ttl(self , seconds: i64) -> Self {
+        Self { ttl: Some(seconds) , ..self }
+    }
+    pub fn namespace(&self) -> &'static str {
+        self.namespace
+    }
+    // Sessionに格納する際のキー
+    pub fn key(&self) -> String {
+        format!("{}{}{}" , self.namespace , SessionHelper::NAMESPACE_SEPARATOR , self.name)
+    }
+}
+
+///
+/// Sessionに格納する値
+/// 値の形式のバージョンと有効期限を併せて保持する
+///
+#[derive(Serialize, Deserialize)]
+struct SessionEntry<T> {
+    version:    u32 ,
+    expires_at: Option<i64> ,   // 有効期限(UNIXタイムスタンプ)
+    value:      T
+}
+
 ///
 /// Session操作
 ///
 pub struct SessionHelper;
 impl SessionHelper {
+    // 名前空間と名前の区切り
+    const NAMESPACE_SEPARATOR: char = '.';
+    // フラッシュメッセージを格納するSessionのキー
+    const FLASH_KEY: SessionKey<Vec<FlashMessage>> = SessionKey::new("flash" , "messages").version(2);
     // 指定された値をSessionに登録する
-    pub fn insert<T: Serialize>(session: &Session, key: &str , value: &T) -> Result<()> {
-        match session.insert(key, value) {
+    pub fn insert<T: Serialize>(session: &Session, key: &SessionKey<T> , value: &T) -> Result<()> {
+        let entry = SessionEntry {
+            version: key.version ,
+            expires_at: key.ttl.map(|ttl| Utc::now().timestamp() + ttl) ,
+            value
+        };
+        match session.insert(key.key(), entry) {
             Ok(()) => Ok(()) ,
             Err(error) => Err(WebAppError::InternalError(error.to_string()))
         }
     }
     // Sessionに登録された値を削除する
-    pub fn remove(session: &Session , key: &str) -> () {
-        match session.remove(key) {
+    pub fn remove<T>(session: &Session , key: &SessionKey<T>) -> () {
+        match session.remove(&key.key()) {
             Some(_) => () , None => ()
         }
     }
-    // セッションから指定された値を取得する
-    pub fn get<T: DeserializeOwned>(session: &Session , key: &str) -> Result<Option<T>>{
-        match session.get(key){
-            Ok(value) => Ok(value) ,
-            Err(error) => Err(WebAppError::InternalError(error.to_string()))
+    ///
+    /// セッションから指定された値を取得する
+    /// 有効期限切れ、形式のバージョンが異なる、または復元できない値は破棄して None を返す
+    ///
+    pub fn get<T: DeserializeOwned>(session: &Session , key: &SessionKey<T>) -> Result<Option<T>>{
+        let entry = match session.get::<SessionEntry<serde_json::Value>>(&key.key()) {
+            Ok(entry) => entry ,
+            Err(error) => {
+                warn!("discard session value {}: {}" , key.key() , error);
+                Self::remove(session , key);
+                return Ok(None);
+            }
+        };
+        let entry = match entry {
+            Some(entry) => entry ,
+            None => return Ok(None)
+        };
+        if entry.version != key.version || entry.expires_at.map_or(false, |expires_at| expires_at < Utc::now().timestamp()) {
+            Self::remove(session , key);
+            return Ok(None);
+        }
+        match serde_json::from_value::<T>(entry.value) {
+            Ok(value) => Ok(Some(value)) ,
+            Err(error) => {
+                warn!("discard session value {}: {}" , key.key() , error);
+                Self::remove(session , key);
+                Ok(None)
+            }
+        }
+    }
+    // Sessionから値を取得し、削除する
+    pub fn take<T: DeserializeOwned>(session: &Session , key: &SessionKey<T>) -> Result<Option<T>> {
+        let value = Self::get(session , key)?;
+        Self::remove(session , key);
+        Ok(value)
+    }
+    // 名前空間に属する値をすべて削除する 画面遷移の完了、中断時に利用する
+    pub fn clear_namespace(session: &Session , namespace: &str) -> () {
+        let prefix = format!("{}{}" , namespace , Self::NAMESPACE_SEPARATOR);
+        let keys = session.entries().keys()
+            .filter(|key| key.starts_with(&prefix)).cloned().collect::<Vec<String>>();
+        for key in keys {
+            session.remove(&key);
+        }
+    }
+    // フラッシュメッセージを追加する 次に表示する画面で出力される
+    pub fn flash(session: &Session , level: FlashLevel , message: impl Into<LocalizedMessage>) -> Result<()> {
+        Self::push_flash(session , FlashMessage { level , message: message.into() , payload: None })
+    }
+    // 画面に出力する値を持つフラッシュメッセージを追加する
+    pub fn flash_with<T: Serialize>(session: &Session , level: FlashLevel , message: impl Into<LocalizedMessage> , payload: &T) -> Result<()> {
+        let payload = serde_json::to_value(payload).map_err(|error| WebAppError::InternalError(error.to_string()))?;
+        Self::push_flash(session , FlashMessage { level , message: message.into() , payload: Some(payload) })
+    }
+    fn push_flash(session: &Session , flash: FlashMessage) -> Result<()> {
+        let mut flashes = Self::get(session , &Self::FLASH_KEY)?.unwrap_or_default();
+        flashes.push(flash);
+        Self::insert(session , &Self::FLASH_KEY , &flashes)
+    }
+    ///
+    /// 未表示のフラッシュメッセージが持つ値を取得する
+    /// メッセージは削除せず、画面の出力時に表示して削除する
+    ///
+    pub fn flash_payload<T: DeserializeOwned>(session: &Session , message: &str) -> Result<Option<T>> {
+        let flashes = Self::get(session , &Self::FLASH_KEY)?.unwrap_or_default();
+        Ok(flashes.into_iter()
+            .filter(|flash| flash.message.id() == message)
+            .filter_map(|flash| flash.payload)
+            .find_map(|payload| serde_json::from_value(payload).ok()))
+    }
+    // フラッシュメッセージを取り出し、Sessionから削除する
+    pub fn take_flashes(session: &Session) -> Result<Vec<FlashMessage>> {
+        let flashes = Self::get(session , &Self::FLASH_KEY)?.unwrap_or_default();
+        if !flashes.is_empty() {
+            Self::remove(session , &Self::FLASH_KEY);
         }
+        Ok(flashes)
     }
 }
\ No newline at end of file
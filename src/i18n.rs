@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::future::{ready, Ready};
+use std::sync::Arc;
+use actix_session::SessionExt;
+use actix_web::{FromRequest, HttpRequest, web};
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use fluent_bundle::bundle::FluentBundle;
+use fluent_langneg::{negotiate_languages, NegotiationStrategy};
+use fluent_langneg::accepted_languages;
+use intl_memoizer::concurrent::IntlLangMemoizer;
+use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
+use crate::WebAppError;
+use crate::handler::view_helper::{SessionHelper, SessionKey};
+use crate::startup::StartupError;
+
+// スレッド間で共有するメッセージカタログ
+type Bundle = FluentBundle<FluentResource, IntlLangMemoizer>;
+
+///
+/// 画面の表示言語
+/// セッションで選択された言語を優先し、無ければAccept-Languageから決定する
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(String);
+impl Locale {
+    // 利用者が選択した言語を格納するSessionのキー
+    pub const SESSION_KEY: SessionKey<String> = SessionKey::new("locale" , "selected");
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+    ///
+    /// リクエストから表示言語を決定する
+    ///
+    pub fn resolve(req: &HttpRequest) -> Self {
+        let translator = match req.app_data::<web::Data<Translator>>() {
+            Some(translator) => translator ,
+            None => return Self(String::from(Translator::DEFAULT_LOCALE))
+        };
+        let selected = SessionHelper::get(&req.get_session() , &Self::SESSION_KEY).ok().flatten()
+            .filter(|lang| translator.is_supported(lang));
+        match selected {
+            Some(lang) => Self(lang) ,
+            None => {
+                let accept_language = req.headers().get(header::ACCEPT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok()).unwrap_or_default();
+                Self(translator.negotiate(accept_language))
+            }
+        }
+    }
+}
+impl FromRequest for Locale {
+    type Error = WebAppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(Self::resolve(req)))
+    }
+}
+
+///
+/// メッセージの引数
+/// 数値はFluentの数値として渡し、複数形などの選択に利用できる
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageArg {
+    Number(i64) ,
+    Text(String)
+}
+impl From<&str> for MessageArg {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+impl From<String> for MessageArg {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+impl From<i64> for MessageArg {
+    fn from(value: i64) -> Self {
+        Self::Number(value)
+    }
+}
+impl From<usize> for MessageArg {
+    fn from(value: usize) -> Self {
+        Self::Number(value as i64)
+    }
+}
+
+///
+/// 画面に出力するメッセージ
+/// メッセージIdと引数の組で保持し、画面生成時に表示言語に翻訳する
+/// フラッシュメッセージとしてSessionに格納できる
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalizedMessage {
+    id:   String ,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    args: Vec<(String, MessageArg)>
+}
+impl LocalizedMessage {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into() , args: Vec::new() }
+    }
+    // 引数を追加する
+    pub fn arg(mut self, name: &str, value: impl Into<MessageArg>) -> Self {
+        self.args.push((name.to_string(), value.into()));
+        self
+    }
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+    // Fluentの引数に変換する
+    pub fn fluent_args(&self) -> FluentArgs<'_> {
+        let mut args = FluentArgs::new();
+        for (name, value) in self.args.iter() {
+            match value {
+                MessageArg::Number(number) => args.set(name.as_str(), FluentValue::from(*number)) ,
+                MessageArg::Text(text) => args.set(name.as_str(), FluentValue::from(text.as_str()))
+            }
+        }
+        args
+    }
+}
+impl From<&str> for LocalizedMessage {
+    fn from(id: &str) -> Self {
+        Self::new(id)
+    }
+}
+impl From<String> for LocalizedMessage {
+    fn from(id: String) -> Self {
+        Self::new(id)
+    }
+}
+impl Display for LocalizedMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)?;
+        for (name, value) in self.args.iter() {
+            match value {
+                MessageArg::Number(number) => write!(f, " {}={}", name, number)? ,
+                MessageArg::Text(text) => write!(f, " {}={:?}", name, text)?
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// メッセージの翻訳
+/// locales配下のFluentカタログをバイナリに埋め込んで利用する
+///
+#[derive(Clone)]
+pub struct Translator {
+    bundles: Arc<HashMap<String, Bundle>> ,   // 言語毎のカタログ
+    locales: Arc<Vec<LanguageIdentifier>>     // 対応している言語
+}
+impl Translator {
+    pub const DEFAULT_LOCALE: &'static str = "ja";
+    const CATALOGS: [(&'static str, &'static str); 2] = [
+        ("ja" , include_str!("../locales/ja/main.ftl")) ,
+        ("en" , include_str!("../locales/en/main.ftl"))
+    ];
+
+    ///
+    /// 埋め込まれたカタログを読み込む
+    ///
+    pub fn load() -> Result<Self, StartupError> {
+        let mut bundles = HashMap::new();
+        let mut locales = Vec::new();
+        for (lang, source) in Self::CATALOGS {
+            let id = lang.parse::<LanguageIdentifier>()
+                .map_err(|error| StartupError::Config(format!("言語'{}'が不正です: {}", lang, error)))?;
+            let resource = FluentResource::try_new(source.to_string())
+                .map_err(|(_, errors)| StartupError::Config(format!("{}のメッセージカタログを解析できません: {:?}", lang, errors)))?;
+            let mut bundle = Bundle::new_concurrent(vec![id.clone()]);
+            // HTMLに双方向制御文字を出力しない
+            bundle.set_use_isolating(false);
+            bundle.add_resource(resource)
+                .map_err(|errors| StartupError::Config(format!("{}のメッセージカタログが不正です: {:?}", lang, errors)))?;
+            bundles.insert(lang.to_string(), bundle);
+            locales.push(id);
+        }
+        Ok(Self { bundles: Arc::new(bundles), locales: Arc::new(locales) })
+    }
+    // 対応している言語か検証する
+    pub fn is_supported(&self, lang: &str) -> bool {
+        self.bundles.contains_key(lang)
+    }
+    // Accept-Languageから表示言語を選択する
+    pub fn negotiate(&self, accept_language: &str) -> String {
+        let requested = accepted_languages::parse(accept_language);
+        let default = self.locales.iter().find(|id| id.to_string() == Self::DEFAULT_LOCALE);
+        negotiate_languages(&requested, self.locales.as_slice(), default, NegotiationStrategy::Lookup)
+            .first().map_or_else(|| String::from(Self::DEFAULT_LOCALE), |id| id.to_string())
+    }
+    ///
+    /// メッセージを翻訳する
+    /// 指定された言語に無い場合は既定の言語、それも無い場合はメッセージIdを返す
+    ///
+    pub fn message(&self, lang: &str, id: &str, args: Option<&FluentArgs>) -> String {
+        self.translate(lang, id, args)
+            .or_else(|| self.translate(Self::DEFAULT_LOCALE, id, args))
+            .unwrap_or_else(|| id.to_string())
+    }
+    ///
+    /// メッセージIdと引数の組を翻訳する
+    ///
+    pub fn localize(&self, lang: &str, message: &LocalizedMessage) -> String {
+        self.message(lang, message.id(), Some(&message.fluent_args()))
+    }
+
+    // 指定された言語でメッセージを翻訳する
+    fn translate(&self, lang: &str, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let bundle = self.bundles.get(lang)?;
+        let pattern = bundle.get_message(id)?.value()?;
+        let mut errors = Vec::new();
+        Some(bundle.format_pattern(pattern, args, &mut errors).into_owned())
+    }
+}
+
+///
+/// テンプレートから利用する翻訳関数
+/// {{ t(key="menu-search", lang=lang) }} のように利用し、その他の引数はメッセージに渡す
+///
+pub struct TranslateFunction(pub Translator);
+impl tera::Function for TranslateFunction {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let key = args.get("key").and_then(|key| key.as_str())
+            .ok_or_else(|| tera::Error::msg("t() requires a `key` argument"))?;
+        let lang = args.get("lang").and_then(|lang| lang.as_str()).unwrap_or(Translator::DEFAULT_LOCALE);
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args.iter().filter(|(name, _)| name.as_str() != "key" && name.as_str() != "lang") {
+            let value = match value {
+                tera::Value::String(value) => FluentValue::from(value.clone()) ,
+                tera::Value::Number(number) => number.as_i64().map_or_else(
+                    || FluentValue::from(number.to_string()), |number| FluentValue::from(number)) ,
+                value => FluentValue::from(value.to_string())
+            };
+            fluent_args.set(name.clone(), value);
+        }
+        Ok(tera::Value::String(self.0.message(lang, key, Some(&fluent_args))))
+    }
+}
\ No newline at end of file
@@ -1,37 +1,101 @@
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
-use actix_web::{FromRequest, HttpRequest};
+use std::sync::Arc;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, web};
 use actix_web::dev::Payload;
 use chrono::Duration;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use log::warn;
+use rand::RngCore;
 use serde::{Serialize, Deserialize};
 use app_commons::application::transfers::UserDto;
-use app_commons::presentation::jwt::{ClaimsGenerator, JWT_COOKIE_KEY, JwtDecoder, JwtEncoder};
+use app_commons::presentation::jwt::{ClaimsGenerator, JWT_COOKIE_KEY};
 use crate::WebAppError;
+use crate::config::{AppEnvironment, EnvConfig};
+use crate::startup::StartupError;
+use crate::account::{entity as account, Role};
+use crate::account::revocation::TokenRevocation;
+use crate::handler::view_helper::SessionKey;
 
 /// クレーム(認証に必要な個人情報)
 /// JWTトークンのPayload
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebClaims {
     iat:        i64 ,      //  Token取得日時
+    #[serde(default)]
+    iat_ms:     i64 ,      //  Token取得日時(ミリ秒 失効の判定に利用する)
     exp:        i64 ,      //  Tokenの有効期限
+    nbf:        i64 ,      //  Tokenの有効開始日時
+    iss:        String ,   //  発行者
+    aud:        String ,   //  受信者
     sub:        String ,   //  リソースオーナーの識別子
     user_id:    String ,   //  ユーザーId(Uuid)
     user_name:  String,    //  ユーザー名
+    #[serde(default)]
+    roles:      Vec<String> , // ロール
+    #[serde(default)]
+    jti:        String      //  トークンId(ログアウト時の個別の失効に利用する)
 }
-impl ClaimsGenerator<UserDto> for WebClaims {
-    fn generate(user: &UserDto) -> Self {
+impl WebClaims {
+    // トークンの有効期間(分)
+    pub const LIFETIME_MINUTES: i64 = 5;
+    // ユーザーIdを取得する
+    pub fn user_id(&self) -> &str {
+        self.user_id.as_str()
+    }
+    // ユーザー名を取得する
+    pub fn user_name(&self) -> &str {
+        self.user_name.as_str()
+    }
+    // 指定されたロールを保持しているか検証する
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|value| value == role)
+    }
+    // 管理者ロールを保持しているか、ADMIN_USERSに含まれるか検証する
+    pub fn is_admin(&self, admin_users: Option<&AdminUsers>) -> bool {
+        self.has_role(Role::ADMIN) || admin_users.map_or(false, |admin_users| admin_users.contains(self.user_name()))
+    }
+    ///
+    /// 失効日時より後に発行したクレームにする
+    /// 失効と同じミリ秒に生成したクレームでも、失効後に発行したトークンは有効とする
+    ///
+    pub fn issued_after(self, revoked_before: i64) -> Self {
+        Self { iat_ms: self.iat_ms.max(revoked_before + 1) , ..self }
+    }
+    // 発行日時(ミリ秒) ミリ秒を持たないトークンは秒から求める
+    fn issued_at_millis(&self) -> i64 {
+        if self.iat_ms > 0 { self.iat_ms } else { self.iat * 1000 }
+    }
+    ///
+    /// アカウントからクレームを生成する
+    ///
+    pub fn from_account(account: &account::Model) -> Self {
+        Self::new(account.id.clone(), account.user_name.clone(), account.role_list())
+    }
+    fn new(user_id: String, user_name: String, roles: Vec<String>) -> Self {
         let now =  chrono::Utc::now();
-        let _iat =  now.timestamp();
         // クレーム(Payload)の生成
         Self {
-            iat: _iat , // 取得日時の設定
-            exp: (now + Duration::minutes(5)).timestamp() , // 有効期限を5分に設定
-            sub: String::from("M.Furukawa") , // オーナー識別子を設定
-            user_id: user.user_id.clone() ,     // ユーザーidを設定
-            user_name: user.user_name.clone(),  // ユーザー名
+            iat: now.timestamp() , // 取得日時の設定
+            iat_ms: now.timestamp_millis() ,
+            exp: (now + Duration::minutes(Self::LIFETIME_MINUTES)).timestamp() , // 有効期限を5分に設定
+            nbf: now.timestamp() , // 有効開始日時の設定
+            iss: String::new() ,   // 発行者はエンコード時に設定する
+            aud: String::new() ,   // 受信者はエンコード時に設定する
+            sub: user_id.clone() , // オーナー識別子にユーザーidを設定
+            user_id ,     // ユーザーidを設定
+            user_name ,   // ユーザー名
+            roles ,       // ロール
+            jti: uuid::Uuid::new_v4().to_string()
         }
     }
 }
+impl ClaimsGenerator<UserDto> for WebClaims {
+    fn generate(user: &UserDto) -> Self {
+        Self::new(user.user_id.clone(), user.user_name.clone(), Vec::new())
+    }
+}
 ///
 /// リクエスト受信時の前処理
 ///
@@ -41,33 +105,323 @@ impl FromRequest for WebClaims {
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
         let request = req.clone();
+        let decoder = req.app_data::<web::Data<WebJwt>>().cloned();
+        let revocation = req.app_data::<web::Data<TokenRevocation>>().cloned();
         Box::pin(async move {
-            // JWTデーコード機能を生成する
-            let decoder = WebJwt::default();
+            // 起動時に生成したJWTデコード機能を取得する
+            let decoder = decoder.ok_or_else(|| WebAppError::InternalError(String::from("jwt settings are not registered.")))?;
             // リクエストヘッダーを解析する
             let token = decoder.parse_header(&request)?;
-            match decoder.decode(token.as_str()) {
-                // 取得したClaimsを返す
-                Ok(token_data) =>  Ok(token_data.claims),
-                // ヘッダーが存在しない場合は認証へリダイレクトさせる
-                Err(error) => Err(WebAppError::AuthorizationError(error.to_string()))
+            // 検証に失敗した場合は認証へリダイレクトさせる
+            let claims = decoder.decode(token.as_str())?;
+            // パスワード変更などで失効したトークンは認証へリダイレクトさせる
+            if let Some(revocation) = revocation {
+                match revocation.is_revoked(&claims.user_id, claims.issued_at_millis(), &claims.jti).await {
+                    Ok(false) => () ,
+                    Ok(true) => return Err(WebAppError::AuthorizationError(String::from("token has been revoked."))) ,
+                    Err(error) => return Err(WebAppError::InternalError(error))
+                }
+            }
+            // 検証済みのクレームを画面生成で参照できるようにする
+            request.extensions_mut().insert(claims.clone());
+            Ok(claims)
+        })
+    }
+}
+///
+/// 管理者のクレーム
+/// 管理者ロールを持たず、ADMIN_USERSにも含まれないリクエストは拒否する
+///
+pub struct AdminClaims(pub WebClaims);
+impl FromRequest for AdminClaims {
+    type Error = WebAppError;
+    type Future = Pin<Box<dyn Future<Output = anyhow::Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let admin_users = req.app_data::<web::Data<AdminUsers>>().cloned();
+        let claims = WebClaims::from_request(req, payload);
+        Box::pin(async move {
+            let claims = claims.await?;
+            if claims.is_admin(admin_users.as_deref()) {
+                Ok(AdminClaims(claims))
+            } else {
+                Err(WebAppError::ForbiddenError(format!("{} is not an administrator.", claims.user_name())))
             }
         })
     }
 }
 ///
-/// Web用Jwtトークンのデコード
+/// 画面に表示するログイン中のユーザー
+///
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrentUser {
+    pub user_id:   String ,        // ユーザーId
+    pub user_name: String ,        // ユーザー名
+    pub roles:     Vec<String> ,   // ロール
+    pub admin:     bool            // 管理者メニューを表示するか
+}
+impl CurrentUser {
+    pub fn new(claims: &WebClaims, admin_users: Option<&AdminUsers>) -> Self {
+        Self {
+            user_id: claims.user_id.clone() ,
+            user_name: claims.user_name.clone() ,
+            roles: claims.roles.clone() ,
+            admin: claims.is_admin(admin_users)
+        }
+    }
+}
+///
+/// Sessionに格納するログイン中のユーザー
+/// purge-sessionsサブコマンドでユーザーのセッションを特定するために利用する
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionUser {
+    pub user_id:   String ,        // ユーザーId
+    pub user_name: String          // ユーザー名
+}
+impl SessionUser {
+    pub const SESSION_KEY: SessionKey<SessionUser> = SessionKey::new("auth" , "user");
+    pub fn new(user_id: &str, user_name: &str) -> Self {
+        Self { user_id: user_id.to_string() , user_name: user_name.to_string() }
+    }
+}
+///
+/// 管理者のユーザー名一覧
+///
+#[derive(Debug, Clone, Default)]
+pub struct AdminUsers(Vec<String>);
+impl AdminUsers {
+    // カンマ区切りのユーザー名から生成する
+    pub fn parse(value: &str) -> Self {
+        Self(value.split(',').map(str::trim).filter(|name| !name.is_empty()).map(String::from).collect())
+    }
+    pub fn contains(&self, user_name: &str) -> bool {
+        self.0.iter().any(|name| name == user_name)
+    }
+}
+
+
+///
+/// Web用Jwtトークンのエンコードとデコード
+/// 署名アルゴリズムと鍵は環境変数から取得し、kidで検証鍵を選択する
 ///
-#[derive(Default)]
-pub struct WebJwt;
-// トークンのエンコード デフォルト実装をそのまま利用する
-impl JwtEncoder for WebJwt{}
-impl JwtDecoder<WebClaims , WebAppError, HttpRequest> for WebJwt{
-    fn parse_header(&self , request: &HttpRequest) -> Result<String, WebAppError> {
-        // CookieからJWTトークンを取得する
+#[derive(Clone)]
+pub struct WebJwt {
+    algorithm:    Algorithm ,                           // 署名アルゴリズム
+    key_id:       String ,                              // 署名に利用する鍵のkid
+    signing_key:  Arc<EncodingKey> ,                    // 署名鍵
+    verify_keys:  Arc<HashMap<String, DecodingKey>> ,   // kid毎の検証鍵
+    issuer:       String ,                              // 発行者(iss)
+    audience:     String                                // 受信者(aud)
+}
+impl WebJwt {
+    // 署名アルゴリズム(HS256 RS256 ES256)
+    const ALGORITHM_KEY: &'static str = "JWT_ALGORITHM";
+    // 署名鍵のkid
+    const KEY_ID_KEY: &'static str = "JWT_KEY_ID";
+    // 署名鍵 HS256は共通鍵、RS256/ES256は秘密鍵(PEM)のパス
+    const SIGNING_KEY_KEY: &'static str = "JWT_SIGNING_KEY";
+    // 検証鍵 kid=値 をセミコロン区切りで指定する HS256は共通鍵、RS256/ES256は公開鍵(PEM)のパス
+    const VERIFICATION_KEYS_KEY: &'static str = "JWT_VERIFICATION_KEYS";
+    const ISSUER_KEY: &'static str = "JWT_ISSUER";
+    const AUDIENCE_KEY: &'static str = "JWT_AUDIENCE";
+    const DEFAULT_KEY_ID: &'static str = "default";
+    const DEFAULT_ISSUER: &'static str = "web_sample";
+    // トークンを格納するCookieのパス
+    const COOKIE_PATH: &'static str = "/web_sample";
+    // 開発環境で署名鍵が未設定の場合に生成する共通鍵のバイト数
+    const GENERATED_SECRET_BYTES: usize = 32;
+
+    ///
+    /// 環境変数から生成する
+    /// 開発環境でHS256の署名鍵が未設定の場合は起動毎に共通鍵を生成する
+    ///
+    pub fn from_env(environment: AppEnvironment) -> Result<Self, StartupError> {
+        let algorithm = match EnvConfig::string(Self::ALGORITHM_KEY, "HS256").as_str() {
+            "HS256" => Algorithm::HS256 ,
+            "RS256" => Algorithm::RS256 ,
+            "ES256" => Algorithm::ES256 ,
+            value => return Err(StartupError::Config(
+                format!("{}の値'{}'はHS256、RS256またはES256を指定してください", Self::ALGORITHM_KEY, value)))
+        };
+        let key_id = EnvConfig::string(Self::KEY_ID_KEY, Self::DEFAULT_KEY_ID);
+        let entries = Self::verification_entries()?;
+        let mut verify_keys = Self::verification_keys(algorithm, &entries)?;
+        let signing_key = match (algorithm, std::env::var(Self::SIGNING_KEY_KEY).ok().filter(|value| !value.is_empty())) {
+            (Algorithm::HS256, Some(secret)) => {
+                // 署名鍵のkidに異なる共通鍵を設定すると、発行した全てのトークンの検証に失敗する
+                if entries.iter().any(|(kid, key)| *kid == key_id && *key != secret) {
+                    return Err(StartupError::Config(format!("{}のkid'{}'の共通鍵が{}と一致しません",
+                        Self::VERIFICATION_KEYS_KEY, key_id, Self::SIGNING_KEY_KEY)));
+                }
+                verify_keys.insert(key_id.clone(), DecodingKey::from_secret(secret.as_bytes()));
+                EncodingKey::from_secret(secret.as_bytes())
+            } ,
+            (Algorithm::HS256, None) if environment == AppEnvironment::Development => {
+                if entries.iter().any(|(kid, _)| *kid == key_id) {
+                    return Err(StartupError::Config(format!("{}のkid'{}'を利用する場合は{}を設定してください",
+                        Self::VERIFICATION_KEYS_KEY, key_id, Self::SIGNING_KEY_KEY)));
+                }
+                warn!("{}が未設定のため共通鍵を生成しました。再起動すると発行済みのトークンは無効になります", Self::SIGNING_KEY_KEY);
+                let mut secret = vec![0u8; Self::GENERATED_SECRET_BYTES];
+                rand::thread_rng().fill_bytes(&mut secret);
+                verify_keys.insert(key_id.clone(), DecodingKey::from_secret(&secret));
+                EncodingKey::from_secret(&secret)
+            } ,
+            (_, Some(path)) => {
+                let pem = Self::read_pem(&path)?;
+                let key = if algorithm == Algorithm::RS256 { EncodingKey::from_rsa_pem(&pem) } else { EncodingKey::from_ec_pem(&pem) };
+                key.map_err(|error| StartupError::Config(format!("{}の秘密鍵'{}'を読み込めません: {}", Self::SIGNING_KEY_KEY, path, error)))?
+            } ,
+            (_, None) => return Err(StartupError::Config(format!("{}を設定してください", Self::SIGNING_KEY_KEY)))
+        };
+        if !verify_keys.contains_key(&key_id) {
+            return Err(StartupError::Config(
+                format!("{}に署名鍵のkid'{}'の検証鍵を設定してください", Self::VERIFICATION_KEYS_KEY, key_id)));
+        }
+        let jwt = Self {
+            algorithm ,
+            key_id ,
+            signing_key: Arc::new(signing_key) ,
+            verify_keys: Arc::new(verify_keys) ,
+            issuer: EnvConfig::string(Self::ISSUER_KEY, Self::DEFAULT_ISSUER) ,
+            audience: EnvConfig::string(Self::AUDIENCE_KEY, Self::DEFAULT_ISSUER)
+        };
+        jwt.probe()?;
+        Ok(jwt)
+    }
+    // 署名鍵で署名した検査用のトークンを署名鍵のkidの検証鍵で検証する
+    // RS256/ES256では秘密鍵と公開鍵の組み合わせの誤りを起動時に検出する
+    fn probe(&self) -> Result<(), StartupError> {
+        let claims = WebClaims::new(String::from("probe"), String::from("probe"), Vec::new());
+        self.encode(&claims).and_then(|token| self.decode(&token)).map(|_| ())
+            .map_err(|error| StartupError::Config(format!("{}の秘密鍵と{}のkid'{}'の検証鍵が対応しません: {:?}",
+                Self::SIGNING_KEY_KEY, Self::VERIFICATION_KEYS_KEY, self.key_id, error)))
+    }
+    ///
+    /// kidと共通鍵を指定してHS256で生成する
+    /// 環境変数を参照しないため、鍵を明示する必要があるテストなどで利用する
+    ///
+    pub fn hs256(key_id: &str, secret: &[u8]) -> Self {
+        let verify_keys = HashMap::from([(key_id.to_string(), DecodingKey::from_secret(secret))]);
+        Self {
+            algorithm: Algorithm::HS256 ,
+            key_id: key_id.to_string() ,
+            signing_key: Arc::new(EncodingKey::from_secret(secret)) ,
+            verify_keys: Arc::new(verify_keys) ,
+            issuer: Self::DEFAULT_ISSUER.to_string() ,
+            audience: Self::DEFAULT_ISSUER.to_string()
+        }
+    }
+    // kid=値 のセミコロン区切りを解析する
+    fn verification_entries() -> Result<Vec<(String, String)>, StartupError> {
+        let value = EnvConfig::string(Self::VERIFICATION_KEYS_KEY, "");
+        value.split(';').map(str::trim).filter(|entry| !entry.is_empty())
+            .map(|entry| entry.split_once('=')
+                .map(|(kid, key)| (kid.trim().to_string(), key.trim().to_string()))
+                .filter(|(kid, key)| !kid.is_empty() && !key.is_empty())
+                .ok_or_else(|| StartupError::Config(
+                    format!("{}の値'{}'はkid=値の形式で指定してください", Self::VERIFICATION_KEYS_KEY, entry))))
+            .collect()
+    }
+    // kidと値の一覧から検証鍵を生成する
+    fn verification_keys(algorithm: Algorithm, entries: &[(String, String)]) -> Result<HashMap<String, DecodingKey>, StartupError> {
+        let mut keys = HashMap::new();
+        for (kid, key) in entries.iter().map(|(kid, key)| (kid.as_str(), key.as_str())) {
+            let decoding_key = match algorithm {
+                Algorithm::HS256 => DecodingKey::from_secret(key.as_bytes()) ,
+                Algorithm::RS256 => DecodingKey::from_rsa_pem(&Self::read_pem(key)?)
+                    .map_err(|error| StartupError::Config(format!("公開鍵'{}'を読み込めません: {}", key, error)))? ,
+                _ => DecodingKey::from_ec_pem(&Self::read_pem(key)?)
+                    .map_err(|error| StartupError::Config(format!("公開鍵'{}'を読み込めません: {}", key, error)))?
+            };
+            if keys.insert(kid.to_string(), decoding_key).is_some() {
+                return Err(StartupError::Config(format!("{}のkid'{}'が重複しています", Self::VERIFICATION_KEYS_KEY, kid)));
+            }
+        }
+        Ok(keys)
+    }
+    // PEMファイルを読み込む
+    fn read_pem(path: &str) -> Result<Vec<u8>, StartupError> {
+        std::fs::read(path).map_err(|error| StartupError::Config(format!("鍵ファイル'{}'を読み込めません: {}", path, error)))
+    }
+
+    ///
+    /// クレームに発行者と受信者を設定し、kidを付与したトークンを生成する
+    ///
+    pub fn encode(&self, claims: &WebClaims) -> crate::Result<String> {
+        let claims = WebClaims { iss: self.issuer.clone(), aud: self.audience.clone(), ..claims.clone() };
+        let mut header = Header::new(self.algorithm);
+        header.kid = Some(self.key_id.clone());
+        jsonwebtoken::encode(&header, &claims, &self.signing_key)
+            .map_err(|error| WebAppError::InternalError(error.to_string()))
+    }
+    ///
+    /// kidに対応する検証鍵で署名、有効期間、発行者、受信者を検証する
+    ///
+    pub fn decode(&self, token: &str) -> crate::Result<WebClaims> {
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|error| WebAppError::AuthorizationError(error.to_string()))?;
+        let key = header.kid.as_ref().and_then(|kid| self.verify_keys.get(kid))
+            .ok_or_else(|| WebAppError::AuthorizationError(format!("unknown key id: {:?}", header.kid)))?;
+        let mut validation = Validation::new(self.algorithm);
+        validation.set_issuer(&[self.issuer.as_str()]);
+        validation.set_audience(&[self.audience.as_str()]);
+        validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud", "sub"]);
+        validation.validate_nbf = true;
+        jsonwebtoken::decode::<WebClaims>(token, key, &validation)
+            .map(|token_data| token_data.claims)
+            .map_err(|error| WebAppError::AuthorizationError(error.to_string()))
+    }
+    ///
+    /// CookieからJWTトークンを取得する
+    ///
+    pub fn parse_header(&self, request: &HttpRequest) -> crate::Result<String> {
         match request.cookie(JWT_COOKIE_KEY) {
             Some(cookie_value) => Ok(String::from(cookie_value.name_value().1)),
-            None => return Err(WebAppError::AuthorizationError(String::from("token does not exist.")))
+            None => Err(WebAppError::AuthorizationError(String::from("token does not exist.")))
         }
     }
-}
+    ///
+    /// トークンを格納したCookieを生成する
+    ///
+    pub fn cookie(&self, claims: &WebClaims) -> crate::Result<cookie::Cookie<'static>> {
+        // JWTトークンを生成する
+        let token = self.encode(claims)?;
+        //　生成したトークンをCookieを生成する
+        Ok(cookie::Cookie::build(JWT_COOKIE_KEY, token)
+            // 有効期限を5分に設定する
+            .max_age(cookie::time::Duration::minutes(WebClaims::LIFETIME_MINUTES))
+            // 発行した画面に関わらずアプリケーション全体で送信させる
+            .path(Self::COOKIE_PATH)
+            // HTTPのみ有効にし、SL/TLSに限定する
+            .http_only(true).secure(true).finish())
+    }
+    ///
+    /// トークンを格納したCookieを削除するCookieを生成する
+    ///
+    pub fn removal_cookie() -> cookie::Cookie<'static> {
+        let mut cookie = cookie::Cookie::build(JWT_COOKIE_KEY, "")
+            .path(Self::COOKIE_PATH).http_only(true).secure(true).finish();
+        cookie.make_removal();
+        cookie
+    }
+    ///
+    /// このトークンだけを残りの有効期間だけ失効させる(ログアウト)
+    ///
+    pub async fn revoke_token(revocation: &TokenRevocation, claims: &WebClaims) -> crate::Result<()> {
+        if claims.jti.is_empty() {
+            return Ok(());
+        }
+        let ttl = (claims.exp - chrono::Utc::now().timestamp()).max(0) as u64;
+        revocation.revoke_token(&claims.jti, ttl).await.map_err(WebAppError::InternalError)
+    }
+    ///
+    /// ユーザーのトークンを全て失効させ、セッションを削除する
+    /// 失効日時(ミリ秒)を返す
+    ///
+    pub async fn revoke(revocation: &TokenRevocation, user_id: &str) -> crate::Result<i64> {
+        revocation.revoke(user_id, (WebClaims::LIFETIME_MINUTES * 60) as u64).await
+            .map_err(WebAppError::InternalError)
+    }
+}
\ No newline at end of file
@@ -1,6 +1,22 @@
 pub mod handler;
 pub mod jwt;
 pub mod error;
+pub mod startup;
+pub mod config;
+pub mod template;
+pub mod assets;
+pub mod middleware;
+pub mod audit;
+pub mod account;
+pub mod mail;
+pub mod i18n;
+pub mod session_store;
+pub mod routes;
+pub mod provider;
+pub mod demo;
+pub mod cli;
+pub mod seed;
+pub mod tls;
 
 use error::WebAppError;
 pub type Result<T> = anyhow::Result<T , WebAppError>;
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use async_trait::async_trait;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use log::info;
+use crate::config::EnvConfig;
+use crate::startup::StartupError;
+
+///
+/// 送信するメール
+///
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to:      String ,
+    pub subject: String ,
+    pub body:    String     // 本文(テキスト)
+}
+
+///
+/// メール送信
+///
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<(), String>;
+}
+
+///
+/// SMTPによるメール送信
+///
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor> ,
+    from:      String
+}
+impl SmtpMailer {
+    ///
+    /// STARTTLSで接続するトランスポートを生成する
+    ///
+    pub fn new(host: &str, port: u16, credentials: Option<(String, String)>, from: &str) -> Result<Self, String> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|error| error.to_string())?
+            .port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        Ok(Self { transport: builder.build(), from: from.to_string() })
+    }
+}
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), String> {
+        let message = Message::builder()
+            .from(self.from.parse().map_err(|error| format!("from {}: {}", self.from, error))?)
+            .to(mail.to.parse().map_err(|error| format!("to {}: {}", mail.to, error))?)
+            .subject(mail.subject.clone())
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body.clone())
+            .map_err(|error| error.to_string())?;
+        self.transport.send(message).await.map(|_| ()).map_err(|error| error.to_string())
+    }
+}
+
+///
+/// ファイル/ログへの出力(開発、テスト用)
+/// ディレクトリが指定された場合は1通毎にファイルを出力する
+///
+pub struct FileMailer {
+    dir: Option<PathBuf>
+}
+impl FileMailer {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+}
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), String> {
+        let content = format!("To: {}\nSubject: {}\n\n{}\n", mail.to, mail.subject, mail.body);
+        match &self.dir {
+            Some(dir) => {
+                let path = dir.join(format!("{}-{}.eml",
+                    chrono::Utc::now().format("%Y%m%d%H%M%S"), uuid::Uuid::new_v4()));
+                std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, content))
+                    .map_err(|error| format!("{}: {}", path.display(), error))?;
+                info!("mail to {} written to {}", mail.to, path.display());
+            },
+            None => info!("mail\n{}", content)
+        }
+        Ok(())
+    }
+}
+
+///
+/// 環境変数からメール送信を生成する
+/// MAIL_TRANSPORT: smtp または file(既定)
+///
+pub fn create_mailer() -> Result<Arc<dyn Mailer>, StartupError> {
+    match EnvConfig::string("MAIL_TRANSPORT", "file").as_str() {
+        "smtp" => {
+            let host = EnvConfig::string("SMTP_HOST", "localhost");
+            let port = EnvConfig::u64("SMTP_PORT", 587)?;
+            let port = u16::try_from(port)
+                .map_err(|_| StartupError::Config(format!("SMTP_PORTの値'{}'は不正です", port)))?;
+            let credentials = match EnvConfig::string("SMTP_USERNAME", "").as_str() {
+                "" => None ,
+                username => Some((username.to_string(), EnvConfig::string("SMTP_PASSWORD", "")))
+            };
+            let from = EnvConfig::string("MAIL_FROM", "web_sample@localhost");
+            SmtpMailer::new(&host, port, credentials, &from)
+                .map(|mailer| Arc::new(mailer) as Arc<dyn Mailer>)
+                .map_err(|error| StartupError::Config(format!("SMTP {}: {}", host, error)))
+        },
+        "file" => {
+            let dir = match EnvConfig::string("MAIL_DIR", "").as_str() {
+                "" => None ,
+                dir => Some(PathBuf::from(dir))
+            };
+            Ok(Arc::new(FileMailer::new(dir)))
+        },
+        other => Err(StartupError::Config(format!("MAIL_TRANSPORTの値'{}'はsmtpまたはfileを指定してください", other)))
+    }
+}
\ No newline at end of file
@@ -1,99 +1,127 @@
-use tera::Tera;
 use actix_web::{App, HttpServer, middleware, web};
-use actix_session::config::PersistentSession;
-use actix_session::SessionMiddleware;
-use actix_session::storage::RedisSessionStore;
-use actix_web::cookie::time::Duration;
-use actix_web::web::resource;
-use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod};
-use app_commons::infrastructure::pool::PoolProvider;
-use app_commons::infrastructure::sea_orm::pool_impl::SeaOrmPool;
-use app_commons::application::sea_orm::provider_impl::AppServiceProvider;
+use clap::Parser;
+use web_sample::cli::Cli;
+use web_sample::middleware::forwarded::{ClientInfo, ForwardedHeaders};
+use web_sample::middleware::https_redirect::HttpsRedirect;
+use web_sample::middleware::request_id::RequestIdentifier;
+use web_sample::middleware::security_headers::SecurityHeaders;
+use web_sample::routes::set_config;
+use web_sample::startup::{Startup, StartupError};
 
+// アクセスログの形式(Logger::default()の%aをクライアントのアドレスに置き換える)
+const LOG_FORMAT: &str = r#"%{client_ip}xi "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T"#;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // ロガーの初期化
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
-    // Teraの生成
-    let tera = Tera::new(concat!(env!("CARGO_MANIFEST_DIR"), "/views/**/*")).unwrap();
-    // SeaOrmのDatabaseConnectionを取得
-    let pool = SeaOrmPool::get().await;
-    // アプリケーションサービスプロバイダの生成
-    let provider = AppServiceProvider::new();
-    // ランダムな署名/暗号化キーを生成
-    let key = cookie::Key::generate();
-    // RedisSessionStoreを生成する
-    let redis_store = RedisSessionStore::new("redis://127.0.0.1:6379").await.unwrap();
+    // サブコマンドが指定された場合は実行して終了する
+    let cli = Cli::parse();
+    if let Some(command) = cli.command {
+        return match command.run().await {
+            Ok(()) => Ok(()) ,
+            Err(errors) => Startup::exit(errors)
+        };
+    }
+    // Tera、DatabaseConnection、セッションストア、証明書を検証して生成する
+    // --demoの場合はPostgreSQL、Redisを利用せずインメモリで起動する
+    let initialized = match cli.demo {
+        true => Startup::initialize_demo().await ,
+        false => Startup::initialize().await
+    };
+    let resources = match initialized {
+        Ok(resources) => resources ,
+        Err(errors) => Startup::exit(errors)
+    };
+    let tera = resources.tera;
+    let translator = resources.translator;
+    let pool = resources.pool;
+    let session = resources.session;
+    let redis_client = resources.redis_client;
+    let assets = resources.assets;
+    let security_headers = SecurityHeaders::new(resources.security);
+    let rate_limiter = resources.rate_limiter;
+    let admin_users = resources.admin_users;
+    let jwt = resources.jwt;
+    let password_policy = resources.password_policy;
+    let revocation = resources.revocation;
+    let mailer = resources.mailer;
+    let password_reset = resources.password_reset;
+    let provider = resources.provider;
+    let accounts = resources.accounts;
+    let audit_log = resources.audit_log;
+    let listen = resources.listen;
+    let trusted_proxies = resources.trusted_proxies;
+    // HTTPを待ち受ける場合はHTTPSのポートにリダイレクトする
+    let https_redirect = HttpsRedirect::new(listen.redirect_port.map(|_| listen.port));
 
     /*  サーバーの実行 */
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(middleware::Logger::default())// ロギングミドルウェアの登録
-            /* セッションミドルウェア(Redis)の登録*/
-            .wrap(
-                SessionMiddleware::builder(
-                    // RedisSessionStoreとKeyを設定する
-                    redis_store.clone() , key.clone())
-                    .session_lifecycle(
-                        // SessionのライフサイクルをPersistenceSessionに設定する 有効期間を5分にする
-                        PersistentSession::default().session_ttl(Duration::minutes(5))
-                    )
-                    // SessionIdの名称をrssessionIdに設定する
-                    .cookie_name("rsessionid".to_string()).build()
-            )
+            .wrap(rate_limiter.clone()) // レート制限ミドルウェアの登録
+            .wrap(security_headers.clone()) // セキュリティヘッダーミドルウェアの登録
+            .wrap(https_redirect) // HTTPSリダイレクトミドルウェアの登録
+            .wrap(RequestIdentifier) // リクエストIdミドルウェアの登録
+            // ロギングミドルウェアの登録(接続元は信頼するプロキシが転送したクライアントのアドレス)
+            .wrap(middleware::Logger::new(LOG_FORMAT)
+                .custom_request_replace("client_ip", |request| ClientInfo::remote_ip(request.request())
+                    .unwrap_or_else(|| String::from("-"))))
+            /* セッションミドルウェア(SESSION_STOREで選択したストア)の登録*/
+            .wrap(session.middleware())
+            // 転送ヘッダーミドルウェアの登録
+            .wrap(ForwardedHeaders::new(trusted_proxies.clone()))
             // Teraの登録
             .app_data(web::Data::new(tera.clone()))
+            // メッセージカタログの登録
+            .app_data(web::Data::new(translator.clone()))
+            // 静的ファイルの登録
+            .app_data(web::Data::new(assets.clone()))
             // DatabaseConnectionの登録
             .app_data(web::Data::new(pool.clone()))
             // アプリケーションサービスプロバイダの登録
-            .app_data(web::Data::new(provider.clone()))
+            .app_data(web::Data::from(provider.clone()))
+            // アカウントのストアの登録
+            .app_data(web::Data::from(accounts.clone()))
+            // 監査ログのストアの登録
+            .app_data(web::Data::from(audit_log.clone()))
+            // Redisクライアントの登録(Redisを利用しない構成ではNone)
+            .app_data(web::Data::new(redis_client.clone()))
+            // セッションストアの登録(Readinessチェック用)
+            .app_data(web::Data::new(session.store()))
+            // 管理者ユーザーの登録
+            .app_data(web::Data::new(admin_users.clone()))
+            // JWTの署名鍵と検証鍵の登録
+            .app_data(web::Data::new(jwt.clone()))
+            // パスワードポリシーの登録
+            .app_data(web::Data::new(password_policy.clone()))
+            // トークン失効の登録
+            .app_data(web::Data::new(revocation.clone()))
+            // メール送信の登録
+            .app_data(web::Data::from(mailer.clone()))
+            // パスワード再設定の設定の登録
+            .app_data(web::Data::new(password_reset.clone()))
             // サービスの登録
             .configure(set_config)
-    }).bind_openssl("127.0.0.1:8081", create_ssl_acceptor_builder())?.run().await
-}
-
-///
-/// OpenSSL SslAcceptorBuilderの生成
-///
-fn create_ssl_acceptor_builder() -> SslAcceptorBuilder {
-    // OpenSSL構造を管理し、暗号スイート、セッションオプションなどを構成する
-    let mut builder: SslAcceptorBuilder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server()).unwrap();
-    // 秘密鍵の設定
-    builder.set_private_key_file("localhost+2-key.pem", SslFiletype::PEM).unwrap();
-    // 証明書の設定
-    builder.set_certificate_chain_file("localhost+2.pem").unwrap();
-    builder
-}
-
-///
-/// サービスの設定
-///
-pub fn set_config(config: &mut web::ServiceConfig){
-    use web_sample::handler::view_commons::{ErrorHandler, MenuHandler};
-    use web_sample::handler::product_search::ProductSearchHandler;
-    use web_sample::handler::product_register::ProductRegisterHandler;
-    use web_sample::handler::authenticate::AuthenticateHandler;
-    config.service(web::scope("/web_sample")
-            //   ログイン認証
-            .service(resource("/login")
-                .route(web::get().to(AuthenticateHandler::enter))
-                .route(web::post().to(AuthenticateHandler::authenticate)))
-            // メニュー
-            .route("/menu",web::get().to(MenuHandler::menu))
-            // 商品キーワード検索
-            .service(resource("/search/product")
-                .route(web::get().to(ProductSearchHandler::enter))
-                .route(web::post().to(ProductSearchHandler::result)))
-            // 商品登録
-            .service(resource("/register/product")
-                .route(web::get().to(ProductRegisterHandler::enter))
-                .route(web::post().to(ProductRegisterHandler::complete)))
-                .route("/register/product/finish" , web::get().to(ProductRegisterHandler::finish))
-            // 内部エラー
-            .route("/error" , web::get().to(ErrorHandler::error))
-        )
-        // デフォルトページ
-        .default_service(web::get().to(MenuHandler::menu)
-    );
-}
+    });
+    // TLS、またはTLSを上流で終端する場合は平文のHTTPで待ち受ける
+    // TLSのバックエンドはopenssl、rustlsフィーチャーで選択する(両方の場合はrustls、どちらもない場合は平文のみ)
+    let server = match resources.tls {
+        #[cfg(all(feature = "openssl", not(feature = "rustls")))]
+        Some(tls) => server.bind_openssl((listen.host.as_str(), listen.port), tls) ,
+        #[cfg(feature = "rustls")]
+        Some(tls) => server.bind_rustls((listen.host.as_str(), listen.port), tls) ,
+        #[cfg(not(any(feature = "openssl", feature = "rustls")))]
+        Some(tls) => match tls {} ,
+        None => server.bind((listen.host.as_str(), listen.port))
+    };
+    // HTTPSにリダイレクトするHTTPの待ち受け
+    let server = match listen.redirect_port {
+        Some(redirect_port) => server.and_then(|server| server.bind((listen.host.as_str(), redirect_port))) ,
+        None => server
+    };
+    match server {
+        Ok(server) => server.run().await ,
+        // ポート使用中などでバインドできない場合
+        Err(error) => Startup::exit(vec![StartupError::Bind(error.to_string())])
+    }
+}
\ No newline at end of file
@@ -0,0 +1,195 @@
+use std::future::{ready, Future, Ready};
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use actix_web::{Error, HttpMessage, HttpRequest};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, HeaderName};
+use crate::config::EnvConfig;
+use crate::startup::StartupError;
+
+///
+/// 信頼するプロキシのアドレス範囲
+///
+#[derive(Debug, Clone, Copy)]
+struct IpRange {
+    network: IpAddr ,
+    prefix:  u8        // プレフィックス長
+}
+impl IpRange {
+    // 10.0.0.1 または 10.0.0.0/8 の形式から生成する
+    fn parse(value: &str) -> Option<Self> {
+        let (address, prefix) = match value.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)) ,
+            None => (value, None)
+        };
+        let network = address.trim().parse::<IpAddr>().ok()?;
+        let bits = Self::bits(&network);
+        let prefix = match prefix {
+            Some(prefix) => prefix.trim().parse::<u8>().ok().filter(|prefix| *prefix <= bits)? ,
+            None => bits
+        };
+        Some(Self { network , prefix })
+    }
+    fn bits(address: &IpAddr) -> u8 {
+        if address.is_ipv4() { 32 } else { 128 }
+    }
+    fn contains(&self, address: &IpAddr) -> bool {
+        let (network, address) = match (self.network, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => (u32::from(network) as u128, u32::from(*address) as u128) ,
+            (IpAddr::V6(network), IpAddr::V6(address)) => (u128::from(network), u128::from(*address)) ,
+            _ => return false
+        };
+        let shift = (Self::bits(&self.network) - self.prefix) as u32;
+        shift >= 128 || network >> shift == address >> shift
+    }
+}
+
+///
+/// 信頼するプロキシ
+/// 接続元が信頼するプロキシの場合のみX-Forwarded-Proto、X-Forwarded-Forを採用する
+///
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    ranges: Arc<Vec<IpRange>>
+}
+impl TrustedProxies {
+    // 信頼するプロキシのIPアドレス、またはCIDR(カンマ区切り)
+    const TRUSTED_PROXIES_KEY: &'static str = "TRUSTED_PROXIES";
+    ///
+    /// 環境変数から生成する
+    /// 未設定の場合は転送ヘッダーを採用しない
+    ///
+    pub fn from_env() -> Result<Self, StartupError> {
+        Self::parse(&EnvConfig::string(Self::TRUSTED_PROXIES_KEY, ""))
+    }
+    ///
+    /// カンマ区切りのIPアドレス、CIDRから生成する
+    ///
+    pub fn parse(value: &str) -> Result<Self, StartupError> {
+        let ranges = value.split(',').map(str::trim).filter(|range| !range.is_empty())
+            .map(|range| IpRange::parse(range).ok_or_else(|| StartupError::Config(
+                format!("{}の値'{}'はIPアドレスまたはCIDRを指定してください", Self::TRUSTED_PROXIES_KEY, range))))
+            .collect::<Result<Vec<IpRange>, StartupError>>()?;
+        Ok(Self { ranges: Arc::new(ranges) })
+    }
+    pub fn contains(&self, address: &IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(address))
+    }
+
+    // X-Forwarded-Forを右から辿り、信頼するプロキシ以外の最初のアドレスをクライアントとする
+    fn client_ip(&self, request: &ServiceRequest) -> Option<IpAddr> {
+        let forwarded = request.headers().get_all(HeaderName::from_static(ForwardedHeaders::X_FORWARDED_FOR))
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|address| address.trim().parse::<IpAddr>().ok())
+            .collect::<Option<Vec<IpAddr>>>()?;
+        forwarded.iter().rev().find(|address| !self.contains(address))
+            .or_else(|| forwarded.first())
+            .copied()
+    }
+    // X-Forwarded-Protoの先頭の値がhttpsか
+    fn forwarded_secure(request: &ServiceRequest) -> Option<bool> {
+        request.headers().get(HeaderName::from_static(ForwardedHeaders::X_FORWARDED_PROTO))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(|proto| proto.trim().eq_ignore_ascii_case("https"))
+    }
+}
+
+///
+/// クライアントの接続情報
+/// 信頼するプロキシを経由した場合はプロキシが転送した値とする
+///
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip:     Option<IpAddr> ,   // クライアントのIPアドレス
+    pub secure: bool               // HTTPSで接続したか
+}
+impl ClientInfo {
+    ///
+    /// リクエストのクライアントのIPアドレス
+    /// ForwardedHeadersを登録していない場合は接続元のアドレスとする
+    ///
+    pub fn remote_ip(request: &HttpRequest) -> Option<String> {
+        match request.extensions().get::<ClientInfo>() {
+            Some(client) => client.ip.map(|ip| ip.to_string()) ,
+            None => request.peer_addr().map(|addr| addr.ip().to_string())
+        }
+    }
+    ///
+    /// リクエストがHTTPSで送信されたか
+    ///
+    pub fn is_secure(request: &HttpRequest) -> bool {
+        match request.extensions().get::<ClientInfo>() {
+            Some(client) => client.secure ,
+            None => request.app_config().secure()
+        }
+    }
+}
+
+///
+/// 転送ヘッダーミドルウェア
+/// クライアントの接続情報をリクエストに格納する
+/// 信頼するプロキシ以外から送信された転送ヘッダーは削除し、ConnectionInfoの詐称を防ぐ
+///
+#[derive(Clone, Default)]
+pub struct ForwardedHeaders {
+    proxies: TrustedProxies
+}
+impl ForwardedHeaders {
+    const X_FORWARDED_FOR: &'static str = "x-forwarded-for";
+    const X_FORWARDED_PROTO: &'static str = "x-forwarded-proto";
+    const X_FORWARDED_HOST: &'static str = "x-forwarded-host";
+    pub fn new(proxies: TrustedProxies) -> Self {
+        Self { proxies }
+    }
+}
+impl<S, B> Transform<S, ServiceRequest> for ForwardedHeaders
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = ForwardedHeadersMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ForwardedHeadersMiddleware { service , proxies: self.proxies.clone() }))
+    }
+}
+
+pub struct ForwardedHeadersMiddleware<S> {
+    service: S ,
+    proxies: TrustedProxies
+}
+impl<S, B> Service<ServiceRequest> for ForwardedHeadersMiddleware<S>
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut request: ServiceRequest) -> Self::Future {
+        let peer = request.peer_addr().map(|addr| addr.ip());
+        let direct_secure = request.app_config().secure();
+        let client = match peer {
+            Some(peer) if self.proxies.contains(&peer) => ClientInfo {
+                ip: self.proxies.client_ip(&request).or(Some(peer)) ,
+                secure: TrustedProxies::forwarded_secure(&request).unwrap_or(direct_secure)
+            },
+            _ => {
+                let headers = request.headers_mut();
+                headers.remove(header::FORWARDED);
+                for name in [ForwardedHeaders::X_FORWARDED_FOR, ForwardedHeaders::X_FORWARDED_PROTO, ForwardedHeaders::X_FORWARDED_HOST] {
+                    headers.remove(HeaderName::from_static(name));
+                }
+                ClientInfo { ip: peer , secure: direct_secure }
+            }
+        };
+        request.extensions_mut().insert(client);
+        Box::pin(self.service.call(request))
+    }
+}
\ No newline at end of file
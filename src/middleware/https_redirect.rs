@@ -0,0 +1,85 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use actix_web::{Error, HttpResponse};
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, Method};
+use crate::middleware::forwarded::ClientInfo;
+
+///
+/// HTTPSリダイレクトミドルウェア
+/// HTTPで受け付けたリクエストを同じホストのHTTPSのポートにリダイレクトする
+/// リダイレクト先のポートを指定しない場合は何もしない
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpsRedirect {
+    https_port: Option<u16>
+}
+impl HttpsRedirect {
+    const DEFAULT_HTTPS_PORT: u16 = 443;
+    pub fn new(https_port: Option<u16>) -> Self {
+        Self { https_port }
+    }
+    // リダイレクト先のURL Hostヘッダーのポートは置き換える
+    fn location(request: &ServiceRequest, https_port: u16) -> String {
+        let connection = request.connection_info();
+        let host = connection.host();
+        let host = match host.strip_prefix('[') {
+            // IPv6アドレス
+            Some(rest) => format!("[{}]", rest.split(']').next().unwrap_or_default()) ,
+            None => host.split(':').next().unwrap_or_default().to_string()
+        };
+        let path = request.uri().path_and_query().map_or("/", |path| path.as_str());
+        match https_port {
+            Self::DEFAULT_HTTPS_PORT => format!("https://{}{}", host, path) ,
+            port => format!("https://{}:{}{}", host, port, path)
+        }
+    }
+}
+impl<S, B> Transform<S, ServiceRequest> for HttpsRedirect
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = HttpsRedirectMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HttpsRedirectMiddleware { service , https_port: self.https_port }))
+    }
+}
+
+pub struct HttpsRedirectMiddleware<S> {
+    service:    S ,
+    https_port: Option<u16>
+}
+impl<S, B> Service<ServiceRequest> for HttpsRedirectMiddleware<S>
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        match self.https_port {
+            Some(https_port) if !ClientInfo::is_secure(request.request()) => {
+                // GET、HEAD以外はメソッドと本文を維持させるため308とする
+                let mut response = match request.method() == Method::GET || request.method() == Method::HEAD {
+                    true => HttpResponse::MovedPermanently() ,
+                    false => HttpResponse::PermanentRedirect()
+                };
+                let response = response
+                    .insert_header((header::LOCATION, HttpsRedirect::location(&request, https_port)))
+                    .finish();
+                Box::pin(ready(Ok(request.into_response(response).map_into_right_body())))
+            },
+            _ => {
+                let future = self.service.call(request);
+                Box::pin(async move { future.await.map(ServiceResponse::map_into_left_body) })
+            }
+        }
+    }
+}
\ No newline at end of file
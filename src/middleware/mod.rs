@@ -0,0 +1,5 @@
+pub mod security_headers;
+pub mod rate_limit;
+pub mod request_id;
+pub mod forwarded;
+pub mod https_redirect;
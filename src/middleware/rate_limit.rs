@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use actix_web::{Error, HttpMessage, HttpResponse, rt, web};
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, Method};
+use async_trait::async_trait;
+use log::warn;
+use redis::aio::ConnectionManager;
+use crate::i18n::Locale;
+use crate::jwt::WebJwt;
+use crate::middleware::forwarded::ClientInfo;
+use crate::middleware::security_headers::CspNonce;
+use crate::startup::StartupError;
+use crate::template::TemplateEngine;
+
+///
+/// ルート毎の制限(トークンバケット)
+///
+#[derive(Debug, Clone)]
+pub struct RateLimitRule {
+    method:   Method ,
+    path:     String ,
+    capacity: u32 ,    // バケットの容量(連続して受け付けるリクエスト数)
+    period:   u64      // 容量分のトークンが補充されるまでの秒数
+}
+impl RateLimitRule {
+    pub const DEFAULT_RULES: &'static str =
+        "POST /web_sample/login=5/60;POST /web_sample/search/product=30/60;POST /web_sample/register/product=10/60;\
+         POST /web_sample/password/forgot=3/300;POST /web_sample/login/mfa=5/60;POST /csp-report=20/60";
+    ///
+    /// 設定値からルールを生成する
+    /// 書式: <METHOD> <PATH>=<容量>/<秒数> をセミコロンで区切る
+    ///
+    pub fn parse_all(value: &str) -> Result<Vec<Self>, StartupError> {
+        value.split(';').map(str::trim).filter(|rule| !rule.is_empty()).map(Self::parse).collect()
+    }
+    fn parse(value: &str) -> Result<Self, StartupError> {
+        let error = || StartupError::Config(format!("レート制限'{}'の書式が不正です", value));
+        let (route, limit) = value.split_once('=').ok_or_else(error)?;
+        let (method, path) = route.trim().split_once(' ').ok_or_else(error)?;
+        let (capacity, period) = limit.trim().split_once('/').ok_or_else(error)?;
+        let rule = Self {
+            method: Method::from_bytes(method.as_bytes()).map_err(|_| error())? ,
+            path: path.trim().to_string() ,
+            capacity: capacity.trim().parse().map_err(|_| error())? ,
+            period: period.trim().parse().map_err(|_| error())?
+        };
+        if rule.capacity == 0 || rule.period == 0 {
+            return Err(error());
+        }
+        Ok(rule)
+    }
+    // 1秒あたりの補充トークン数
+    fn refill_rate(&self) -> f64 {
+        self.capacity as f64 / self.period as f64
+    }
+}
+
+///
+/// レート制限の状態を保持するストア
+///
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    ///
+    /// トークンを1つ消費する
+    /// 受け付け可能な場合はNone、制限中の場合は再試行までの秒数を返す
+    ///
+    async fn acquire(&self, key: &str, rule: &RateLimitRule) -> Result<Option<u64>, String>;
+}
+
+///
+/// インメモリストア(単一ノード、テスト用)
+///
+pub struct MemoryRateLimitStore {
+    buckets: Arc<Mutex<HashMap<String, (f64, Instant, u64)>>>   // キーと(残トークン数,更新日時,補充秒数)
+}
+impl Default for MemoryRateLimitStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl MemoryRateLimitStore {
+    // 補充期間を過ぎたバケットを破棄する間隔
+    const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+    ///
+    /// ストアを生成し、補充期間を過ぎたバケットを定期的に破棄する
+    /// 破棄処理はストアが破棄されると終了する
+    ///
+    pub fn new() -> Self {
+        let store = Self { buckets: Arc::default() };
+        let buckets = Arc::downgrade(&store.buckets);
+        rt::spawn(async move {
+            let mut interval = rt::time::interval(Self::SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let buckets = match buckets.upgrade() {
+                    Some(buckets) => Self { buckets } ,
+                    None => break
+                };
+                buckets.sweep();
+            }
+        });
+        store
+    }
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, (f64, Instant, u64)>> {
+        self.buckets.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    // 補充期間を過ぎたバケットは満杯と同じため破棄する
+    fn sweep(&self) {
+        let now = Instant::now();
+        self.lock().retain(|_, (_, updated, period)| now.duration_since(*updated).as_secs() <= *period);
+    }
+}
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn acquire(&self, key: &str, rule: &RateLimitRule) -> Result<Option<u64>, String> {
+        let now = Instant::now();
+        let capacity = rule.capacity as f64;
+        let mut buckets = self.lock();
+        let (tokens, updated, _) = buckets.entry(key.to_string()).or_insert((capacity, now, rule.period));
+        *tokens = capacity.min(*tokens + now.duration_since(*updated).as_secs_f64() * rule.refill_rate());
+        *updated = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(None)
+        } else {
+            Ok(Some(((1.0 - *tokens) / rule.refill_rate()).ceil() as u64))
+        }
+    }
+}
+
+///
+/// Redisストア(複数レプリカ間で状態を共有する)
+///
+pub struct RedisRateLimitStore {
+    connection: ConnectionManager ,
+    script:     redis::Script
+}
+impl RedisRateLimitStore {
+    const KEY_PREFIX: &'static str = "rate_limit:";
+    // トークンバケットをアトミックに更新するスクリプト 戻り値は再試行までの秒数(0は受付可)
+    const SCRIPT: &'static str = r#"
+        local capacity = tonumber(ARGV[1])
+        local rate = tonumber(ARGV[2])
+        local now = tonumber(ARGV[3])
+        local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated')
+        local tokens = tonumber(bucket[1]) or capacity
+        local updated = tonumber(bucket[2]) or now
+        tokens = math.min(capacity, tokens + math.max(0, now - updated) / 1000 * rate)
+        local retry = 0
+        if tokens >= 1 then
+            tokens = tokens - 1
+        else
+            retry = math.ceil((1 - tokens) / rate)
+        end
+        redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated', now)
+        redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / rate * 1000))
+        return retry
+    "#;
+    pub async fn new(client: redis::Client) -> redis::RedisResult<Self> {
+        Ok(Self { connection: ConnectionManager::new(client).await?, script: redis::Script::new(Self::SCRIPT) })
+    }
+}
+#[async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn acquire(&self, key: &str, rule: &RateLimitRule) -> Result<Option<u64>, String> {
+        let mut connection = self.connection.clone();
+        let retry: u64 = self.script
+            .key(format!("{}{}", Self::KEY_PREFIX, key))
+            .arg(rule.capacity)
+            .arg(rule.refill_rate())
+            .arg(chrono::Utc::now().timestamp_millis())
+            .invoke_async(&mut connection).await
+            .map_err(|error| error.to_string())?;
+        Ok(if retry == 0 { None } else { Some(retry) })
+    }
+}
+
+///
+/// レート制限ミドルウェア
+/// 認証済みの場合はユーザーId、未認証の場合はIPアドレス毎に制限する
+///
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore> ,
+    rules: Arc<Vec<RateLimitRule>>
+}
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, rules: Vec<RateLimitRule>) -> Self {
+        Self { store, rules: Arc::new(rules) }
+    }
+}
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimiterMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimiterMiddleware { service: Rc::new(service), limiter: self.clone() }))
+    }
+}
+
+pub struct RateLimiterMiddleware<S> {
+    service: Rc<S> ,
+    limiter: RateLimiter
+}
+impl<S> RateLimiterMiddleware<S> {
+    const VIEW_PATH: &'static str = "pages/error/too_many_requests.html";
+    // 制限のキーを生成する
+    fn key(request: &ServiceRequest, rule: &RateLimitRule) -> String {
+        let route = format!("{} {}", rule.method, rule.path);
+        let user_id = request.app_data::<web::Data<WebJwt>>()
+            .and_then(|decoder| decoder.parse_header(request.request()).ok()
+                .and_then(|token| decoder.decode(&token).ok()))
+            .map(|claims| claims.user_id().to_string());
+        match user_id {
+            Some(user_id) => format!("{}:user:{}", route, user_id) ,
+            None => {
+                let ip = ClientInfo::remote_ip(request.request()).unwrap_or_else(|| String::from("unknown"));
+                format!("{}:ip:{}", route, ip)
+            }
+        }
+    }
+    // 429レスポンスを生成する
+    fn too_many_requests(request: &ServiceRequest, retry_after: u64) -> HttpResponse {
+        let mut context = tera::Context::new();
+        context.insert("retry_after", &retry_after);
+        context.insert("lang", Locale::resolve(request.request()).as_str());
+        context.insert("csp_nonce", &request.extensions().get::<CspNonce>().map(|nonce| nonce.0.clone()).unwrap_or_default());
+        let body = request.app_data::<web::Data<TemplateEngine>>()
+            .and_then(|tera| tera.render(Self::VIEW_PATH, &context).ok())
+            .unwrap_or_else(|| String::from("Too Many Requests"));
+        HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+            .content_type(mime::TEXT_HTML_UTF_8)
+            .body(body)
+    }
+}
+impl<S, B> Service<ServiceRequest> for RateLimiterMiddleware<S>
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let limiter = self.limiter.clone();
+        Box::pin(async move {
+            let rule = limiter.rules.iter()
+                .find(|rule| rule.method == request.method() && rule.path == request.path());
+            if let Some(rule) = rule {
+                let key = Self::key(&request, rule);
+                match limiter.store.acquire(&key, rule).await {
+                    Ok(None) => () ,
+                    Ok(Some(retry_after)) => {
+                        let response = Self::too_many_requests(&request, retry_after);
+                        return Ok(request.into_response(response).map_into_right_body());
+                    },
+                    // ストアの障害時はリクエストを受け付ける
+                    Err(error) => warn!("rate limit store error: {}", error)
+                }
+            }
+            service.call(request).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use actix_web::{Error, HttpMessage};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+
+///
+/// リクエストId
+/// X-Request-Idヘッダーの値、存在しない場合は生成したUUID
+///
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+impl RequestId {
+    pub const HEADER: &'static str = "x-request-id";
+    // 受け入れるヘッダー値の最大長
+    const MAX_LENGTH: usize = 64;
+    fn from_request(request: &ServiceRequest) -> Self {
+        let value = request.headers().get(Self::HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty() && value.len() <= Self::MAX_LENGTH
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        match value {
+            Some(value) => Self(value.to_string()) ,
+            None => Self(uuid::Uuid::new_v4().to_string())
+        }
+    }
+}
+
+///
+/// リクエストIdミドルウェア
+/// リクエストIdをリクエストに格納し、レスポンスヘッダーに設定する
+///
+#[derive(Clone, Default)]
+pub struct RequestIdentifier;
+impl<S, B> Transform<S, ServiceRequest> for RequestIdentifier
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdentifierMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdentifierMiddleware { service }))
+    }
+}
+
+pub struct RequestIdentifierMiddleware<S> {
+    service: S
+}
+impl<S, B> Service<ServiceRequest> for RequestIdentifierMiddleware<S>
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let request_id = RequestId::from_request(&request);
+        request.extensions_mut().insert(request_id.clone());
+        let future = self.service.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+                response.headers_mut().insert(HeaderName::from_static(RequestId::HEADER), value);
+            }
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::sync::Arc;
+use actix_web::{Error, HttpMessage};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use rand::RngCore;
+use crate::config::{AppEnvironment, EnvConfig};
+use crate::startup::StartupError;
+
+///
+/// リクエスト毎に生成するCSPのnonce
+/// テンプレートからは csp_nonce として参照する
+///
+#[derive(Debug, Clone)]
+pub struct CspNonce(pub String);
+impl CspNonce {
+    fn generate() -> Self {
+        let mut bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self(base64::encode(bytes))
+    }
+}
+
+///
+/// セキュリティヘッダーの設定
+///
+#[derive(Debug, Clone)]
+pub struct SecurityPolicy {
+    csp_report_only:    bool ,                 // CSPを報告のみとする
+    hsts:               Option<HeaderValue> ,  // Strict-Transport-Security
+    frame_options:      HeaderValue ,          // X-Frame-Options
+    referrer_policy:    HeaderValue ,          // Referrer-Policy
+    permissions_policy: HeaderValue            // Permissions-Policy
+}
+impl SecurityPolicy {
+    pub const REPORT_PATH: &'static str = "/csp-report";
+    ///
+    /// 実行環境と環境変数から設定を生成する
+    /// 開発環境ではHSTSを送出せず、CSPを報告のみとする
+    ///
+    pub fn from_env(environment: AppEnvironment) -> Result<Self, StartupError> {
+        let development = environment == AppEnvironment::Development;
+        let csp_report_only = EnvConfig::bool("CSP_REPORT_ONLY", development)?;
+        let hsts_max_age = EnvConfig::u64("HSTS_MAX_AGE", if development { 0 } else { 31_536_000 })?;
+        let hsts = match hsts_max_age {
+            0 => None ,
+            max_age => Some(Self::header_value("HSTS_MAX_AGE",
+                format!("max-age={}; includeSubDomains", max_age))?)
+        };
+        Ok(Self {
+            csp_report_only ,
+            hsts ,
+            frame_options: Self::header_value("FRAME_OPTIONS",
+                EnvConfig::string("FRAME_OPTIONS", "DENY"))? ,
+            referrer_policy: Self::header_value("REFERRER_POLICY",
+                EnvConfig::string("REFERRER_POLICY", "strict-origin-when-cross-origin"))? ,
+            permissions_policy: Self::header_value("PERMISSIONS_POLICY",
+                EnvConfig::string("PERMISSIONS_POLICY", "camera=(), microphone=(), geolocation=(), payment=()"))?
+        })
+    }
+    // nonceを含むContent-Security-Policyを生成する
+    fn content_security_policy(nonce: &str) -> String {
+        format!("default-src 'self'; script-src 'self' 'nonce-{0}'; style-src 'self' 'nonce-{0}'; \
+            img-src 'self' data:; object-src 'none'; base-uri 'self'; form-action 'self'; \
+            frame-ancestors 'none'; report-uri {1}", nonce, Self::REPORT_PATH)
+    }
+    fn header_value(key: &str, value: String) -> Result<HeaderValue, StartupError> {
+        HeaderValue::from_str(&value)
+            .map_err(|_| StartupError::Config(format!("{}の値'{}'はヘッダーに設定できません", key, value)))
+    }
+    // レスポンスにヘッダーを設定する
+    fn apply(&self, headers: &mut header::HeaderMap, nonce: &str) {
+        if let Ok(csp) = HeaderValue::from_str(&Self::content_security_policy(nonce)) {
+            let name = if self.csp_report_only {
+                header::CONTENT_SECURITY_POLICY_REPORT_ONLY
+            } else {
+                header::CONTENT_SECURITY_POLICY
+            };
+            headers.insert(name, csp);
+        }
+        if let Some(hsts) = &self.hsts {
+            headers.insert(header::STRICT_TRANSPORT_SECURITY, hsts.clone());
+        }
+        headers.insert(header::X_FRAME_OPTIONS, self.frame_options.clone());
+        headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+        headers.insert(header::REFERRER_POLICY, self.referrer_policy.clone());
+        headers.insert(HeaderName::from_static("permissions-policy"), self.permissions_policy.clone());
+    }
+}
+
+///
+/// セキュリティヘッダーミドルウェア
+///
+#[derive(Clone)]
+pub struct SecurityHeaders {
+    policy: Arc<SecurityPolicy>
+}
+impl SecurityHeaders {
+    pub fn new(policy: SecurityPolicy) -> Self {
+        Self { policy: Arc::new(policy) }
+    }
+}
+impl<S, B> Transform<S, ServiceRequest> for SecurityHeaders
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SecurityHeadersMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SecurityHeadersMiddleware { service, policy: self.policy.clone() }))
+    }
+}
+
+pub struct SecurityHeadersMiddleware<S> {
+    service: S ,
+    policy:  Arc<SecurityPolicy>
+}
+impl<S, B> Service<ServiceRequest> for SecurityHeadersMiddleware<S>
+    where S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static ,
+          S::Future: 'static , B: 'static {
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        // nonceを生成してリクエストに格納する
+        let nonce = CspNonce::generate();
+        request.extensions_mut().insert(nonce.clone());
+        let policy = self.policy.clone();
+        let future = self.service.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            policy.apply(response.headers_mut(), &nonce.0);
+            Ok(response)
+        })
+    }
+}
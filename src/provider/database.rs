@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+use app_commons::application::sea_orm::provider_impl::AppServiceProvider;
+use app_commons::application::transfers::{CategoryDto, ProductDto, UserDto};
+use app_commons::error::AppError;
+use app_commons::presentation::forms::{LoginForm, ProductRegisterForm, ProductSearchForm};
+use crate::i18n::LocalizedMessage;
+use super::{ProviderError, ServiceProvider};
+
+///
+/// SeaORM(PostgreSQL)によるサービスプロバイダ
+/// app_commonsのAppServiceProviderに起動時に生成したDatabaseConnectionを渡す
+///
+pub struct DatabaseServiceProvider {
+    pool:     Arc<DatabaseConnection> ,
+    provider: Arc<AppServiceProvider>
+}
+impl DatabaseServiceProvider {
+    pub fn new(pool: Arc<DatabaseConnection>) -> Self {
+        Self { pool , provider: AppServiceProvider::new() }
+    }
+    // app_commonsのメッセージ(日本語)を画面に出力するメッセージに置き換える 内部エラーはそのまま通知する
+    fn message(error: AppError, message: LocalizedMessage) -> ProviderError {
+        match error {
+            AppError::AuthenticateError(_) => ProviderError::AuthenticateError(message) ,
+            AppError::SearchError(_) => ProviderError::SearchError(message) ,
+            AppError::RegisterError(_) => ProviderError::RegisterError(message) ,
+            error => ProviderError::from(error)
+        }
+    }
+}
+#[async_trait(?Send)]
+impl ServiceProvider for DatabaseServiceProvider {
+    async fn authenticate(&self, form: &LoginForm) -> Result<UserDto, ProviderError> {
+        self.provider.authenticate_service.execute(&self.pool , form).await
+            .map_err(|error| Self::message(error, LocalizedMessage::new("login-failed")))
+    }
+    async fn search(&self, form: &ProductSearchForm) -> Result<Vec<ProductDto>, ProviderError> {
+        self.provider.search_service.search(&self.pool , form).await
+            .map_err(|error| Self::message(error, LocalizedMessage::new("product-not-found").arg("keyword", form.keyword.trim())))
+    }
+    async fn register(&self, form: &ProductRegisterForm) -> Result<ProductDto, ProviderError> {
+        self.provider.register_service.execute(&self.pool , form).await
+            .map_err(|error| Self::message(error, LocalizedMessage::new("product-exists").arg("name", form.name.trim())))
+    }
+    async fn categories(&self) -> Result<Vec<CategoryDto>, ProviderError> {
+        self.provider.register_service.categories(&self.pool).await.map_err(ProviderError::from)
+    }
+}
\ No newline at end of file
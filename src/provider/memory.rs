@@ -0,0 +1,132 @@
+use std::sync::{Arc, PoisonError, RwLock};
+use async_trait::async_trait;
+use log::error;
+use app_commons::application::transfers::{CategoryDto, ProductDto, UserDto};
+use app_commons::presentation::forms::{LoginForm, ProductRegisterForm, ProductSearchForm};
+use crate::account::Password;
+use crate::account::store::{AccountStore, MemoryAccountStore};
+use crate::i18n::LocalizedMessage;
+use super::{ProviderError, ServiceProvider};
+
+///
+/// インメモリのサービスプロバイダ(テスト、デモ用)
+/// ユーザーはMemoryAccountStoreに登録されたアカウントで認証する
+///
+#[derive(Default)]
+pub struct MemoryServiceProvider {
+    categories: Vec<CategoryDto> ,
+    products:   RwLock<Vec<ProductDto>> ,
+    accounts:   Option<Arc<MemoryAccountStore>>   // 認証に利用するアカウント
+}
+impl MemoryServiceProvider {
+    // 認証失敗、カテゴリ不明時のメッセージ
+    const FAILED_MESSAGE: &'static str = "login-failed";
+    const UNKNOWN_CATEGORY_MESSAGE: &'static str = "product-category-unknown";
+    ///
+    /// 商品カテゴリ、商品を持たないプロバイダを生成する
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+    ///
+    /// 見本の商品カテゴリ、商品を登録したプロバイダを生成する
+    ///
+    pub fn sample() -> Self {
+        Self::new()
+            .category(1, "文房具")
+            .category(2, "雑貨")
+            .category(3, "パソコン周辺機器")
+            .product("水性ボールペン(黒)", 120, 1)
+            .product("水性ボールペン(赤)", 120, 1)
+            .product("油性ボールペン(黒)", 100, 1)
+            .product("レザーネックストラップ", 1200, 2)
+            .product("無線マウス", 1800, 3)
+            .product("有線キーボード", 2500, 3)
+    }
+    ///
+    /// 認証に利用するアカウントのストアを設定する
+    ///
+    pub fn accounts(mut self, accounts: Arc<MemoryAccountStore>) -> Self {
+        self.accounts = Some(accounts);
+        self
+    }
+    ///
+    /// 商品カテゴリを追加する
+    ///
+    pub fn category(mut self, id: i32, name: &str) -> Self {
+        self.categories.push(CategoryDto { id , name: name.to_string() });
+        self
+    }
+    ///
+    /// 商品を追加する 商品番号は登録順に採番する
+    ///
+    pub fn product(self, name: &str, price: i32, category_id: i32) -> Self {
+        let category = self.find_category(category_id);
+        {
+            let mut products = self.products.write().unwrap_or_else(PoisonError::into_inner);
+            let id = Self::next_id(&products);
+            products.push(ProductDto { id , name: name.to_string() , price , category });
+        }
+        self
+    }
+
+    // カテゴリ番号で商品カテゴリを取得する
+    fn find_category(&self, id: i32) -> Option<CategoryDto> {
+        self.categories.iter().find(|category| category.id == id).cloned()
+    }
+    // 有効なアカウントのユーザー名とパスワードを照合する
+    async fn verify(&self, form: &LoginForm) -> crate::Result<Option<UserDto>> {
+        let model = match &self.accounts {
+            Some(accounts) => accounts.find_by_name(&form.name).await? ,
+            None => None
+        };
+        match model {
+            Some(model) if model.enabled && Password::verify(&form.password, &model.password_hash).await? =>
+                Ok(Some(UserDto { user_id: model.id , user_name: model.user_name })) ,
+            _ => Ok(None)
+        }
+    }
+    // 登録済みの最大の商品番号 + 1
+    fn next_id(products: &[ProductDto]) -> i32 {
+        products.iter().map(|product| product.id).max().unwrap_or(0) + 1
+    }
+}
+#[async_trait(?Send)]
+impl ServiceProvider for MemoryServiceProvider {
+    async fn authenticate(&self, form: &LoginForm) -> Result<UserDto, ProviderError> {
+        match self.verify(form).await {
+            Ok(Some(user)) => Ok(user) ,
+            Ok(None) => Err(ProviderError::AuthenticateError(LocalizedMessage::new(Self::FAILED_MESSAGE))) ,
+            Err(cause) => {
+                error!("in-memory authentication failed: {:?}", cause);
+                Err(ProviderError::AuthenticateError(LocalizedMessage::new(Self::FAILED_MESSAGE)))
+            }
+        }
+    }
+    async fn search(&self, form: &ProductSearchForm) -> Result<Vec<ProductDto>, ProviderError> {
+        let keyword = form.keyword.trim();
+        let results = self.products.read().unwrap_or_else(PoisonError::into_inner).iter()
+            .filter(|product| product.name.contains(keyword))
+            .cloned()
+            .collect::<Vec<ProductDto>>();
+        if results.is_empty() {
+            return Err(ProviderError::SearchError(LocalizedMessage::new("product-not-found").arg("keyword", keyword)));
+        }
+        Ok(results)
+    }
+    async fn register(&self, form: &ProductRegisterForm) -> Result<ProductDto, ProviderError> {
+        let name = form.name.trim().to_string();
+        let category = self.find_category(form.category_id)
+            .ok_or_else(|| ProviderError::RegisterError(LocalizedMessage::new(Self::UNKNOWN_CATEGORY_MESSAGE)))?;
+        let mut products = self.products.write().unwrap_or_else(PoisonError::into_inner);
+        if products.iter().any(|product| product.name == name) {
+            return Err(ProviderError::RegisterError(LocalizedMessage::new("product-exists").arg("name", name.as_str())));
+        }
+        let new_product = ProductDto { id: Self::next_id(&products) , name , price: form.price , category: Some(category) };
+        products.push(new_product.clone());
+        Ok(new_product)
+    }
+    async fn categories(&self) -> Result<Vec<CategoryDto>, ProviderError> {
+        Ok(self.categories.clone())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use thiserror::Error;
+use app_commons::application::transfers::{CategoryDto, ProductDto, UserDto};
+use app_commons::error::AppError;
+use app_commons::presentation::forms::{LoginForm, ProductRegisterForm, ProductSearchForm};
+use crate::i18n::LocalizedMessage;
+
+mod database;
+mod memory;
+pub use database::DatabaseServiceProvider;
+pub use memory::MemoryServiceProvider;
+
+///
+/// サービスプロバイダのエラー
+/// AppErrorと同じ分類で、内部エラー以外は画面に出力するメッセージを持つ
+///
+#[derive(Debug, Error)]
+pub enum ProviderError {
+    #[error("internal error: {0}")]
+    InternalError(String) ,                    // 内部エラー
+    #[error("authenticate error: {0}")]
+    AuthenticateError(LocalizedMessage) ,      // 認証エラー
+    #[error("search error: {0}")]
+    SearchError(LocalizedMessage) ,            // 検索エラー
+    #[error("register error: {0}")]
+    RegisterError(LocalizedMessage)            // 登録エラー
+}
+impl From<AppError> for ProviderError {
+    // app_commonsのメッセージはカタログに無いため、翻訳時はそのまま出力される
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::AuthenticateError(message) => Self::AuthenticateError(LocalizedMessage::new(message)) ,
+            AppError::SearchError(message) => Self::SearchError(LocalizedMessage::new(message)) ,
+            AppError::RegisterError(message) => Self::RegisterError(LocalizedMessage::new(message)) ,
+            error => Self::InternalError(error.to_string())
+        }
+    }
+}
+
+///
+/// アプリケーションサービスプロバイダ
+/// ハンドラはweb::Data<dyn ServiceProvider>で利用し、永続化層の実装に依存しない
+/// Futureはワーカースレッド内で完結するためSendを要求しない
+/// 内部エラー以外のエラーは画面に出力するメッセージを持つ
+///
+#[async_trait(?Send)]
+pub trait ServiceProvider: Send + Sync {
+    // ユーザー名とパスワードで認証する
+    async fn authenticate(&self, form: &LoginForm) -> Result<UserDto, ProviderError>;
+    // キーワードを含む商品を検索する
+    async fn search(&self, form: &ProductSearchForm) -> Result<Vec<ProductDto>, ProviderError>;
+    // 商品を登録する
+    async fn register(&self, form: &ProductRegisterForm) -> Result<ProductDto, ProviderError>;
+    // 商品カテゴリの一覧を取得する
+    async fn categories(&self) -> Result<Vec<CategoryDto>, ProviderError>;
+}
\ No newline at end of file
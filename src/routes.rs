@@ -0,0 +1,122 @@
+use actix_web::web;
+use actix_web::web::resource;
+use actix_web::http::Method;
+use crate::handler::view_commons::{ErrorHandler, MenuHandler};
+use crate::handler::product_search::ProductSearchHandler;
+use crate::handler::product_register::ProductRegisterHandler;
+use crate::handler::authenticate::AuthenticateHandler;
+use crate::handler::health::HealthHandler;
+use crate::handler::static_files::StaticFileHandler;
+use crate::handler::csp_report::CspReportHandler;
+use crate::handler::audit_log::AuditLogHandler;
+use crate::handler::account_admin::AccountAdminHandler;
+use crate::handler::account::AccountHandler;
+use crate::handler::password_reset::PasswordResetHandler;
+use crate::handler::locale::LocaleHandler;
+use crate::middleware::security_headers::SecurityPolicy;
+
+///
+/// ルート
+/// set_config()での登録とroutesサブコマンドでの表示に利用する
+///
+#[derive(Debug, Clone, Copy)]
+pub struct Route {
+    pub method:  &'static str ,
+    pub path:    &'static str ,
+    pub handler: &'static str ,
+    endpoint:    fn() -> web::Route    // メソッドとハンドラを設定したRoute
+}
+// メソッド、パス、ハンドラからRouteを生成する
+macro_rules! route {
+    ($method:ident $path:expr => $handler:ident :: $function:ident) => {
+        Route {
+            method: stringify!($method) ,
+            path: $path ,
+            handler: concat!(stringify!($handler), "::", stringify!($function)) ,
+            endpoint: || web::method(Method::$method).to($handler::$function)
+        }
+    };
+}
+
+///
+/// 登録するルートの一覧
+/// 同じパスのルートは1つのリソースにまとめて、この順序で登録する
+///
+pub const ROUTES: &[Route] = &[
+    // ログイン認証
+    route!(GET "/web_sample/login" => AuthenticateHandler::enter) ,
+    route!(POST "/web_sample/login" => AuthenticateHandler::authenticate) ,
+    // 初回ログイン時のパスワード変更
+    route!(GET "/web_sample/login/password" => AuthenticateHandler::password_enter) ,
+    route!(POST "/web_sample/login/password" => AuthenticateHandler::password_change) ,
+    // 二要素認証のコード入力
+    route!(GET "/web_sample/login/mfa" => AuthenticateHandler::mfa_enter) ,
+    route!(POST "/web_sample/login/mfa" => AuthenticateHandler::mfa_verify) ,
+    // ログアウト
+    route!(POST "/web_sample/logout" => AuthenticateHandler::logout) ,
+    // 表示言語の切り替え
+    route!(GET "/web_sample/locale/{lang}" => LocaleHandler::change) ,
+    // パスワード再設定(認証不要)
+    route!(GET "/web_sample/password/forgot" => PasswordResetHandler::forgot_enter) ,
+    route!(POST "/web_sample/password/forgot" => PasswordResetHandler::forgot) ,
+    route!(GET "/web_sample/password/reset/{token}" => PasswordResetHandler::reset_enter) ,
+    route!(POST "/web_sample/password/reset/{token}" => PasswordResetHandler::reset) ,
+    // メニュー
+    route!(GET "/web_sample/menu" => MenuHandler::menu) ,
+    // 商品キーワード検索
+    route!(GET "/web_sample/search/product" => ProductSearchHandler::enter) ,
+    route!(POST "/web_sample/search/product" => ProductSearchHandler::result) ,
+    // 商品登録
+    route!(GET "/web_sample/register/product" => ProductRegisterHandler::enter) ,
+    route!(POST "/web_sample/register/product" => ProductRegisterHandler::complete) ,
+    route!(GET "/web_sample/register/product/finish" => ProductRegisterHandler::finish) ,
+    // パスワード変更
+    route!(GET "/web_sample/account/password" => AccountHandler::password_enter) ,
+    route!(POST "/web_sample/account/password" => AccountHandler::password_change) ,
+    // 二要素認証の登録
+    route!(GET "/web_sample/account/mfa" => AccountHandler::mfa_enter) ,
+    route!(POST "/web_sample/account/mfa" => AccountHandler::mfa_enable) ,
+    route!(POST "/web_sample/account/mfa/disable" => AccountHandler::mfa_disable) ,
+    route!(POST "/web_sample/account/mfa/recovery" => AccountHandler::mfa_recovery) ,
+    // 監査ログ(管理者のみ)
+    route!(GET "/web_sample/admin/audit" => AuditLogHandler::list) ,
+    // アカウント管理(管理者のみ)
+    route!(GET "/web_sample/admin/accounts" => AccountAdminHandler::list) ,
+    route!(GET "/web_sample/admin/accounts/new" => AccountAdminHandler::enter) ,
+    route!(POST "/web_sample/admin/accounts/new" => AccountAdminHandler::create) ,
+    route!(GET "/web_sample/admin/accounts/{id}" => AccountAdminHandler::edit) ,
+    route!(POST "/web_sample/admin/accounts/{id}" => AccountAdminHandler::update) ,
+    route!(POST "/web_sample/admin/accounts/{id}/password" => AccountAdminHandler::reset_password) ,
+    route!(POST "/web_sample/admin/accounts/{id}/delete" => AccountAdminHandler::delete) ,
+    // 内部エラー
+    route!(GET "/web_sample/error" => ErrorHandler::error) ,
+    // ヘルスチェック(認証不要)
+    route!(GET "/health/live" => HealthHandler::live) ,
+    route!(GET "/health/ready" => HealthHandler::ready) ,
+    // 静的ファイル(認証不要)
+    route!(GET "/static/{path:.*}" => StaticFileHandler::serve) ,
+    // CSP違反レポート(認証不要)
+    route!(POST SecurityPolicy::REPORT_PATH => CspReportHandler::report)
+];
+///
+/// どのルートにも一致しない場合のデフォルトページ
+///
+pub const DEFAULT_ROUTE: Route = route!(GET "(default)" => MenuHandler::menu);
+
+///
+/// サービスの設定
+/// ROUTESとDEFAULT_ROUTEを登録する
+///
+pub fn set_config(config: &mut web::ServiceConfig){
+    for (index, route) in ROUTES.iter().enumerate() {
+        // 同じパスのルートは最初に現れた位置でまとめて登録する
+        if ROUTES[..index].iter().any(|registered| registered.path == route.path) {
+            continue;
+        }
+        let resource = ROUTES[index..].iter()
+            .filter(|other| other.path == route.path)
+            .fold(resource(route.path), |resource, other| resource.route((other.endpoint)()));
+        config.service(resource);
+    }
+    config.default_service((DEFAULT_ROUTE.endpoint)());
+}
\ No newline at end of file
@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr};
+use sea_orm::sea_query::Query;
+use serde::{Deserialize, Serialize};
+use app_commons::presentation::forms::ProductSearchForm;
+use migration::{Product, ProductCategory};
+use crate::provider::{ProviderError, ServiceProvider};
+use crate::startup::StartupError;
+
+///
+/// 初期データ(商品カテゴリと商品)
+/// YAMLは categories(カテゴリ名の一覧)と products、CSVは商品を name,price,category の列、
+/// 商品カテゴリだけの場合は name の列で記述する
+///
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SeedData {
+    #[serde(default)]
+    pub categories: Vec<String> ,
+    #[serde(default)]
+    pub products:   Vec<SeedProduct>
+}
+///
+/// 初期データの商品
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeedProduct {
+    pub name:     String ,
+    pub price:    i32 ,
+    pub category: String    // カテゴリ名
+}
+// CSVの商品カテゴリ
+#[derive(Debug, Serialize, Deserialize)]
+struct SeedCategory {
+    name: String
+}
+///
+/// 登録した件数
+///
+#[derive(Debug, Default)]
+pub struct SeedReport {
+    pub categories: u64 ,
+    pub products:   u64
+}
+impl SeedData {
+    ///
+    /// 拡張子(.yaml .yml .csv)で形式を判定して読み込む
+    ///
+    pub fn load(path: &Path) -> Result<Self, StartupError> {
+        let error = |message: String| StartupError::Config(format!("{}: {}", path.display(), message));
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml") | Some("yml") => {
+                let text = std::fs::read_to_string(path).map_err(|cause| error(cause.to_string()))?;
+                serde_yaml::from_str(&text).map_err(|cause| error(cause.to_string()))
+            },
+            Some("csv") => {
+                let mut reader = csv::Reader::from_path(path).map_err(|cause| error(cause.to_string()))?;
+                let headers = reader.headers().map_err(|cause| error(cause.to_string()))?.clone();
+                // priceの列がない場合は商品カテゴリの一覧とする
+                if !headers.iter().any(|header| header == "price") {
+                    let categories = reader.deserialize::<SeedCategory>()
+                        .map(|category| category.map(|category| category.name))
+                        .collect::<Result<Vec<String>, csv::Error>>()
+                        .map_err(|cause| error(cause.to_string()))?;
+                    return Ok(Self { categories , products: Vec::new() });
+                }
+                let products = reader.deserialize::<SeedProduct>().collect::<Result<Vec<SeedProduct>, csv::Error>>()
+                    .map_err(|cause| error(cause.to_string()))?;
+                Ok(Self { categories: Vec::new() , products })
+            },
+            _ => Err(error(String::from("拡張子は.yaml、.ymlまたは.csvを指定してください")))
+        }
+    }
+
+    ///
+    /// 拡張子(.yaml .yml .csv)で形式を判定して書き込む
+    /// CSVは商品がある場合は商品、ない場合は商品カテゴリを書き込む
+    ///
+    pub fn save(&self, path: &Path) -> Result<(), StartupError> {
+        let error = |message: String| StartupError::Config(format!("{}: {}", path.display(), message));
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml") | Some("yml") => {
+                let text = serde_yaml::to_string(self).map_err(|cause| error(cause.to_string()))?;
+                std::fs::write(path, text).map_err(|cause| error(cause.to_string()))
+            },
+            Some("csv") => {
+                let mut writer = csv::Writer::from_path(path).map_err(|cause| error(cause.to_string()))?;
+                let written = match self.products.is_empty() {
+                    true => self.categories.iter()
+                        .try_for_each(|name| writer.serialize(SeedCategory { name: name.clone() })) ,
+                    false => self.products.iter().try_for_each(|product| writer.serialize(product))
+                };
+                written.map_err(|cause| error(cause.to_string()))?;
+                writer.flush().map_err(|cause| error(cause.to_string()))
+            },
+            _ => Err(error(String::from("拡張子は.yaml、.ymlまたは.csvを指定してください")))
+        }
+    }
+
+    ///
+    /// サービスプロバイダから登録済みの商品カテゴリと商品を取得する
+    ///
+    pub async fn export(provider: &dyn ServiceProvider) -> Result<Self, ProviderError> {
+        let categories = provider.categories().await?.into_iter()
+            .map(|category| category.name)
+            .collect();
+        let products = match provider.search(&Self::search_form("")?).await {
+            Ok(products) => products ,
+            // 商品が1件も登録されていない
+            Err(ProviderError::SearchError(_)) => Vec::new() ,
+            Err(error) => return Err(error)
+        };
+        let products = products.into_iter()
+            .filter_map(|product| Some(SeedProduct {
+                category: product.category?.name ,
+                name: product.name ,
+                price: product.price
+            }))
+            .collect();
+        Ok(Self { categories , products })
+    }
+    ///
+    /// キーワードで商品を検索する条件を生成する
+    ///
+    pub fn search_form(keyword: &str) -> Result<ProductSearchForm, ProviderError> {
+        serde_json::from_value(serde_json::json!({ "keyword": keyword }))
+            .map_err(|error| ProviderError::InternalError(error.to_string()))
+    }
+
+    ///
+    /// 未登録の商品カテゴリと商品を登録する
+    /// 同じ名前で登録済みのデータは変更しないため、繰り返し実行できる
+    ///
+    pub async fn apply(&self, db: &DatabaseConnection) -> Result<SeedReport, DbErr> {
+        let backend = db.get_database_backend();
+        let mut report = SeedReport::default();
+        let mut category_ids = Self::category_ids(db).await?;
+        for name in self.category_names() {
+            if category_ids.contains_key(&name) {
+                continue;
+            }
+            let statement = Query::insert()
+                .into_table(ProductCategory::Table)
+                .columns([ProductCategory::Name])
+                .values_panic([name.clone().into()])
+                .to_owned();
+            db.execute(backend.build(&statement)).await?;
+            report.categories += 1;
+        }
+        if report.categories > 0 {
+            category_ids = Self::category_ids(db).await?;
+        }
+        let mut product_names = Self::product_names(db).await?;
+        for product in self.products.iter() {
+            if product_names.contains(&product.name) {
+                continue;
+            }
+            let category_id = category_ids.get(&product.category).copied()
+                .ok_or_else(|| DbErr::Custom(format!("商品'{}'のカテゴリ'{}'がありません", product.name, product.category)))?;
+            let statement = Query::insert()
+                .into_table(Product::Table)
+                .columns([Product::Name, Product::Price, Product::CategoryId])
+                .values_panic([product.name.clone().into(), product.price.into(), category_id.into()])
+                .to_owned();
+            db.execute(backend.build(&statement)).await?;
+            product_names.insert(product.name.clone());
+            report.products += 1;
+        }
+        Ok(report)
+    }
+
+    // 登録するカテゴリ名(商品が参照するカテゴリを含む)
+    fn category_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for name in self.categories.iter().chain(self.products.iter().map(|product| &product.category)) {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+    // 登録済みのカテゴリ名とカテゴリ番号
+    async fn category_ids(db: &DatabaseConnection) -> Result<HashMap<String, i32>, DbErr> {
+        let statement = Query::select()
+            .columns([ProductCategory::Id, ProductCategory::Name])
+            .from(ProductCategory::Table)
+            .to_owned();
+        let rows = db.query_all(db.get_database_backend().build(&statement)).await?;
+        rows.iter()
+            .map(|row| Ok((row.try_get::<String>("", "name")?, row.try_get::<i32>("", "id")?)))
+            .collect()
+    }
+    // 登録済みの商品名
+    async fn product_names(db: &DatabaseConnection) -> Result<HashSet<String>, DbErr> {
+        let statement = Query::select()
+            .column(Product::Name)
+            .from(Product::Table)
+            .to_owned();
+        let rows = db.query_all(db.get_database_backend().build(&statement)).await?;
+        rows.iter().map(|row| row.try_get::<String>("", "name")).collect()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,576 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration as StdDuration, Instant};
+use actix_session::SessionMiddleware;
+use actix_session::config::{CookieContentSecurity, PersistentSession};
+use actix_session::storage::{CookieSessionStore, LoadError, RedisSessionStore, SaveError, SessionKey, SessionStore, UpdateError};
+use actix_web::cookie::Key;
+use actix_web::cookie::time::Duration;
+use actix_web::rt;
+use async_trait::async_trait;
+use log::warn;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use redis::{AsyncCommands, ErrorKind, FromRedisValue, RedisResult};
+use redis::aio::{ConnectionLike, MultiplexedConnection};
+use redis::cluster::ClusterClient;
+use redis::cluster_async::ClusterConnection;
+use redis::sentinel::{SentinelClient, SentinelServerType};
+use crate::config::{AppEnvironment, EnvConfig};
+use crate::jwt::SessionUser;
+use crate::startup::StartupError;
+
+// Sessionに格納された値
+type SessionState = HashMap<String, String>;
+
+///
+/// セッションストアの種類
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStoreKind {
+    Redis ,           // 単一のRedis
+    RedisCluster ,    // Redisクラスタ
+    RedisSentinel ,   // Redis Sentinelが管理するマスター
+    Cookie ,          // 署名、暗号化したCookie
+    Memory            // インメモリ(単一ノード、開発、テスト用)
+}
+impl SessionStoreKind {
+    const STORE_KEY: &'static str = "SESSION_STORE";
+    ///
+    /// 環境変数からセッションストアの種類を取得する
+    /// 未設定の場合はRedisとする
+    ///
+    pub fn from_env() -> Result<Self, StartupError> {
+        match EnvConfig::string(Self::STORE_KEY, "redis").as_str() {
+            "redis" => Ok(Self::Redis) ,
+            "redis-cluster" => Ok(Self::RedisCluster) ,
+            "redis-sentinel" => Ok(Self::RedisSentinel) ,
+            "cookie" => Ok(Self::Cookie) ,
+            "memory" => Ok(Self::Memory) ,
+            other => Err(StartupError::Config(format!(
+                "{}の値'{}'はredis、redis-cluster、redis-sentinel、cookieまたはmemoryを指定してください", Self::STORE_KEY, other)))
+        }
+    }
+}
+
+// セッションキーの長さ(actix-sessionと同じ英数字64文字)
+const SESSION_KEY_LENGTH: usize = 64;
+
+///
+/// セッションキーを生成する
+///
+fn generate_session_key() -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(SESSION_KEY_LENGTH).map(char::from).collect()
+}
+
+///
+/// インメモリストア(単一ノード、開発、テスト用)
+///
+#[derive(Clone)]
+pub struct MemorySessionStore {
+    sessions: Arc<Mutex<HashMap<String, (SessionState, Instant)>>>   // キーと(値,有効期限)
+}
+impl Default for MemorySessionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl MemorySessionStore {
+    // 有効期限切れのSessionを破棄する間隔
+    const SWEEP_INTERVAL: StdDuration = StdDuration::from_secs(60);
+    ///
+    /// ストアを生成し、有効期限切れのSessionを定期的に破棄する
+    /// 破棄処理はストアが全て破棄されると終了する
+    ///
+    pub fn new() -> Self {
+        let store = Self { sessions: Arc::default() };
+        let sessions = Arc::downgrade(&store.sessions);
+        rt::spawn(async move {
+            let mut interval = rt::time::interval(Self::SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let sessions = match sessions.upgrade() {
+                    Some(sessions) => Self { sessions } ,
+                    None => break
+                };
+                sessions.sweep();
+            }
+        });
+        store
+    }
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, (SessionState, Instant)>> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    fn expires_at(ttl: &Duration) -> Instant {
+        Instant::now() + StdDuration::from_secs(ttl.whole_seconds().max(0) as u64)
+    }
+    // 有効期限切れのSessionを破棄する
+    fn sweep(&self) {
+        let now = Instant::now();
+        self.lock().retain(|_, (_, expires_at)| *expires_at > now);
+    }
+    // 指定ユーザーのセッションを削除する
+    fn purge_user(&self, user_id: &str) -> usize {
+        let mut sessions = self.lock();
+        let count = sessions.len();
+        sessions.retain(|_, (state, _)| session_user(state).map_or(true, |user| user.user_id != user_id));
+        count - sessions.len()
+    }
+}
+#[async_trait(?Send)]
+impl SessionStore for MemorySessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        // 破棄される前の有効期限切れのSessionは存在しないものとする
+        let now = Instant::now();
+        Ok(self.lock().get(session_key.as_ref())
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(state, _)| state.clone()))
+    }
+    async fn save(&self, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, SaveError> {
+        let key = generate_session_key();
+        self.lock().insert(key.clone(), (session_state, Self::expires_at(ttl)));
+        SessionKey::try_from(key).map_err(|error| SaveError::Other(anyhow::anyhow!(error.to_string())))
+    }
+    async fn update(&self, session_key: SessionKey, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, UpdateError> {
+        let mut sessions = self.lock();
+        let now = Instant::now();
+        if let Some(entry) = sessions.get_mut(session_key.as_ref()).filter(|(_, expires_at)| *expires_at > now) {
+            *entry = (session_state, Self::expires_at(ttl));
+            return Ok(session_key);
+        }
+        drop(sessions);
+        // 有効期限切れの場合は新しいSessionとして格納する
+        self.save(session_state, ttl).await.map_err(|error| UpdateError::Other(anyhow::anyhow!(error.to_string())))
+    }
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> Result<(), anyhow::Error> {
+        if let Some((_, expires_at)) = self.lock().get_mut(session_key.as_ref()) {
+            *expires_at = Self::expires_at(ttl);
+        }
+        Ok(())
+    }
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        self.lock().remove(session_key.as_ref());
+        Ok(())
+    }
+}
+
+///
+/// Redisへの接続の生成
+/// RedisSessionStoreを利用できない構成(クラスタ、Sentinel)で接続先毎に実装する
+///
+#[async_trait(?Send)]
+pub trait RedisConnector: Send + Sync + 'static {
+    type Connection: ConnectionLike + Clone + Send + 'static;
+    // 接続先を解決して接続する
+    async fn connect(&self) -> RedisResult<Self::Connection>;
+}
+
+///
+/// Redisクラスタへの接続
+/// 非同期のクラスタ接続はスロットの移動やノードの切断を検知して接続し直す
+///
+pub struct ClusterConnector {
+    client: ClusterClient
+}
+impl ClusterConnector {
+    pub fn new(nodes: Vec<String>) -> RedisResult<Self> {
+        Ok(Self { client: ClusterClient::new(nodes)? })
+    }
+}
+#[async_trait(?Send)]
+impl RedisConnector for ClusterConnector {
+    type Connection = ClusterConnection;
+    async fn connect(&self) -> RedisResult<ClusterConnection> {
+        self.client.get_async_connection().await
+    }
+}
+
+///
+/// Redis Sentinelが管理するマスターへの接続
+/// 接続の度にSentinelへ問い合わせるため、フェイルオーバー後は新しいマスターに接続する
+///
+pub struct SentinelConnector {
+    nodes:  Vec<String> ,   // Sentinelのノード
+    master: String          // マスター名
+}
+impl SentinelConnector {
+    pub fn new(nodes: Vec<String>, master: &str) -> Self {
+        Self { nodes , master: master.to_string() }
+    }
+}
+#[async_trait(?Send)]
+impl RedisConnector for SentinelConnector {
+    type Connection = MultiplexedConnection;
+    async fn connect(&self) -> RedisResult<MultiplexedConnection> {
+        let mut client = SentinelClient::build(self.nodes.clone(), self.master.clone(), None, SentinelServerType::Master)?;
+        client.get_async_connection().await
+    }
+}
+
+///
+/// RedisSessionStoreと同じ形式でセッションを格納するストア
+/// 接続を共有し、切断された場合は次の要求で接続し直す
+/// ログイン済みのセッションはユーザー毎のセットに登録し、キー空間を走査せずに削除できるようにする
+///
+pub struct RedisCommandSessionStore<C: RedisConnector> {
+    connector:  Arc<C> ,
+    connection: Arc<Mutex<Option<C::Connection>>>   // 共有する接続(切断後はNone)
+}
+pub type RedisClusterSessionStore = RedisCommandSessionStore<ClusterConnector>;
+pub type RedisSentinelSessionStore = RedisCommandSessionStore<SentinelConnector>;
+impl<C: RedisConnector> Clone for RedisCommandSessionStore<C> {
+    fn clone(&self) -> Self {
+        Self { connector: self.connector.clone() , connection: self.connection.clone() }
+    }
+}
+impl<C: RedisConnector> RedisCommandSessionStore<C> {
+    ///
+    /// 接続を確認してストアを生成する
+    ///
+    pub async fn new(connector: C) -> RedisResult<Self> {
+        let connection = connector.connect().await?;
+        Ok(Self { connector: Arc::new(connector) , connection: Arc::new(Mutex::new(Some(connection))) })
+    }
+    // 共有する接続を取得する 切断後は接続し直す
+    async fn connection(&self) -> RedisResult<C::Connection> {
+        let connection = self.connection.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        match connection {
+            Some(connection) => Ok(connection) ,
+            None => {
+                let connection = self.connector.connect().await?;
+                *self.connection.lock().unwrap_or_else(PoisonError::into_inner) = Some(connection.clone());
+                Ok(connection)
+            }
+        }
+    }
+    // 共有する接続を破棄する
+    fn disconnect(&self) {
+        self.connection.lock().unwrap_or_else(PoisonError::into_inner).take();
+    }
+    // コマンドを実行する
+    async fn query<T: FromRedisValue>(&self, command: &redis::Cmd) -> RedisResult<T> {
+        let mut connection = self.connection().await?;
+        match command.query_async::<_, T>(&mut connection).await {
+            // 接続先がレプリカに降格している場合、コマンドは実行されていないため接続し直して再実行する
+            Err(error) if error.kind() == ErrorKind::ReadOnly => {
+                warn!("接続先がレプリカに切り替わったため接続し直します: {}", error);
+                self.disconnect();
+                let mut connection = self.connection().await?;
+                command.query_async::<_, T>(&mut connection).await
+            },
+            // 切断された場合は次の要求で接続し直す
+            Err(error) if error.is_io_error() || error.is_connection_dropped() => {
+                self.disconnect();
+                Err(error)
+            },
+            result => result
+        }
+    }
+    // PINGを発行して接続を確認する
+    async fn ping(&self) -> RedisResult<()> {
+        self.query::<String>(&redis::cmd("PING")).await.map(|_| ())
+    }
+    // ユーザー毎のセッションキーのセット
+    const USER_SESSIONS_PREFIX: &'static str = "user_sessions:";
+    fn user_sessions_key(user_id: &str) -> String {
+        format!("{}{}", Self::USER_SESSIONS_PREFIX, user_id)
+    }
+    // ログイン済みのセッションをユーザー毎のセットに登録する
+    // セットの有効期限は最後に格納したセッションに合わせる(期限切れのキーは削除時に読み飛ばす)
+    async fn index(&self, key: &str, session_state: &SessionState, ttl: &Duration) -> RedisResult<()> {
+        let user = match session_user(session_state) {
+            Some(user) => user ,
+            None => return Ok(())
+        };
+        let user_sessions = Self::user_sessions_key(&user.user_id);
+        let mut command = redis::cmd("SADD");
+        command.arg(&user_sessions).arg(key);
+        self.query::<()>(&command).await?;
+        let mut command = redis::cmd("EXPIRE");
+        command.arg(&user_sessions).arg(ttl.whole_seconds());
+        self.query::<()>(&command).await
+    }
+    // ユーザー毎のセットに登録したセッションを削除する
+    // クラスタでは各キーのスロットを持つマスターに振り分けられる
+    async fn purge_user(&self, user_id: &str) -> anyhow::Result<usize> {
+        let user_sessions = Self::user_sessions_key(user_id);
+        let mut command = redis::cmd("SMEMBERS");
+        command.arg(&user_sessions);
+        let keys = self.query::<Vec<String>>(&command).await?;
+        let mut count = 0;
+        for key in keys {
+            let mut command = redis::cmd("DEL");
+            command.arg(&key);
+            count += self.query::<usize>(&command).await?;
+        }
+        let mut command = redis::cmd("DEL");
+        command.arg(&user_sessions);
+        self.query::<()>(&command).await?;
+        Ok(count)
+    }
+    // 有効期間付きで値を格納する conditionはNX(新規)またはXX(既存)
+    fn set_command(key: &str, value: &str, condition: &str, ttl: &Duration) -> redis::Cmd {
+        let mut command = redis::cmd("SET");
+        command.arg(key).arg(value).arg(condition).arg("EX").arg(ttl.whole_seconds());
+        command
+    }
+}
+#[async_trait(?Send)]
+impl<C: RedisConnector> SessionStore for RedisCommandSessionStore<C> {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        let mut command = redis::cmd("GET");
+        command.arg(session_key.as_ref());
+        match self.query::<Option<String>>(&command).await.map_err(|error| LoadError::Other(error.into()))? {
+            Some(value) => serde_json::from_str(&value).map(Some)
+                .map_err(|error| LoadError::Deserialization(error.into())) ,
+            None => Ok(None)
+        }
+    }
+    async fn save(&self, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, SaveError> {
+        let value = serde_json::to_string(&session_state).map_err(|error| SaveError::Serialization(error.into()))?;
+        let key = generate_session_key();
+        let saved = self.query::<Option<String>>(&Self::set_command(&key, &value, "NX", ttl)).await
+            .map_err(|error| SaveError::Other(error.into()))?;
+        if saved.is_none() {
+            return Err(SaveError::Other(anyhow::anyhow!("session key collision")));
+        }
+        self.index(&key, &session_state, ttl).await.map_err(|error| SaveError::Other(error.into()))?;
+        SessionKey::try_from(key).map_err(|error| SaveError::Other(anyhow::anyhow!(error.to_string())))
+    }
+    async fn update(&self, session_key: SessionKey, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, UpdateError> {
+        let value = serde_json::to_string(&session_state).map_err(|error| UpdateError::Serialization(error.into()))?;
+        let command = Self::set_command(session_key.as_ref(), &value, "XX", ttl);
+        match self.query::<Option<String>>(&command).await.map_err(|error| UpdateError::Other(error.into()))? {
+            Some(_) => {
+                self.index(session_key.as_ref(), &session_state, ttl).await.map_err(|error| UpdateError::Other(error.into()))?;
+                Ok(session_key)
+            },
+            // 有効期限切れで破棄されている場合は新しいSessionとして格納する
+            None => self.save(session_state, ttl).await.map_err(|error| UpdateError::Other(anyhow::anyhow!(error.to_string())))
+        }
+    }
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> Result<(), anyhow::Error> {
+        let mut command = redis::cmd("EXPIRE");
+        command.arg(session_key.as_ref()).arg(ttl.whole_seconds());
+        Ok(self.query::<()>(&command).await?)
+    }
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        let mut command = redis::cmd("DEL");
+        command.arg(session_key.as_ref());
+        Ok(self.query::<()>(&command).await?)
+    }
+}
+
+///
+/// Redisに格納された指定ユーザーのセッションを削除する
+/// ログイン時に格納したSessionUserでユーザーを判定し、削除したセッションのユーザーIdを返す
+///
+pub async fn purge_redis_sessions(client: &redis::Client, matches: impl Fn(&SessionUser) -> bool)
+    -> redis::RedisResult<Vec<String>> {
+    let mut connection = client.get_async_connection().await?;
+    let mut user_ids = Vec::new();
+    for user in purge_sessions(&mut connection, matches).await? {
+        if !user_ids.contains(&user.user_id) {
+            user_ids.push(user.user_id);
+        }
+    }
+    Ok(user_ids)
+}
+// 接続先のノードのセッションを走査し、条件に一致するユーザーのセッションを削除する
+async fn purge_sessions<C: ConnectionLike + Send>(connection: &mut C, matches: impl Fn(&SessionUser) -> bool)
+    -> redis::RedisResult<Vec<SessionUser>> {
+    // セッションキーの形式のキーだけを対象とする(トークン失効、レート制限のキーは除く)
+    let mut keys = Vec::new();
+    {
+        let mut iter = connection.scan::<String>().await?;
+        while let Some(key) = iter.next_item().await {
+            if key.len() == SESSION_KEY_LENGTH && key.chars().all(|c| c.is_ascii_alphanumeric()) {
+                keys.push(key);
+            }
+        }
+    }
+    let mut users = Vec::new();
+    for key in keys {
+        // 走査後に期限切れになった、または文字列以外の値は読み飛ばす
+        let state: redis::RedisResult<Option<String>> = connection.get(&key).await;
+        let state = match state {
+            Ok(Some(state)) => state ,
+            _ => continue
+        };
+        let user = serde_json::from_str::<SessionState>(&state).ok()
+            .and_then(|state| session_user(&state));
+        if let Some(user) = user.filter(|user| matches(user)) {
+            let _: () = connection.del(&key).await?;
+            users.push(user);
+        }
+    }
+    Ok(users)
+}
+// ログイン時にセッションに格納したユーザーを取得する
+fn session_user(state: &SessionState) -> Option<SessionUser> {
+    state.get(&SessionUser::SESSION_KEY.key())
+        .and_then(|entry| serde_json::from_str::<serde_json::Value>(entry).ok())
+        .and_then(|entry| serde_json::from_value::<SessionUser>(entry["value"].clone()).ok())
+}
+
+///
+/// アプリケーションのセッションストア
+/// 設定で選択したストアに処理を委譲する
+///
+#[derive(Clone)]
+pub enum AppSessionStore {
+    Redis(RedisSessionStore, redis::Client) ,    // 単一のRedis(セッション削除用のクライアント)
+    RedisCluster(RedisClusterSessionStore) ,     // Redisクラスタ
+    RedisSentinel(RedisSentinelSessionStore) ,   // Redis Sentinelが管理するマスター
+    Cookie ,                                     // 署名、暗号化したCookie
+    Memory(MemorySessionStore)                   // インメモリ
+}
+impl AppSessionStore {
+    ///
+    /// ログイン時に格納したSessionUserで判定し、指定ユーザーのセッションを全て削除する
+    /// Cookieストアはセッションをサーバーに保持しないため削除できない
+    ///
+    pub async fn purge_user(&self, user_id: &str) -> anyhow::Result<usize> {
+        match self {
+            Self::Redis(_, client) => {
+                let mut connection = client.get_async_connection().await?;
+                Ok(purge_sessions(&mut connection, |user| user.user_id == user_id).await?.len())
+            },
+            Self::RedisCluster(store) => store.purge_user(user_id).await ,
+            Self::RedisSentinel(store) => store.purge_user(user_id).await ,
+            Self::Cookie => Ok(0) ,
+            Self::Memory(store) => Ok(store.purge_user(user_id))
+        }
+    }
+    ///
+    /// セッションを外部のサービス(Redis)に格納するかどうか
+    ///
+    pub fn is_remote(&self) -> bool {
+        matches!(self, Self::Redis(..) | Self::RedisCluster(_) | Self::RedisSentinel(_))
+    }
+    ///
+    /// セッションを格納する接続先へPINGを発行する
+    /// CookieとインメモリのストアはPINGの対象がないため常に成功する
+    ///
+    pub async fn ping(&self) -> RedisResult<()> {
+        match self {
+            Self::Redis(_, client) => {
+                let mut connection = client.get_async_connection().await?;
+                redis::cmd("PING").query_async::<_, String>(&mut connection).await.map(|_| ())
+            },
+            Self::RedisCluster(store) => store.ping().await ,
+            Self::RedisSentinel(store) => store.ping().await ,
+            Self::Cookie | Self::Memory(_) => Ok(())
+        }
+    }
+}
+#[async_trait(?Send)]
+impl SessionStore for AppSessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        match self {
+            Self::Redis(store, _) => store.load(session_key).await ,
+            Self::RedisCluster(store) => store.load(session_key).await ,
+            Self::RedisSentinel(store) => store.load(session_key).await ,
+            Self::Cookie => CookieSessionStore::default().load(session_key).await ,
+            Self::Memory(store) => store.load(session_key).await
+        }
+    }
+    async fn save(&self, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, SaveError> {
+        match self {
+            Self::Redis(store, _) => store.save(session_state, ttl).await ,
+            Self::RedisCluster(store) => store.save(session_state, ttl).await ,
+            Self::RedisSentinel(store) => store.save(session_state, ttl).await ,
+            Self::Cookie => CookieSessionStore::default().save(session_state, ttl).await ,
+            Self::Memory(store) => store.save(session_state, ttl).await
+        }
+    }
+    async fn update(&self, session_key: SessionKey, session_state: SessionState, ttl: &Duration) -> Result<SessionKey, UpdateError> {
+        match self {
+            Self::Redis(store, _) => store.update(session_key, session_state, ttl).await ,
+            Self::RedisCluster(store) => store.update(session_key, session_state, ttl).await ,
+            Self::RedisSentinel(store) => store.update(session_key, session_state, ttl).await ,
+            Self::Cookie => CookieSessionStore::default().update(session_key, session_state, ttl).await ,
+            Self::Memory(store) => store.update(session_key, session_state, ttl).await
+        }
+    }
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Redis(store, _) => store.update_ttl(session_key, ttl).await ,
+            Self::RedisCluster(store) => store.update_ttl(session_key, ttl).await ,
+            Self::RedisSentinel(store) => store.update_ttl(session_key, ttl).await ,
+            Self::Cookie => CookieSessionStore::default().update_ttl(session_key, ttl).await ,
+            Self::Memory(store) => store.update_ttl(session_key, ttl).await
+        }
+    }
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Redis(store, _) => store.delete(session_key).await ,
+            Self::RedisCluster(store) => store.delete(session_key).await ,
+            Self::RedisSentinel(store) => store.delete(session_key).await ,
+            Self::Cookie => CookieSessionStore::default().delete(session_key).await ,
+            Self::Memory(store) => store.delete(session_key).await
+        }
+    }
+}
+
+///
+/// セッションミドルウェアの設定
+/// ストアに関わらず、有効期間とCookieの設定は共通とする
+///
+#[derive(Clone)]
+pub struct SessionSettings {
+    store: AppSessionStore ,
+    key:   Key              // Cookieの署名、暗号化キー
+}
+impl SessionSettings {
+    // 署名、暗号化キー(64バイト以上をBase64で指定)
+    const SECRET_KEY: &'static str = "SESSION_SECRET";
+    const SECRET_LENGTH: usize = 64;
+    // SessionIdを格納するCookieの名称
+    const COOKIE_NAME: &'static str = "rsessionid";
+    // Sessionの有効期間(分)
+    const TTL_MINUTES: i64 = 5;
+
+    ///
+    /// ストアと環境変数の署名、暗号化キーから生成する
+    /// Cookieストアの本番環境ではキーを必須とする
+    ///
+    pub fn new(store: AppSessionStore, environment: AppEnvironment) -> Result<Self, StartupError> {
+        let key = match std::env::var(Self::SECRET_KEY).ok().filter(|value| !value.trim().is_empty()) {
+            Some(value) => {
+                let secret = base64::decode(value.trim())
+                    .map_err(|error| StartupError::Config(format!("{}をBase64として解析できません: {}", Self::SECRET_KEY, error)))?;
+                if secret.len() < Self::SECRET_LENGTH {
+                    return Err(StartupError::Config(format!("{}は{}バイト以上を指定してください", Self::SECRET_KEY, Self::SECRET_LENGTH)));
+                }
+                Key::from(&secret)
+            },
+            None if matches!(store, AppSessionStore::Cookie) && environment == AppEnvironment::Production =>
+                return Err(StartupError::Config(format!("Cookieストアでは{}を設定してください", Self::SECRET_KEY))) ,
+            // 再起動すると既存のSessionは無効になる
+            None => Key::generate()
+        };
+        if matches!(store, AppSessionStore::Memory(_)) && environment == AppEnvironment::Production {
+            warn!("インメモリのセッションストアはレプリカ間で共有されません");
+        }
+        Ok(Self { store, key })
+    }
+    ///
+    /// セッションストアを取得する
+    ///
+    pub fn store(&self) -> AppSessionStore {
+        self.store.clone()
+    }
+    ///
+    /// セッションミドルウェアを生成する
+    ///
+    pub fn middleware(&self) -> SessionMiddleware<AppSessionStore> {
+        SessionMiddleware::builder(self.store.clone() , self.key.clone())
+            // SessionのライフサイクルをPersistenceSessionに設定する 有効期間を5分にする
+            .session_lifecycle(PersistentSession::default().session_ttl(Duration::minutes(Self::TTL_MINUTES)))
+            // Cookieストアでは値を暗号化して格納する
+            .cookie_content_security(CookieContentSecurity::Private)
+            // SessionIdの名称をrssessionIdに設定する
+            .cookie_name(Self::COOKIE_NAME.to_string())
+            .build()
+    }
@@ -0,0 +1,539 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use actix_session::storage::RedisSessionStore;
+use actix_web::rt::time::sleep;
+use log::{info, warn};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{Database, DatabaseConnection};
+use thiserror::Error;
+use x509_parser::pem::parse_x509_pem;
+use crate::account::policy::PasswordPolicy;
+use crate::account::reset::PasswordResetConfig;
+use crate::account::store::{AccountStore, DatabaseAccountStore, MemoryAccountStore};
+use crate::account::revocation::{MemoryRevocationStore, RedisRevocationStore, TokenRevocation};
+use crate::audit::store::{AuditStore, DatabaseAuditStore, MemoryAuditStore};
+use crate::config::{AppEnvironment, EnvConfig, ListenMode, ListenSettings};
+use crate::demo::Demo;
+use crate::error::error_chain;
+use crate::i18n::Translator;
+use crate::jwt::{AdminUsers, WebJwt};
+use crate::mail::{create_mailer, FileMailer, Mailer};
+use crate::assets::StaticAssets;
+use crate::middleware::forwarded::TrustedProxies;
+use crate::middleware::rate_limit::{MemoryRateLimitStore, RateLimiter, RateLimitRule, RateLimitStore, RedisRateLimitStore};
+use crate::middleware::security_headers::SecurityPolicy;
+use crate::provider::{DatabaseServiceProvider, MemoryServiceProvider, ServiceProvider};
+use crate::session_store::{AppSessionStore, ClusterConnector, MemorySessionStore, RedisClusterSessionStore, RedisSentinelSessionStore,
+    SentinelConnector, SessionSettings, SessionStoreKind};
+use crate::template::TemplateEngine;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use crate::tls::TlsCertificate;
+use crate::tls::{ServerTlsConfig, TlsSettings};
+
+///
+/// 起動処理　エラー型
+///
+#[derive(Debug, Error)]
+pub enum StartupError {
+    #[error("設定値が不正です: {0}")]
+    Config(String) ,        // 設定エラー
+    #[error("テンプレートを読み込めません: {0}")]
+    Template(String) ,      // テンプレートエラー
+    #[error("TLS証明書を読み込めません: {0}")]
+    Certificate(String) ,   // 証明書エラー
+    #[error("データベースに接続できません: {0}")]
+    Database(String) ,      // PostgreSQL接続エラー
+    #[error("Redisに接続できません: {0}")]
+    Redis(String) ,         // Redis接続エラー
+    #[error("サーバーを起動できません: {0}")]
+    Bind(String)            // ソケットのバインドエラー
+}
+impl StartupError {
+    // エラーの種類毎の終了コード
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            StartupError::Config(_) => 2 ,
+            StartupError::Template(_) => 3 ,
+            StartupError::Certificate(_) => 4 ,
+            StartupError::Database(_) => 5 ,
+            StartupError::Redis(_) => 6 ,
+            StartupError::Bind(_) => 7
+        }
+    }
+}
+
+///
+/// 起動処理の目的
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StartupMode {
+    Serve ,    // サーバーを起動する
+    Verify     // 設定を検証する(データベースを変更せず、ファイルの監視も開始しない)
+}
+
+///
+/// 起動時に生成する依存リソース
+///
+pub struct StartupResources {
+    pub tera:            TemplateEngine ,
+    pub translator:      Translator ,
+    pub assets:          StaticAssets ,
+    pub pool:            Arc<DatabaseConnection> ,
+    pub provider:        Arc<dyn ServiceProvider> ,
+    pub accounts:        Arc<dyn AccountStore> ,
+    pub audit_log:       Arc<dyn AuditStore> ,
+    pub session:         SessionSettings ,
+    pub redis_client:    Option<redis::Client> ,   // Redisを利用しない構成ではNone
+    pub tls:             Option<ServerTlsConfig> ,   // LISTEN_MODE=plainの場合はNone
+    pub listen:          ListenSettings ,
+    pub trusted_proxies: TrustedProxies ,
+    pub security:        SecurityPolicy ,
+    pub rate_limiter:    RateLimiter ,
+    pub admin_users:     AdminUsers ,
+    pub jwt:             WebJwt ,
+    pub password_policy: PasswordPolicy ,
+    pub revocation:      TokenRevocation ,
+    pub mailer:          Arc<dyn Mailer> ,
+    pub password_reset:  PasswordResetConfig
+}
+
+///
+/// 起動処理
+/// 依存リソースを全て検証し、問題をまとめて報告する
+///
+pub struct Startup;
+impl Startup {
+    pub const REDIS_URL: &'static str = "redis://127.0.0.1:6379";
+    // 待ち受けるアドレスとポートの既定値
+    pub const HOST: &'static str = "127.0.0.1";
+    pub const PORT: u16 = 8081;
+    const REDIS_URL_KEY: &'static str = "REDIS_URL";
+    // Redisクラスタのノード(カンマ区切り)
+    const REDIS_CLUSTER_NODES_KEY: &'static str = "REDIS_CLUSTER_NODES";
+    // Redis Sentinelのノード(カンマ区切り)とマスター名
+    const REDIS_SENTINEL_NODES_KEY: &'static str = "REDIS_SENTINEL_NODES";
+    const REDIS_SENTINEL_MASTER_KEY: &'static str = "REDIS_SENTINEL_MASTER";
+    const DEFAULT_SENTINEL_MASTER: &'static str = "mymaster";
+    // 接続待ちの上限(秒) 0の場合は再試行しない
+    const WAIT_SECONDS_KEY: &'static str = "STARTUP_WAIT_SECONDS";
+    // 再試行間隔(ミリ秒)
+    const RETRY_INTERVAL_KEY: &'static str = "STARTUP_RETRY_INTERVAL_MILLIS";
+    const DEFAULT_RETRY_INTERVAL: u64 = 1000;
+    // テンプレートのディレクトリ
+    const TEMPLATE_DIR_KEY: &'static str = "TEMPLATE_DIR";
+    const DEFAULT_TEMPLATE_DIR: &'static str = "views";
+    // レート制限のルールとストア(redis または memory)
+    const RATE_LIMITS_KEY: &'static str = "RATE_LIMITS";
+    const RATE_LIMIT_STORE_KEY: &'static str = "RATE_LIMIT_STORE";
+    // 管理者ユーザー名(カンマ区切り)
+    const ADMIN_USERS_KEY: &'static str = "ADMIN_USERS";
+    // 起動時に未適用のマイグレーションを適用する
+    const AUTO_MIGRATE_KEY: &'static str = "AUTO_MIGRATE";
+    const DATABASE_URL_KEY: &'static str = "DATABASE_URL";
+
+    ///
+    /// 依存リソースを生成する
+    /// 失敗した場合は検出した全てのエラーを返す
+    ///
+    pub async fn initialize() -> Result<StartupResources, Vec<StartupError>> {
+        Self::initialize_with(StartupMode::Serve).await
+    }
+    ///
+    /// 依存リソースを検証する(verifyサブコマンド用)
+    /// マイグレーションは適用せずに未適用のものを報告し、テンプレートと証明書の監視は開始しない
+    ///
+    pub async fn verify() -> Result<StartupResources, Vec<StartupError>> {
+        Self::initialize_with(StartupMode::Verify).await
+    }
+    async fn initialize_with(mode: StartupMode) -> Result<StartupResources, Vec<StartupError>> {
+        dotenv::dotenv().ok();
+        let mut errors = Vec::new();
+        let (deadline, interval) = match Self::wait_policy() {
+            Ok(policy) => policy ,
+            Err(error) => { errors.push(error); (Duration::ZERO , Duration::ZERO) }
+        };
+        let environment = EnvConfig::environment().map_err(|error| errors.push(error)).unwrap_or(AppEnvironment::Production);
+        let security = SecurityPolicy::from_env(environment).map_err(|error| errors.push(error)).ok();
+        let assets = Self::load_assets(environment);
+        let translator = Translator::load().map_err(|error| errors.push(error)).ok();
+        let tera = match &translator {
+            Some(translator) => Self::load_templates(environment, assets.clone(), translator.clone(), mode)
+                .map_err(|error| errors.push(error)).ok() ,
+            None => None
+        };
+        let listen = ListenSettings::from_env().map_err(|error| errors.push(error)).ok();
+        // TLSを上流で終端する場合は証明書を読み込まない
+        let tls = match listen.as_ref().map(|listen| listen.mode) {
+            Some(ListenMode::Tls) => Self::load_certificate(mode).map(Some).map_err(|error| errors.push(error)).ok() ,
+            Some(ListenMode::Plain) => Some(None) ,
+            None => None
+        };
+        let trusted_proxies = TrustedProxies::from_env().map_err(|error| errors.push(error)).ok();
+        let pool = Self::connect_database(deadline, interval, mode).await.map_err(|error| errors.push(error)).ok();
+        // セッションストアとレート制限のいずれかがRedisを利用する場合のみ接続する
+        let session_store = SessionStoreKind::from_env().map_err(|error| errors.push(error)).ok();
+        let redis_url = EnvConfig::string(Self::REDIS_URL_KEY, Self::REDIS_URL);
+        let uses_redis = session_store == Some(SessionStoreKind::Redis)
+            || EnvConfig::string(Self::RATE_LIMIT_STORE_KEY, "redis") == "redis";
+        let redis_client = match uses_redis {
+            true => Self::connect_redis(&redis_url, deadline, interval).await.map_err(|error| errors.push(error)).ok() ,
+            false => None
+        };
+        // Redisに接続できない場合はエラーを報告済みのため生成しない
+        let redis_failed = uses_redis && redis_client.is_none();
+        let session = match session_store {
+            Some(kind) if !(redis_failed && kind == SessionStoreKind::Redis) =>
+                Self::create_session_store(kind, environment, &redis_url, deadline, interval).await
+                    .map_err(|error| errors.push(error)).ok() ,
+            _ => None
+        };
+        let rate_limiter = match redis_failed {
+            true => None ,
+            false => Self::create_rate_limiter(redis_client.clone()).await.map_err(|error| errors.push(error)).ok()
+        };
+        let revocation = match &redis_client {
+            Some(client) => RedisRevocationStore::new(client.clone()).await
+                .map(|store| TokenRevocation::new(Arc::new(store)))
+                .map_err(|error| errors.push(StartupError::Redis(error.to_string()))).ok() ,
+            None if redis_failed => None ,
+            None => {
+                warn!("Redisを利用しないため、トークンの失効はレプリカ間で共有されません");
+                Some(TokenRevocation::new(Arc::new(MemoryRevocationStore::default())))
+            }
+        };
+        let admin_users = AdminUsers::parse(&EnvConfig::string(Self::ADMIN_USERS_KEY, ""));
+        let jwt = WebJwt::from_env(environment).map_err(|error| errors.push(error)).ok();
+        let password_policy = PasswordPolicy::from_env().map_err(|error| errors.push(error)).ok();
+        let mailer = create_mailer().map_err(|error| errors.push(error)).ok();
+        let password_reset = PasswordResetConfig::from_env().map_err(|error| errors.push(error)).ok();
+        match (tera, translator, security, tls, listen, trusted_proxies, pool, session, rate_limiter, revocation,
+               jwt, password_policy, mailer, password_reset) {
+            (Some(tera), Some(translator), Some(security), Some(tls), Some(listen), Some(trusted_proxies),
+                Some(pool), Some(session), Some(rate_limiter), Some(revocation), Some(jwt), Some(password_policy),
+                Some(mailer), Some(password_reset)) if errors.is_empty() => {
+                let provider: Arc<dyn ServiceProvider> = Arc::new(DatabaseServiceProvider::new(pool.clone()));
+                let accounts: Arc<dyn AccountStore> = Arc::new(DatabaseAccountStore::new(pool.clone()));
+                let audit_log: Arc<dyn AuditStore> = Arc::new(DatabaseAuditStore::new(pool.clone()));
+                // トークンの失効時にユーザーのセッションを削除する
+                let revocation = revocation.sessions(session.store());
+                Ok(StartupResources { tera, translator, assets, pool, provider, accounts, audit_log, session, redis_client, tls,
+                    listen, trusted_proxies, security, rate_limiter, admin_users, jwt, password_policy, revocation, mailer,
+                    password_reset })
+            },
+            _ => Err(errors)
+        }
+    }
+
+    ///
+    /// デモモードの依存リソースを生成する
+    /// 商品、商品カテゴリ、ユーザー、アカウント、監査ログとセッションはインメモリに保持し、
+    /// 証明書は起動毎に生成する
+    ///
+    pub async fn initialize_demo() -> Result<StartupResources, Vec<StartupError>> {
+        dotenv::dotenv().ok();
+        let mut errors = Vec::new();
+        let environment = AppEnvironment::Development;
+        let security = SecurityPolicy::from_env(environment).map_err(|error| errors.push(error)).ok();
+        let assets = Self::load_assets(environment);
+        let translator = Translator::load().map_err(|error| errors.push(error)).ok();
+        let tera = match &translator {
+            Some(translator) => Self::load_templates(environment, assets.clone(), translator.clone(), StartupMode::Serve)
+                .map_err(|error| errors.push(error)).ok() ,
+            None => None
+        };
+        let listen = ListenSettings::from_env().map_err(|error| errors.push(error)).ok();
+        let tls = match listen.as_ref().map(|listen| listen.mode) {
+            Some(ListenMode::Tls) => Self::demo_certificate().map(Some).map_err(|error| errors.push(error)).ok() ,
+            Some(ListenMode::Plain) => Some(None) ,
+            None => None
+        };
+        let trusted_proxies = TrustedProxies::from_env().map_err(|error| errors.push(error)).ok();
+        let pool = match Demo::connect_database().await {
+            Ok(db) => Self::migrate(&db).await.map(|_| Arc::new(db)).map_err(|error| errors.push(error)).ok() ,
+            Err(error) => { errors.push(error); None }
+        };
+        let password = Demo::password();
+        let accounts = MemoryAccountStore::new().user(Demo::USER_NAME, &password).await
+            .map_err(|error| errors.push(StartupError::Config(format!("{:?}", error)))).ok();
+        let session = SessionSettings::new(AppSessionStore::Memory(MemorySessionStore::new()), environment)
+            .map_err(|error| errors.push(error)).ok();
+        let rate_limiter = RateLimitRule::parse_all(&EnvConfig::string(Self::RATE_LIMITS_KEY, RateLimitRule::DEFAULT_RULES))
+            .map(|rules| RateLimiter::new(Arc::new(MemoryRateLimitStore::default()), rules))
+            .map_err(|error| errors.push(error)).ok();
+        let jwt = WebJwt::from_env(environment).map_err(|error| errors.push(error)).ok();
+        let password_policy = PasswordPolicy::from_env().map_err(|error| errors.push(error)).ok();
+        let password_reset = PasswordResetConfig::from_env().map_err(|error| errors.push(error)).ok();
+        match (tera, translator, security, tls, listen, trusted_proxies, pool, accounts, session, rate_limiter,
+               jwt, password_policy, password_reset) {
+            (Some(tera), Some(translator), Some(security), Some(tls), Some(listen), Some(trusted_proxies),
+                Some(pool), Some(accounts), Some(session), Some(rate_limiter), Some(jwt), Some(password_policy),
+                Some(password_reset)) if errors.is_empty() => {
+                Demo::print_credentials(&listen.url("/web_sample/login"), &password);
+                let revocation = TokenRevocation::new(Arc::new(MemoryRevocationStore::default())).sessions(session.store());
+                let accounts = Arc::new(accounts);
+                Ok(StartupResources { tera, translator, assets, pool ,
+                    provider: Arc::new(MemoryServiceProvider::sample().accounts(accounts.clone())) ,
+                    accounts , audit_log: Arc::new(MemoryAuditStore::new()) ,
+                    session, redis_client: None,
+                    tls, listen, trusted_proxies, security, rate_limiter,
+                    // デモユーザーは管理画面も利用できる
+                    admin_users: AdminUsers::parse(Demo::USER_NAME) ,
+                    jwt, password_policy ,
+                    revocation ,
+                    // メールは送信せずログに出力する
+                    mailer: Arc::new(FileMailer::new(None)) ,
+                    password_reset })
+            },
+            _ => Err(errors)
+        }
+    }
+
+    ///
+    /// 起動失敗の報告と終了
+    /// 最初に検出したエラーの終了コードでプロセスを終了する
+    ///
+    pub fn exit(errors: Vec<StartupError>) -> ! {
+        for error in errors.iter() {
+            log::error!("{}", error);
+        }
+        let code = errors.first().map_or(1, |error| error.exit_code());
+        log::error!("起動に失敗しました。({}件のエラー 終了コード:{})", errors.len(), code);
+        std::process::exit(code)
+    }
+
+    // 環境変数から接続待ちの上限と再試行間隔を取得する
+    fn wait_policy() -> Result<(Duration, Duration), StartupError> {
+        let deadline = EnvConfig::u64(Self::WAIT_SECONDS_KEY, 0)?;
+        let interval = EnvConfig::u64(Self::RETRY_INTERVAL_KEY, Self::DEFAULT_RETRY_INTERVAL)?;
+        Ok((Duration::from_secs(deadline), Duration::from_millis(interval)))
+    }
+
+    ///
+    /// 静的ファイル群を生成する
+    /// 開発環境では、埋め込まれていないファイルをテンプレートのディレクトリ配下のstaticから配信する
+    ///
+    fn load_assets(environment: AppEnvironment) -> StaticAssets {
+        let assets = StaticAssets::embedded();
+        match environment {
+            AppEnvironment::Development => {
+                let dir = EnvConfig::string(Self::TEMPLATE_DIR_KEY, Self::DEFAULT_TEMPLATE_DIR);
+                assets.disk_fallback(&format!("{}/static", dir))
+            },
+            AppEnvironment::Production => assets
+        }
+    }
+
+    ///
+    /// テンプレートエンジンを生成する
+    /// 開発環境ではテンプレートの変更を監視し、本番環境では埋め込みテンプレートを優先する
+    ///
+    fn load_templates(environment: AppEnvironment, assets: StaticAssets, translator: Translator, mode: StartupMode)
+        -> Result<TemplateEngine, StartupError> {
+        let dir = EnvConfig::string(Self::TEMPLATE_DIR_KEY, Self::DEFAULT_TEMPLATE_DIR);
+        #[cfg(feature = "embed-templates")]
+        if environment == AppEnvironment::Production {
+            return TemplateEngine::embedded(assets, translator).map_err(|error| StartupError::Template(error_chain(&error)));
+        }
+        let engine = TemplateEngine::load(&dir, assets, translator)
+            .map_err(|error| StartupError::Template(format!("{}: {}", dir, error_chain(&error))))?;
+        if environment == AppEnvironment::Development && mode == StartupMode::Serve {
+            engine.watch(&dir).map_err(|error| StartupError::Template(format!("{}: {}", dir, error)))?;
+        }
+        Ok(engine)
+    }
+
+    ///
+    /// サーバー証明書を読み込み、TLS設定を生成する
+    /// 証明書と秘密鍵のファイルの変更、またはSIGHUPの受信で再読み込みする
+    ///
+    #[cfg(any(feature = "openssl", feature = "rustls"))]
+    fn load_certificate(mode: StartupMode) -> Result<ServerTlsConfig, StartupError> {
+        let certificate = TlsCertificate::load(TlsSettings::from_env()?)?;
+        if mode == StartupMode::Verify {
+            return certificate.server_config();
+        }
+        // 監視できない場合もSIGHUPで再読み込みできるため起動は継続する
+        if let Err(error) = certificate.watch() {
+            warn!("証明書の変更を監視できません: {}", error);
+        }
+        certificate.reload_on_hangup();
+        certificate.server_config()
+    }
+    // 自己署名証明書を生成し、TLS設定を生成する
+    #[cfg(any(feature = "openssl", feature = "rustls"))]
+    fn demo_certificate() -> Result<ServerTlsConfig, StartupError> {
+        let (certificate, private_key) = Demo::certificate()?;
+        TlsCertificate::from_pem(TlsSettings::from_env()?, &certificate, &private_key)?.server_config()
+    }
+    ///
+    /// TLSのバックエンドを有効にしていない場合はTLSで待ち受けられない
+    ///
+    #[cfg(not(any(feature = "openssl", feature = "rustls")))]
+    fn load_certificate(_mode: StartupMode) -> Result<ServerTlsConfig, StartupError> {
+        Err(StartupError::Config(String::from(
+            "LISTEN_MODE=tlsの場合はopensslまたはrustlsフィーチャーを有効にしてビルドしてください")))
+    }
+    #[cfg(not(any(feature = "openssl", feature = "rustls")))]
+    fn demo_certificate() -> Result<ServerTlsConfig, StartupError> {
+        Self::load_certificate(StartupMode::Serve)
+    }
+
+    ///
+    /// DATABASE_URLのデータベースに接続する(サブコマンド用 再試行しない)
+    ///
+    pub async fn connect() -> Result<DatabaseConnection, StartupError> {
+        dotenv::dotenv().ok();
+        Database::connect(Self::database_url()?).await
+            .map_err(|error| StartupError::Database(error.to_string()))
+    }
+    ///
+    /// REDIS_URLのRedisに接続する(サブコマンド用 再試行しない)
+    ///
+    pub async fn redis() -> Result<redis::Client, StartupError> {
+        dotenv::dotenv().ok();
+        Self::connect_redis(&EnvConfig::string(Self::REDIS_URL_KEY, Self::REDIS_URL), Duration::ZERO, Duration::ZERO).await
+    }
+    ///
+    /// サーバー証明書の有効期限を取得する(verifyサブコマンド用)
+    ///
+    pub fn certificate_not_after() -> Result<String, StartupError> {
+        let settings = TlsSettings::from_env()?;
+        let path = settings.certificate_file.display();
+        let error = |message: String| StartupError::Certificate(format!("{}: {}", path, message));
+        let pem = std::fs::read(&settings.certificate_file).map_err(|cause| error(cause.to_string()))?;
+        let (_, pem) = parse_x509_pem(&pem).map_err(|cause| error(cause.to_string()))?;
+        pem.parse_x509().map(|certificate| certificate.validity().not_after.to_string())
+            .map_err(|cause| error(cause.to_string()))
+    }
+    fn database_url() -> Result<String, StartupError> {
+        std::env::var(Self::DATABASE_URL_KEY)
+            .map_err(|_| StartupError::Config(format!("{}が設定されていません", Self::DATABASE_URL_KEY)))
+    }
+
+    // SeaOrmのDatabaseConnectionを取得し、マイグレーションの適用状況を確認する
+    // 検証時は適用せず、AUTO_MIGRATE=trueの場合は起動時に適用されるマイグレーションを報告する
+    async fn connect_database(deadline: Duration, interval: Duration, mode: StartupMode) -> Result<Arc<DatabaseConnection>, StartupError> {
+        let url = Self::database_url()?;
+        let auto_migrate = EnvConfig::bool(Self::AUTO_MIGRATE_KEY, false)?;
+        let db = Self::retry("PostgreSQL", deadline, interval, || Database::connect(url.clone())).await
+            .map_err(|error| StartupError::Database(error.to_string()))?;
+        match (auto_migrate, mode) {
+            (true, StartupMode::Serve) => {
+                Self::migrate(&db).await?;
+                info!("未適用のマイグレーションを適用しました");
+            },
+            (true, StartupMode::Verify) => {
+                let names = Self::pending_migrations(&db).await?;
+                if !names.is_empty() {
+                    warn!("未適用のマイグレーションがあります(起動時に適用されます): {}", names.join(", "));
+                }
+            },
+            (false, _) => Self::check_migrations(&db).await?
+        }
+        Ok(Arc::new(db))
+    }
+    // 未適用のマイグレーションを適用する
+    async fn migrate(db: &DatabaseConnection) -> Result<(), StartupError> {
+        Migrator::up(db, None).await
+            .map_err(|error| StartupError::Database(format!("マイグレーション: {}", error)))
+    }
+    // 未適用のマイグレーションがある場合は起動しない
+    async fn check_migrations(db: &DatabaseConnection) -> Result<(), StartupError> {
+        let names = Self::pending_migrations(db).await?;
+        if names.is_empty() {
+            return Ok(());
+        }
+        Err(StartupError::Database(format!(
+            "未適用のマイグレーションがあります(migrate upを実行するか{}=trueを設定してください): {}", Self::AUTO_MIGRATE_KEY, names.join(", "))))
+    }
+    // 未適用のマイグレーションの名前
+    async fn pending_migrations(db: &DatabaseConnection) -> Result<Vec<String>, StartupError> {
+        let pending = Migrator::get_pending_migrations(db).await
+            .map_err(|error| StartupError::Database(format!("マイグレーション: {}", error)))?;
+        Ok(pending.iter().map(|migration| migration.name().to_string()).collect())
+    }
+
+    // Redisのクライアントを生成し、疎通を確認する
+    async fn connect_redis(url: &str, deadline: Duration, interval: Duration) -> Result<redis::Client, StartupError> {
+        let client = redis::Client::open(url)
+            .map_err(|error| StartupError::Config(format!("{}: {}", url, error)))?;
+        Self::retry("Redis", deadline, interval, || client.get_async_connection()).await
+            .map_err(|error| StartupError::Redis(error.to_string()))?;
+        Ok(client)
+    }
+
+    ///
+    /// セッションストアを生成する
+    /// ストアの種類はSESSION_STOREで指定する
+    ///
+    async fn create_session_store(kind: SessionStoreKind, environment: AppEnvironment, redis_url: &str,
+                                  deadline: Duration, interval: Duration) -> Result<SessionSettings, StartupError> {
+        let store = match kind {
+            SessionStoreKind::Redis => AppSessionStore::Redis(
+                Self::retry("Redis", deadline, interval, || RedisSessionStore::new(redis_url)).await
+                    .map_err(|error| StartupError::Redis(error.to_string()))? ,
+                redis::Client::open(redis_url).map_err(|error| StartupError::Config(format!("{}: {}", redis_url, error)))?) ,
+            SessionStoreKind::RedisCluster => {
+                let nodes = Self::nodes(Self::REDIS_CLUSTER_NODES_KEY)?;
+                AppSessionStore::RedisCluster(
+                    Self::retry("Redisクラスタ", deadline, interval, || {
+                        let nodes = nodes.clone();
+                        async move { RedisClusterSessionStore::new(ClusterConnector::new(nodes)?).await }
+                    }).await.map_err(|error| StartupError::Redis(error.to_string()))?)
+            },
+            SessionStoreKind::RedisSentinel => {
+                // マスターは接続の度にSentinelへ問い合わせる
+                let nodes = Self::nodes(Self::REDIS_SENTINEL_NODES_KEY)?;
+                let master = EnvConfig::string(Self::REDIS_SENTINEL_MASTER_KEY, Self::DEFAULT_SENTINEL_MASTER);
+                AppSessionStore::RedisSentinel(
+                    Self::retry("Redis Sentinel", deadline, interval, || RedisSentinelSessionStore::new(SentinelConnector::new(nodes.clone(), &master))).await
+                        .map_err(|error| StartupError::Redis(error.to_string()))?)
+            },
+            SessionStoreKind::Cookie => AppSessionStore::Cookie ,
+            SessionStoreKind::Memory => AppSessionStore::Memory(MemorySessionStore::new())
+        };
+        SessionSettings::new(store, environment)
+    }
+
+    // カンマ区切りのノードを取得する
+    fn nodes(key: &str) -> Result<Vec<String>, StartupError> {
+        let nodes = EnvConfig::string(key, "").split(',').map(str::trim)
+            .filter(|node| !node.is_empty()).map(String::from).collect::<Vec<String>>();
+        if nodes.is_empty() {
+            return Err(StartupError::Config(format!("{}が設定されていません", key)));
+        }
+        Ok(nodes)
+    }
+
+    // レート制限ミドルウェアを生成する
+    async fn create_rate_limiter(client: Option<redis::Client>) -> Result<RateLimiter, StartupError> {
+        let rules = RateLimitRule::parse_all(&EnvConfig::string(Self::RATE_LIMITS_KEY, RateLimitRule::DEFAULT_RULES))?;
+        let store: Arc<dyn RateLimitStore> = match (EnvConfig::string(Self::RATE_LIMIT_STORE_KEY, "redis").as_str(), client) {
+            ("redis", Some(client)) => Arc::new(RedisRateLimitStore::new(client).await
+                .map_err(|error| StartupError::Redis(error.to_string()))?) ,
+            ("redis", None) => return Err(StartupError::Redis(String::from("Redisに接続していません"))) ,
+            ("memory", _) => Arc::new(MemoryRateLimitStore::default()) ,
+            (other, _) => return Err(StartupError::Config(
+                format!("{}の値'{}'はredisまたはmemoryを指定してください", Self::RATE_LIMIT_STORE_KEY, other)))
+        };
+        Ok(RateLimiter::new(store, rules))
+    }
+
+    // 上限時間に達するまで一定間隔で接続を再試行する
+    async fn retry<T, E, F, Fut>(name: &str, deadline: Duration, interval: Duration, mut connect: F) -> Result<T, E>
+        where F: FnMut() -> Fut , Fut: Future<Output = Result<T, E>> , E: Display {
+        let start = Instant::now();
+        loop {
+            match connect().await {
+                Ok(value) => return Ok(value) ,
+                Err(error) if start.elapsed() + interval < deadline => {
+                    warn!("{}に接続できません。{}ms後に再試行します: {}", name, interval.as_millis(), error);
+                    sleep(interval).await;
+                },
+                Err(error) => return Err(error)
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+use log::{error, info, warn};
+use notify::{RecursiveMode, Watcher};
+use tera::{Context, Tera};
+use crate::assets::{AssetUrlFunction, StaticAssets};
+use crate::i18n::{TranslateFunction, Translator};
+
+///
+/// テンプレートエンジン
+/// 開発モードではテンプレートの変更を監視して再読み込みする
+///
+#[derive(Clone)]
+pub struct TemplateEngine {
+    tera:       Arc<RwLock<Tera>> ,
+    assets:     StaticAssets ,
+    translator: Translator
+}
+impl TemplateEngine {
+    ///
+    /// 指定されたディレクトリからテンプレートを読み込む
+    ///
+    pub fn load(dir: &str, assets: StaticAssets, translator: Translator) -> tera::Result<Self> {
+        let tera = Self::build(dir, &assets, &translator)?;
+        Ok(Self { tera: Arc::new(RwLock::new(tera)), assets, translator })
+    }
+    ///
+    /// バイナリに埋め込まれたテンプレートを読み込む
+    ///
+    #[cfg(feature = "embed-templates")]
+    pub fn embedded(assets: StaticAssets, translator: Translator) -> tera::Result<Self> {
+        let templates = EmbeddedPages::iter()
+            .filter_map(|name| EmbeddedPages::get(&name)
+                .map(|file| (name.to_string(), String::from_utf8_lossy(&file.data).into_owned())))
+            .collect::<Vec<(String, String)>>();
+        let mut tera = Tera::default();
+        tera.add_raw_templates(templates)?;
+        Self::configure(&mut tera, &assets, &translator);
+        Ok(Self { tera: Arc::new(RwLock::new(tera)), assets, translator })
+    }
+    ///
+    /// テンプレートをレンダリングする
+    ///
+    pub fn render(&self, path: &str, context: &Context) -> tera::Result<String> {
+        self.tera.read().unwrap_or_else(PoisonError::into_inner).render(path, context)
+    }
+    ///
+    /// views/pages配下の変更を監視し、変更時にテンプレートを再読み込みする
+    ///
+    pub fn watch(&self, dir: &str) -> notify::Result<()> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&Path::new(dir).join("pages"), RecursiveMode::Recursive)?;
+        let engine = self.clone();
+        let dir = dir.to_string();
+        std::thread::spawn(move || {
+            // 監視を継続するためWatcherをスレッドで保持する
+            let _watcher = watcher;
+            for result in receiver {
+                match result {
+                    Ok(event) if event.kind.is_access() => () ,
+                    Ok(_) => engine.reload(&dir) ,
+                    Err(error) => warn!("template watch error: {}", error)
+                }
+            }
+        });
+        info!("watching templates in {}/pages", dir);
+        Ok(())
+    }
+
+    // テンプレートを再読み込みする 失敗した場合は現在のテンプレートを使い続ける
+    fn reload(&self, dir: &str) {
+        match Self::build(dir, &self.assets, &self.translator) {
+            Ok(tera) => {
+                *self.tera.write().unwrap_or_else(PoisonError::into_inner) = tera;
+                info!("templates reloaded");
+            },
+            Err(error) => error!("template reload failed: {}", crate::error::error_chain(&error))
+        }
+    }
+    // ディレクトリ配下のHTMLからTeraを生成する
+    fn build(dir: &str, assets: &StaticAssets, translator: &Translator) -> tera::Result<Tera> {
+        let mut tera = Tera::new(&format!("{}/**/*.html", dir))?;
+        Self::configure(&mut tera, assets, translator);
+        Ok(tera)
+    }
+    // テンプレートから利用する関数を登録する
+    fn configure(tera: &mut Tera, assets: &StaticAssets, translator: &Translator) {
+        tera.register_function("asset", AssetUrlFunction(assets.clone()));
+        tera.register_function("t", TranslateFunction(translator.clone()));
+    }
+}
+
+///
+/// バイナリに埋め込むテンプレート
+///
+#[cfg(feature = "embed-templates")]
+#[derive(rust_embed::RustEmbed)]
+#[folder = "views/pages/"]
+#[prefix = "pages/"]
+struct EmbeddedPages;
@@ -0,0 +1,408 @@
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use std::collections::HashSet;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use std::ffi::OsString;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use std::path::Path;
+use std::path::PathBuf;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use log::{error, info, warn};
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use notify::{RecursiveMode, Watcher};
+use crate::config::EnvConfig;
+use crate::startup::StartupError;
+
+///
+/// サーバーのTLS設定
+/// 既定はOpenSSL、rustlsフィーチャーを有効にした場合はrustlsで待ち受ける
+///
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+pub use backend::ServerTlsConfig;
+///
+/// TLSのバックエンドを有効にしない場合のTLS設定
+/// 平文のHTTPでのみ待ち受けるため、値を持たない
+///
+#[cfg(not(any(feature = "openssl", feature = "rustls")))]
+pub enum ServerTlsConfig {}
+
+///
+/// TLSの最小バージョン
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsVersion {
+    Tls12 ,   // TLS1.2(既定)
+    Tls13     // TLS1.3
+}
+
+///
+/// 暗号スイートの方針(Mozillaの推奨設定)
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherPolicy {
+    Intermediate ,   // TLS1.2以上のECDHE、AEADの暗号スイート(既定)
+    Modern           // TLS1.3の暗号スイートのみ
+}
+
+///
+/// TLSの設定
+///
+#[derive(Debug, Clone)]
+pub struct TlsSettings {
+    pub certificate_file: PathBuf ,      // 証明書(中間証明書を含むPEM)
+    pub private_key_file: PathBuf ,      // 秘密鍵(PEM)
+    pub min_version:      TlsVersion ,
+    pub cipher_policy:    CipherPolicy
+}
+impl TlsSettings {
+    const CERTIFICATE_FILE_KEY: &'static str = "TLS_CERTIFICATE_FILE";
+    const PRIVATE_KEY_FILE_KEY: &'static str = "TLS_PRIVATE_KEY_FILE";
+    const DEFAULT_CERTIFICATE_FILE: &'static str = "localhost+2.pem";
+    const DEFAULT_PRIVATE_KEY_FILE: &'static str = "localhost+2-key.pem";
+    // 1.2 または 1.3
+    const MIN_VERSION_KEY: &'static str = "TLS_MIN_VERSION";
+    // intermediate または modern
+    const CIPHER_POLICY_KEY: &'static str = "TLS_CIPHER_POLICY";
+    ///
+    /// 環境変数からTLSの設定を取得する
+    ///
+    pub fn from_env() -> Result<Self, StartupError> {
+        let min_version = match EnvConfig::string(Self::MIN_VERSION_KEY, "1.2").as_str() {
+            "1.2" => TlsVersion::Tls12 ,
+            "1.3" => TlsVersion::Tls13 ,
+            other => return Err(StartupError::Config(
+                format!("{}の値'{}'は1.2または1.3を指定してください", Self::MIN_VERSION_KEY, other)))
+        };
+        let cipher_policy = match EnvConfig::string(Self::CIPHER_POLICY_KEY, "intermediate").as_str() {
+            "intermediate" => CipherPolicy::Intermediate ,
+            "modern" => CipherPolicy::Modern ,
+            other => return Err(StartupError::Config(
+                format!("{}の値'{}'はintermediateまたはmodernを指定してください", Self::CIPHER_POLICY_KEY, other)))
+        };
+        let settings = Self {
+            certificate_file: PathBuf::from(EnvConfig::string(Self::CERTIFICATE_FILE_KEY, Self::DEFAULT_CERTIFICATE_FILE)) ,
+            private_key_file: PathBuf::from(EnvConfig::string(Self::PRIVATE_KEY_FILE_KEY, Self::DEFAULT_PRIVATE_KEY_FILE)) ,
+            min_version ,
+            cipher_policy
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+    ///
+    /// 最小バージョンと暗号スイートの方針の組み合わせを検証する
+    ///
+    pub fn validate(&self) -> Result<(), StartupError> {
+        match (self.cipher_policy, self.min_version) {
+            (CipherPolicy::Modern, TlsVersion::Tls12) => Err(StartupError::Config(
+                format!("{}=modernの場合は{}=1.3を指定してください", Self::CIPHER_POLICY_KEY, Self::MIN_VERSION_KEY))) ,
+            _ => Ok(())
+        }
+    }
+}
+
+///
+/// 再読み込みできるサーバー証明書
+/// 再読み込み後のハンドシェイクから新しい証明書を利用するため、確立済みの接続は切断しない
+///
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+#[derive(Clone)]
+pub struct TlsCertificate {
+    settings:   TlsSettings ,
+    current:    Arc<RwLock<backend::Certificate>> ,
+    reloadable: bool    // ファイルから読み込んだ場合のみ再読み込みする
+}
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+impl TlsCertificate {
+    ///
+    /// 証明書と秘密鍵のファイルを読み込む
+    ///
+    pub fn load(settings: TlsSettings) -> Result<Self, StartupError> {
+        let certificate = Self::read(&settings)?;
+        Ok(Self { settings , current: Arc::new(RwLock::new(certificate)) , reloadable: true })
+    }
+    ///
+    /// PEMの証明書と秘密鍵から生成する(再読み込みしない)
+    ///
+    pub fn from_pem(settings: TlsSettings, certificate: &[u8], private_key: &[u8]) -> Result<Self, StartupError> {
+        let certificate = backend::certificate(&settings, certificate, private_key)
+            .map_err(StartupError::Certificate)?;
+        Ok(Self { settings , current: Arc::new(RwLock::new(certificate)) , reloadable: false })
+    }
+
+    ///
+    /// 待ち受けに利用するTLS設定を生成する
+    /// ハンドシェイク毎に現在の証明書を参照する
+    ///
+    pub fn server_config(&self) -> Result<ServerTlsConfig, StartupError> {
+        backend::server_config(&self.settings, self.current.clone()).map_err(StartupError::Certificate)
+    }
+
+    ///
+    /// 証明書と秘密鍵のファイルを再読み込みする
+    /// 失敗した場合は現在の証明書を使い続ける
+    ///
+    pub fn reload(&self) -> Result<(), StartupError> {
+        if !self.reloadable {
+            return Ok(());
+        }
+        let certificate = Self::read(&self.settings)?;
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = certificate;
+        Ok(())
+    }
+
+    ///
+    /// 証明書と秘密鍵のファイルの変更を監視し、変更された場合は再読み込みする
+    ///
+    pub fn watch(&self) -> notify::Result<()> {
+        if !self.reloadable {
+            return Ok(());
+        }
+        let files = [&self.settings.certificate_file, &self.settings.private_key_file];
+        // ファイルを置き換えて更新する場合に備え、ファイルのあるディレクトリを監視する
+        let dirs = files.iter().map(|file| match file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf() ,
+            _ => PathBuf::from(".")
+        }).collect::<HashSet<PathBuf>>();
+        let names = files.iter().filter_map(|file| file.file_name().map(OsString::from)).collect::<HashSet<OsString>>();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        for dir in dirs.iter() {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+        let certificate = self.clone();
+        std::thread::spawn(move || {
+            // 監視を継続するためWatcherをスレッドで保持する
+            let _watcher = watcher;
+            for result in receiver {
+                match result {
+                    Ok(event) if event.kind.is_access() => () ,
+                    Ok(event) if event.paths.iter().any(|path| Self::is_target(path, &names)) =>
+                        certificate.reload_logged("file change") ,
+                    Ok(_) => () ,
+                    Err(error) => warn!("certificate watch error: {}", error)
+                }
+            }
+        });
+        info!("watching certificate {}", self.settings.certificate_file.display());
+        Ok(())
+    }
+
+    ///
+    /// SIGHUPを受信した場合は再読み込みする
+    /// 実行中のランタイムでシグナルを待ち受けるため、非同期処理から呼び出す
+    ///
+    #[cfg(unix)]
+    pub fn reload_on_hangup(&self) {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+        if !self.reloadable {
+            return;
+        }
+        let certificate = self.clone();
+        actix_web::rt::spawn(async move {
+            let mut hangup = match signal(SignalKind::hangup()) {
+                Ok(hangup) => hangup ,
+                Err(error) => { warn!("SIGHUP handler error: {}", error); return; }
+            };
+            while hangup.recv().await.is_some() {
+                certificate.reload_logged("SIGHUP");
+            }
+        });
+    }
+    ///
+    /// SIGHUPのないプラットフォームではファイルの変更のみで再読み込みする
+    ///
+    #[cfg(not(unix))]
+    pub fn reload_on_hangup(&self) {}
+
+    // 再読み込みして結果をログに出力する
+    // 証明書と秘密鍵を順に更新する場合、一方の更新では組み合わせが一致せず失敗することがある
+    fn reload_logged(&self, trigger: &str) {
+        match self.reload() {
+            Ok(()) => info!("certificate reloaded ({})", trigger) ,
+            Err(error) => error!("certificate reload failed ({}): {}", trigger, error)
+        }
+    }
+    // 監視対象のファイルか
+    fn is_target(path: &Path, names: &HashSet<OsString>) -> bool {
+        path.file_name().map_or(false, |name| names.contains(name))
+    }
+    // 証明書と秘密鍵のファイルを読み込む
+    fn read(settings: &TlsSettings) -> Result<backend::Certificate, StartupError> {
+        let read = |path: &Path| std::fs::read(path)
+            .map_err(|error| StartupError::Certificate(format!("{}: {}", path.display(), error)));
+        let certificate = read(&settings.certificate_file)?;
+        let private_key = read(&settings.private_key_file)?;
+        backend::certificate(settings, &certificate, &private_key)
+            .map_err(|error| StartupError::Certificate(format!("{}: {}", settings.certificate_file.display(), error)))
+    }
+}
+
+///
+/// OpenSSLのバックエンド
+/// 証明書毎にSslContextを生成し、SNIコールバックでハンドシェイク中の接続に設定する
+///
+#[cfg(all(feature = "openssl", not(feature = "rustls")))]
+mod backend {
+    use std::sync::{Arc, PoisonError, RwLock};
+    use openssl::error::ErrorStack;
+    use openssl::pkey::PKey;
+    use openssl::ssl::{select_next_proto, AlpnError, SniError, SslAcceptor, SslAcceptorBuilder, SslContext, SslMethod, SslVersion};
+    use openssl::x509::X509;
+    use super::{CipherPolicy, TlsSettings, TlsVersion};
+
+    pub type ServerTlsConfig = SslAcceptorBuilder;
+    pub type Certificate = SslContext;
+
+    // HTTP/2とHTTP/1.1(ALPNのワイヤー形式)
+    const ALPN_PROTOCOLS: &[u8] = b"\x02h2\x08http/1.1";
+
+    // 証明書と秘密鍵を設定したSslContextを生成する
+    pub fn certificate(settings: &TlsSettings, certificate: &[u8], private_key: &[u8]) -> Result<Certificate, String> {
+        let mut builder = acceptor_builder(settings).map_err(|error| error.to_string())?;
+        let mut chain = X509::stack_from_pem(certificate).map_err(|error| error.to_string())?.into_iter();
+        let leaf = chain.next().ok_or_else(|| String::from("証明書がありません"))?;
+        builder.set_certificate(&leaf).map_err(|error| error.to_string())?;
+        // 中間証明書
+        for intermediate in chain {
+            builder.add_extra_chain_cert(intermediate).map_err(|error| error.to_string())?;
+        }
+        let key = PKey::private_key_from_pem(private_key).map_err(|error| error.to_string())?;
+        builder.set_private_key(&key).map_err(|error| error.to_string())?;
+        // 秘密鍵と証明書の組み合わせを検証する
+        builder.check_private_key()
+            .map_err(|error| format!("秘密鍵と証明書が一致しません: {}", error))?;
+        // SNIコールバックで切り替えたSslContextのALPNが利用されるため、こちらにも設定する
+        builder.set_alpn_select_callback(|_, client| select_next_proto(ALPN_PROTOCOLS, client).ok_or(AlpnError::NOACK));
+        Ok(builder.build().into_context())
+    }
+
+    // 現在の証明書で開始し、ハンドシェイク毎に最新の証明書へ切り替えるSslAcceptorBuilderを生成する
+    pub fn server_config(settings: &TlsSettings, current: Arc<RwLock<Certificate>>) -> Result<ServerTlsConfig, String> {
+        let mut builder = acceptor_builder(settings).map_err(|error| error.to_string())?;
+        {
+            let context = current.read().unwrap_or_else(PoisonError::into_inner);
+            if let Some(certificate) = context.certificate() {
+                builder.set_certificate(certificate).map_err(|error| error.to_string())?;
+            }
+            if let Some(key) = context.private_key() {
+                builder.set_private_key(key).map_err(|error| error.to_string())?;
+            }
+        }
+        // SNIの有無に関わらずClientHelloの受信時に呼び出される
+        builder.set_servername_callback(move |ssl, _| {
+            let context = current.read().unwrap_or_else(PoisonError::into_inner);
+            ssl.set_ssl_context(&context).map_err(|_| SniError::ALERT_FATAL)
+        });
+        Ok(builder)
+    }
+
+    // 暗号スイートの方針と最小バージョンを設定したSslAcceptorBuilderを生成する
+    fn acceptor_builder(settings: &TlsSettings) -> Result<SslAcceptorBuilder, ErrorStack> {
+        let mut builder = match settings.cipher_policy {
+            CipherPolicy::Intermediate => SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server())? ,
+            CipherPolicy::Modern => SslAcceptor::mozilla_modern_v5(SslMethod::tls_server())?
+        };
+        let version = match settings.min_version {
+            TlsVersion::Tls12 => SslVersion::TLS1_2 ,
+            TlsVersion::Tls13 => SslVersion::TLS1_3
+        };
+        builder.set_min_proto_version(Some(version))?;
+        Ok(builder)
+    }
+}
+
+///
+/// rustlsのバックエンド
+/// ハンドシェイク毎に現在の証明書を返すResolvesServerCertを登録する
+///
+#[cfg(feature = "rustls")]
+mod backend {
+    use std::io::BufReader;
+    use std::sync::{Arc, PoisonError, RwLock};
+    use rustls::{PrivateKey, ServerConfig, SignatureScheme, SupportedCipherSuite, SupportedProtocolVersion};
+    use rustls::server::{ClientHello, ResolvesServerCert};
+    use rustls::sign::{any_supported_type, CertifiedKey, SigningKey};
+    use rustls_pemfile::Item;
+    use super::{CipherPolicy, TlsSettings, TlsVersion};
+
+    pub type ServerTlsConfig = ServerConfig;
+    pub type Certificate = Arc<CertifiedKey>;
+
+    // 秘密鍵と証明書の組み合わせの検証に利用する署名方式と署名する値
+    const KEY_CHECK_SCHEMES: &[SignatureScheme] = &[
+        SignatureScheme::ECDSA_NISTP256_SHA256 ,
+        SignatureScheme::ECDSA_NISTP384_SHA384 ,
+        SignatureScheme::ED25519 ,
+        SignatureScheme::RSA_PKCS1_SHA256
+    ];
+    const KEY_CHECK_MESSAGE: &[u8] = b"web_sample private key check";
+
+    // 証明書チェーンと署名鍵を生成する
+    pub fn certificate(_settings: &TlsSettings, certificate: &[u8], private_key: &[u8]) -> Result<Certificate, String> {
+        let chain = rustls_pemfile::certs(&mut BufReader::new(certificate)).map_err(|error| error.to_string())?;
+        if chain.is_empty() {
+            return Err(String::from("証明書がありません"));
+        }
+        let key = rustls_pemfile::read_all(&mut BufReader::new(private_key)).map_err(|error| error.to_string())?
+            .into_iter()
+            .find_map(|item| match item {
+                Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => Some(PrivateKey(key)) ,
+                _ => None
+            })
+            .ok_or_else(|| String::from("秘密鍵がありません"))?;
+        let key = any_supported_type(&key).map_err(|error| error.to_string())?;
+        let chain = chain.into_iter().map(rustls::Certificate).collect::<Vec<rustls::Certificate>>();
+        // 秘密鍵と証明書の組み合わせを検証する
+        check_private_key(&chain[0], key.as_ref())
+            .map_err(|error| format!("秘密鍵と証明書が一致しません: {}", error))?;
+        Ok(Arc::new(CertifiedKey::new(chain, key)))
+    }
+
+    // 秘密鍵で署名した値を証明書の公開鍵で検証する
+    fn check_private_key(certificate: &rustls::Certificate, key: &dyn SigningKey) -> Result<(), String> {
+        let signer = key.choose_scheme(KEY_CHECK_SCHEMES).ok_or_else(|| String::from("未対応の秘密鍵です"))?;
+        let signature = signer.sign(KEY_CHECK_MESSAGE).map_err(|error| error.to_string())?;
+        let algorithm = match signer.scheme() {
+            SignatureScheme::ECDSA_NISTP256_SHA256 => &webpki::ECDSA_P256_SHA256 ,
+            SignatureScheme::ECDSA_NISTP384_SHA384 => &webpki::ECDSA_P384_SHA384 ,
+            SignatureScheme::ED25519 => &webpki::ED25519 ,
+            _ => &webpki::RSA_PKCS1_2048_8192_SHA256
+        };
+        webpki::EndEntityCert::try_from(certificate.0.as_slice())
+            .and_then(|leaf| leaf.verify_signature(algorithm, KEY_CHECK_MESSAGE, &signature))
+            .map_err(|error| format!("{:?}", error))
+    }
+
+    // 暗号スイートの方針と最小バージョンを設定したServerConfigを生成する
+    pub fn server_config(settings: &TlsSettings, current: Arc<RwLock<Certificate>>) -> Result<ServerTlsConfig, String> {
+        let versions: &[&'static SupportedProtocolVersion] = match settings.min_version {
+            TlsVersion::Tls12 => &[&rustls::version::TLS13, &rustls::version::TLS12] ,
+            TlsVersion::Tls13 => &[&rustls::version::TLS13]
+        };
+        // rustlsはECDHE、AEADの暗号スイートのみを実装するため、intermediateは全ての暗号スイートとする
+        let suites = rustls::ALL_CIPHER_SUITES.iter().copied()
+            .filter(|suite| match settings.cipher_policy {
+                CipherPolicy::Intermediate => true ,
+                CipherPolicy::Modern => suite.version() == &rustls::version::TLS13
+            })
+            .filter(|suite| versions.contains(&suite.version()))
+            .collect::<Vec<SupportedCipherSuite>>();
+        let config = ServerConfig::builder()
+            .with_cipher_suites(&suites)
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(versions)
+            .map_err(|error| error.to_string())?
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(ReloadableResolver(current)));
+        Ok(config)
+    }
+
+    // 現在の証明書を返す
+    struct ReloadableResolver(Arc<RwLock<Certificate>>);
+    impl ResolvesServerCert for ReloadableResolver {
+        fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+            Some(self.0.read().unwrap_or_else(PoisonError::into_inner).clone())
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,229 @@
+//!
+//! ログイン認証と認可のリダイレクトの結合テスト
+//!
+mod common;
+
+use actix_web::cookie::Cookie;
+use actix_web::http::{header, StatusCode};
+use actix_web::test;
+use app_commons::presentation::jwt::JWT_COOKIE_KEY;
+use web_sample::account::store::MemoryAccountStore;
+
+///
+/// 正しいユーザー名とパスワードでトークンが発行され、メニューにリダイレクトされる
+///
+#[actix_web::test]
+async fn login_succeeded_issues_token_and_redirects_to_menu() {
+    let account = common::account().await;
+    let jwt = common::jwt();
+    let accounts = MemoryAccountStore::new().account(account);
+    let app = test::init_service(common::app_with_accounts(common::empty_database(), accounts, &jwt)).await;
+
+    let response = common::login(&app, common::USER_NAME, common::PASSWORD).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/menu"));
+    let token = common::response_cookie(&response, JWT_COOKIE_KEY).expect("token cookie");
+    // 発行されたトークンはアプリケーションの署名鍵で検証できる
+    let claims = jwt.decode(token.value()).expect("valid token");
+    assert_eq!(claims.user_name(), common::USER_NAME);
+}
+
+///
+/// パスワードが違う場合はトークンを発行せず、ログイン画面にエラーを表示する
+///
+#[actix_web::test]
+async fn login_failed_renders_error_without_token() {
+    let account = common::account().await;
+    let jwt = common::jwt();
+    let accounts = MemoryAccountStore::new().account(account);
+    let app = test::init_service(common::app_with_accounts(common::empty_database(), accounts, &jwt)).await;
+
+    let response = common::login(&app, common::USER_NAME, "wrong-password").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(common::response_cookie(&response, JWT_COOKIE_KEY).is_none());
+    let body = common::body_text(response).await;
+    assert!(body.contains("ユーザー名またはパスワードが違います。"));
+}
+
+///
+/// エラーメッセージはメッセージIdから表示言語に翻訳する
+///
+#[actix_web::test]
+async fn login_failed_renders_error_in_accepted_language() {
+    let account = common::account().await;
+    let jwt = common::jwt();
+    let accounts = MemoryAccountStore::new().account(account);
+    let app = test::init_service(common::app_with_accounts(common::empty_database(), accounts, &jwt)).await;
+
+    let request = test::TestRequest::post()
+        .uri("/web_sample/login")
+        .insert_header((header::ACCEPT_LANGUAGE, "en-US,en;q=0.9"))
+        .set_form(&[("name", common::USER_NAME), ("password", "wrong-password")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = common::body_text(response).await;
+    assert!(body.contains("The user name or password is incorrect."));
+    assert!(!body.contains("login-failed"));
+}
+
+///
+/// 無効化されたアカウントは正しいパスワードでもログインできない
+///
+#[actix_web::test]
+async fn login_with_disabled_account_is_rejected() {
+    let account = common::account().await;
+    let account = web_sample::account::entity::Model { enabled: false , ..account };
+    let jwt = common::jwt();
+    let accounts = MemoryAccountStore::new().account(account);
+    let app = test::init_service(common::app_with_accounts(common::empty_database(), accounts, &jwt)).await;
+
+    let response = common::login(&app, common::USER_NAME, common::PASSWORD).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(common::response_cookie(&response, JWT_COOKIE_KEY).is_none());
+}
+
+///
+/// アカウントに登録されていないユーザーは既存の認証サービスで認証せずに拒否する
+/// (MockDatabaseは問い合わせ結果を持たないため、認証サービスを呼び出すとエラー画面になる)
+///
+#[actix_web::test]
+async fn login_with_unknown_user_is_rejected() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+
+    let response = common::login(&app, common::USER_NAME, common::PASSWORD).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(common::response_cookie(&response, JWT_COOKIE_KEY).is_none());
+    let body = common::body_text(response).await;
+    assert!(body.contains("ユーザー名またはパスワードが違います。"));
+}
+
+///
+/// トークンを持たないリクエストはログイン画面にリダイレクトされる
+///
+#[actix_web::test]
+async fn request_without_token_redirects_to_login() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+
+    for uri in ["/web_sample/menu", "/web_sample/search/product", "/web_sample/register/product"] {
+        let response = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        assert_eq!(response.status(), StatusCode::FOUND, "{}", uri);
+        assert_eq!(common::location(&response), Some("/web_sample/login"), "{}", uri);
+    }
+}
+
+///
+/// 他の署名鍵で生成されたトークンはログイン画面にリダイレクトされる
+///
+#[actix_web::test]
+async fn request_with_foreign_token_redirects_to_login() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let foreign = common::login_cookie(&common::jwt(), &common::account().await);
+
+    let request = test::TestRequest::get().uri("/web_sample/menu").cookie(foreign).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/login"));
+
+    let request = test::TestRequest::get().uri("/web_sample/menu")
+        .cookie(Cookie::new(JWT_COOKIE_KEY, "not-a-token")).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(common::location(&response), Some("/web_sample/login"));
+}
+
+///
+/// ログイン済みのトークンでメニューを表示できる
+///
+#[actix_web::test]
+async fn request_with_token_renders_menu() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let cookie = common::login_cookie(&jwt, &common::account().await);
+
+    let request = test::TestRequest::get().uri("/web_sample/menu").cookie(cookie).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = common::body_text(response).await;
+    assert!(body.contains(common::USER_NAME));
+}
+
+///
+/// 管理者ロールを持たないトークンで管理画面を要求するとメニューにリダイレクトされる
+///
+#[actix_web::test]
+async fn admin_page_without_admin_role_redirects_to_menu() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let cookie = common::login_cookie(&jwt, &common::account().await);
+
+    let request = test::TestRequest::get().uri("/web_sample/admin/accounts").cookie(cookie).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/menu"));
+}
+
+///
+/// パスワードを変更すると変更前のトークンと他のセッションを失効させ、再発行したトークンは有効になる
+///
+#[actix_web::test]
+async fn password_change_revokes_other_tokens_and_sessions() {
+    let jwt = common::jwt();
+    let accounts = MemoryAccountStore::new().account(common::account().await);
+    let app = test::init_service(common::app_with_accounts(common::empty_database(), accounts, &jwt)).await;
+
+    // 別の端末でのログイン
+    let other = common::login(&app, common::USER_NAME, common::PASSWORD).await;
+    let other_token = common::response_cookie(&other, JWT_COOKIE_KEY).expect("token cookie");
+    let other_session = common::response_cookie(&other, common::SESSION_COOKIE).expect("session cookie");
+    // パスワードを変更する端末でのログイン
+    let current = common::login(&app, common::USER_NAME, common::PASSWORD).await;
+    let token = common::response_cookie(&current, JWT_COOKIE_KEY).expect("token cookie");
+    let session = common::response_cookie(&current, common::SESSION_COOKIE).expect("session cookie");
+
+    let request = test::TestRequest::post().uri("/web_sample/account/password")
+        .cookie(token)
+        .cookie(session)
+        .set_form(&[("current_password", common::PASSWORD), ("password", "changed01password"),
+            ("password_confirm", "changed01password")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let renewed = common::response_cookie(&response, JWT_COOKIE_KEY).expect("renewed token");
+
+    // 変更前のトークンはログイン画面にリダイレクトされる
+    let request = test::TestRequest::get().uri("/web_sample/menu").cookie(other_token).to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::FOUND);
+    // 失効と同じミリ秒に発行されても、再発行したトークンは有効
+    let request = test::TestRequest::get().uri("/web_sample/menu").cookie(renewed).to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::OK);
+    // 別の端末のセッションは削除されている
+    let request = test::TestRequest::get().uri(common::SESSION_USER_PATH).cookie(other_session).to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::NOT_FOUND);
+}
+
+///
+/// ログアウトしたトークンは失効し、ログイン画面にもログイン中として表示しない
+///
+#[actix_web::test]
+async fn logout_revokes_token() {
+    let jwt = common::jwt();
+    let accounts = MemoryAccountStore::new().account(common::account().await);
+    let app = test::init_service(common::app_with_accounts(common::empty_database(), accounts, &jwt)).await;
+
+    let response = common::login(&app, common::USER_NAME, common::PASSWORD).await;
+    let token = common::response_cookie(&response, JWT_COOKIE_KEY).expect("token cookie");
+    let request = test::TestRequest::post().uri("/web_sample/logout").cookie(token.clone()).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/login"));
+
+    // Cookieを削除しなかったクライアントのトークンも利用できない
+    let request = test::TestRequest::get().uri("/web_sample/menu").cookie(token.clone()).to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::FOUND);
+    let request = test::TestRequest::get().uri("/web_sample/login").cookie(token).to_request();
+    let body = common::body_text(test::call_service(&app, request).await).await;
+    assert!(!body.contains(common::USER_NAME));
+}
\ No newline at end of file
@@ -0,0 +1,244 @@
+//!
+//! 結合テストの共通処理
+//! set_configでサービスを登録したAppを、インメモリのセッションストア、アカウントと監査ログのストア、
+//! SeaORMのMockDatabaseで構築する
+//!
+#![allow(dead_code)]
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use actix_http::Request;
+use actix_session::Session;
+use actix_web::{App, Error, HttpResponse, test, web};
+use actix_web::body::MessageBody;
+use actix_web::cookie::Cookie;
+use actix_web::dev::{Service, ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use app_commons::application::transfers::{CategoryDto, ProductDto};
+use chrono::Utc;
+use rand::RngCore;
+use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, Value};
+use web_sample::account::Password;
+use web_sample::account::entity as account;
+use web_sample::account::policy::PasswordPolicy;
+use web_sample::account::revocation::{MemoryRevocationStore, TokenRevocation};
+use web_sample::account::store::{AccountStore, MemoryAccountStore};
+use web_sample::assets::StaticAssets;
+use web_sample::audit::store::{AuditStore, MemoryAuditStore};
+use web_sample::config::AppEnvironment;
+use web_sample::handler::product_register::ProductRegisterHandler;
+use web_sample::handler::view_helper::{FlashLevel, SessionHelper};
+use web_sample::i18n::Translator;
+use web_sample::jwt::{AdminUsers, SessionUser, WebClaims, WebJwt};
+use web_sample::provider::{DatabaseServiceProvider, ServiceProvider};
+use web_sample::routes::set_config;
+use web_sample::session_store::{AppSessionStore, MemorySessionStore, SessionSettings};
+use web_sample::template::TemplateEngine;
+
+// テスト用アカウントのユーザー名とパスワード
+pub const USER_NAME: &str = "user01";
+pub const PASSWORD: &str = "password01";
+// セッションの状態を準備するテスト専用のパス
+pub const SEED_CATEGORIES_PATH: &str = "/test/session/categories";
+pub const SEED_NEW_PRODUCT_PATH: &str = "/test/session/new_product";
+// ログイン中のユーザーがセッションに格納されているか確認するテスト専用のパス
+pub const SESSION_USER_PATH: &str = "/test/session/user";
+// セッションを保持するCookieの名前
+pub const SESSION_COOKIE: &str = "rsessionid";
+
+///
+/// 呼び出し毎に異なる共通鍵でJWTの署名鍵を生成する
+/// 環境変数の鍵は利用しない Appとlogin_cookie()に同じ鍵を渡す
+///
+pub fn jwt() -> WebJwt {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    WebJwt::hs256("test", &secret)
+}
+
+///
+/// テスト対象のAppを生成する
+/// サービスプロバイダはMockDatabaseを利用するSeaORMの実装で、アカウントは登録されていない
+///
+pub fn app(db: DatabaseConnection, jwt: &WebJwt) -> App<impl ServiceFactory<ServiceRequest, Config = (),
+    Response = ServiceResponse<impl MessageBody>, Error = Error, InitError = ()>> {
+    app_with_accounts(db, MemoryAccountStore::new(), jwt)
+}
+///
+/// 指定したアカウントのストアでテスト対象のAppを生成する
+///
+pub fn app_with_accounts(db: DatabaseConnection, accounts: MemoryAccountStore, jwt: &WebJwt)
+    -> App<impl ServiceFactory<ServiceRequest, Config = (),
+    Response = ServiceResponse<impl MessageBody>, Error = Error, InitError = ()>> {
+    let db = Arc::new(db);
+    let provider: Arc<dyn ServiceProvider> = Arc::new(DatabaseServiceProvider::new(db.clone()));
+    app_with_provider(db, provider, accounts, jwt)
+}
+///
+/// 指定したサービスプロバイダとアカウントのストアでテスト対象のAppを生成する
+/// 本番と同じset_configに、セッションを準備するテスト専用のルートを加える
+///
+pub fn app_with_provider(db: Arc<DatabaseConnection>, provider: Arc<dyn ServiceProvider>, accounts: MemoryAccountStore,
+                         jwt: &WebJwt) -> App<impl ServiceFactory<ServiceRequest, Config = (),
+    Response = ServiceResponse<impl MessageBody>, Error = Error, InitError = ()>> {
+    let translator = Translator::load().expect("message catalogs");
+    let assets = StaticAssets::embedded();
+    let tera = TemplateEngine::load("views", assets.clone(), translator.clone()).expect("templates");
+    let session = SessionSettings::new(
+        AppSessionStore::Memory(MemorySessionStore::new()), AppEnvironment::Development).expect("session settings");
+    let accounts: Arc<dyn AccountStore> = Arc::new(accounts);
+    let audit_log: Arc<dyn AuditStore> = Arc::new(MemoryAuditStore::new());
+    App::new()
+        .wrap(session.middleware())
+        .app_data(web::Data::new(tera))
+        .app_data(web::Data::new(translator))
+        .app_data(web::Data::new(assets))
+        .app_data(web::Data::new(db))
+        .app_data(web::Data::from(provider))
+        .app_data(web::Data::from(accounts))
+        .app_data(web::Data::from(audit_log))
+        .app_data(web::Data::new(None::<redis::Client>))
+        .app_data(web::Data::new(AdminUsers::parse("")))
+        .app_data(web::Data::new(jwt.clone()))
+        .app_data(web::Data::new(PasswordPolicy::default()))
+        .app_data(web::Data::new(session.store()))
+        .app_data(web::Data::new(TokenRevocation::new(Arc::new(MemoryRevocationStore::default())).sessions(session.store())))
+        .configure(set_config)
+        .route(SEED_CATEGORIES_PATH, web::post().to(seed_categories))
+        .route(SEED_NEW_PRODUCT_PATH, web::post().to(seed_new_product))
+        .route(SESSION_USER_PATH, web::get().to(session_user))
+}
+
+// 商品カテゴリをセッションに格納する(商品入力画面を表示した状態)
+async fn seed_categories(session: Session, categories: web::Json<Vec<CategoryDto>>) -> HttpResponse {
+    SessionHelper::insert(&session, &ProductRegisterHandler::CATEGORIES_KEY, &categories.into_inner())
+        .expect("store categories");
+    HttpResponse::Ok().finish()
+}
+// 登録した商品をセッションに格納する(登録が完了した状態)
+async fn seed_new_product(session: Session, product: web::Json<ProductDto>) -> HttpResponse {
+    SessionHelper::flash_with(&session, FlashLevel::Success, ProductRegisterHandler::COMPLETED_MESSAGE, &product.into_inner())
+        .expect("store new product");
+    HttpResponse::Ok().finish()
+}
+
+// ログイン中のユーザーがセッションにあれば200、なければ404を返す
+async fn session_user(session: Session) -> HttpResponse {
+    match SessionHelper::get(&session, &SessionUser::SESSION_KEY).expect("session user") {
+        Some(_) => HttpResponse::Ok().finish() ,
+        None => HttpResponse::NotFound().finish()
+    }
+}
+
+///
+/// テスト用のアカウント
+///
+pub async fn account() -> account::Model {
+    let now = Utc::now();
+    account::Model {
+        id: uuid::Uuid::new_v4().to_string() ,
+        user_name: USER_NAME.to_string() ,
+        email: None ,
+        password_hash: Password::hash(PASSWORD).await.expect("password hash") ,
+        roles: String::new() ,
+        enabled: true ,
+        must_change_password: false ,
+        created_at: now.into() ,
+        updated_at: now.into()
+    }
+}
+
+///
+/// 問い合わせ結果を持たないデータベース
+/// 問い合わせはすべてエラーになる
+///
+pub fn empty_database() -> DatabaseConnection {
+    MockDatabase::new(DatabaseBackend::Postgres).into_connection()
+}
+///
+/// 商品の登録に成功するデータベース
+/// 問い合わせの順に、登録済み商品の確認(該当なし)、登録した商品(RETURNING)、商品カテゴリを返す
+/// 商品カテゴリの行は、商品と結合して取得する場合の列(A_*,B_*)も持つ
+///
+pub fn register_database(id: i32, name: &str, price: i32, category: (i32, &str)) -> DatabaseConnection {
+    let product = BTreeMap::from([
+        (String::from("id"), Value::from(id)) ,
+        (String::from("name"), Value::from(name.to_string())) ,
+        (String::from("price"), Value::from(price)) ,
+        (String::from("category_id"), Value::from(category.0))
+    ]);
+    let mut category_row = BTreeMap::from([
+        (String::from("id"), Value::from(category.0)) ,
+        (String::from("name"), Value::from(category.1.to_string())) ,
+        (String::from("B_id"), Value::from(category.0)) ,
+        (String::from("B_name"), Value::from(category.1.to_string()))
+    ]);
+    category_row.extend(product.iter().map(|(column, value)| (format!("A_{}", column), value.clone())));
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::new(), vec![product], vec![category_row]])
+        .into_connection()
+}
+///
+/// テスト用アカウントでログイン済みのトークンを格納したCookieを生成する
+///
+pub fn login_cookie(jwt: &WebJwt, account: &account::Model) -> Cookie<'static> {
+    jwt.cookie(&WebClaims::from_account(account)).expect("token cookie")
+}
+///
+/// ログイン画面からユーザー名とパスワードを送信する
+///
+pub async fn login<S, B>(app: &S, user_name: &str, password: &str) -> ServiceResponse<B>
+    where S: Service<Request, Response = ServiceResponse<B>, Error = Error> {
+    let request = test::TestRequest::post()
+        .uri("/web_sample/login")
+        .set_form(&[("name", user_name), ("password", password)])
+        .to_request();
+    test::call_service(app, request).await
+}
+///
+/// テスト専用のパスにJSONを送信し、セッションを保持するCookieを返す
+///
+pub async fn seed_session<S, B>(app: &S, path: &str, value: serde_json::Value) -> Cookie<'static>
+    where S: Service<Request, Response = ServiceResponse<B>, Error = Error> {
+    let request = test::TestRequest::post().uri(path).set_json(&value).to_request();
+    let response = test::call_service(app, request).await;
+    assert!(response.status().is_success(), "seed {} failed: {}", path, response.status());
+    response_cookie(&response, SESSION_COOKIE).expect("session cookie")
+}
+
+///
+/// テストで利用する商品カテゴリ
+///
+pub fn categories() -> serde_json::Value {
+    serde_json::json!([
+        { "id": 1 , "name": "文房具" } ,
+        { "id": 2 , "name": "雑貨" }
+    ])
+}
+///
+/// テストで利用する登録済みの商品
+///
+pub fn new_product() -> serde_json::Value {
+    serde_json::json!({
+        "id": 100 , "name": "水性ボールペン(黒)" , "price": 120 ,
+        "category": { "id": 1 , "name": "文房具" }
+    })
+}
+
+///
+/// レスポンスから指定した名前のCookieを取得する
+///
+pub fn response_cookie<B>(response: &ServiceResponse<B>, name: &str) -> Option<Cookie<'static>> {
+    response.response().cookies().find(|cookie| cookie.name() == name).map(Cookie::into_owned)
+}
+///
+/// リダイレクト先を取得する
+///
+pub fn location<B>(response: &ServiceResponse<B>) -> Option<&str> {
+    response.headers().get(header::LOCATION).and_then(|value| value.to_str().ok())
+}
+///
+/// レスポンスボディを文字列で取得する
+///
+pub async fn body_text<B: MessageBody>(response: ServiceResponse<B>) -> String {
+    String::from_utf8(test::read_body(response).await.to_vec()).expect("utf-8 body")
+}
\ No newline at end of file
@@ -0,0 +1,88 @@
+//!
+//! 転送ヘッダーとHTTPSリダイレクトの結合テスト
+//!
+use std::net::SocketAddr;
+use actix_web::{App, HttpRequest, HttpResponse, test, web};
+use actix_web::http::StatusCode;
+use actix_web::http::header;
+use web_sample::middleware::forwarded::{ClientInfo, ForwardedHeaders, TrustedProxies};
+use web_sample::middleware::https_redirect::HttpsRedirect;
+
+// 信頼するプロキシと、プロキシ以外の接続元
+const PROXY: &str = "10.0.0.2:40000";
+const CLIENT: &str = "198.51.100.7:50000";
+const HTTPS_PORT: u16 = 8443;
+
+// クライアントのアドレスとHTTPSで送信されたかを返す
+async fn client(request: HttpRequest) -> HttpResponse {
+    HttpResponse::Ok().body(format!("{} {}",
+        ClientInfo::remote_ip(&request).unwrap_or_default(), ClientInfo::is_secure(&request)))
+}
+fn peer(address: &str) -> SocketAddr {
+    address.parse().expect("socket address")
+}
+
+///
+/// 信頼するプロキシが転送したクライアントのアドレスとスキームを採用する
+///
+#[actix_web::test]
+async fn trusted_proxy_headers_are_applied() {
+    let app = test::init_service(App::new()
+        .wrap(HttpsRedirect::new(Some(HTTPS_PORT)))
+        .wrap(ForwardedHeaders::new(TrustedProxies::parse("10.0.0.0/8").expect("trusted proxies")))
+        .route("/client", web::get().to(client))).await;
+    let request = test::TestRequest::get().uri("/client")
+        .peer_addr(peer(PROXY))
+        .insert_header(("x-forwarded-for", "203.0.113.5, 10.0.0.3"))
+        .insert_header(("x-forwarded-proto", "https"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, "203.0.113.5 true");
+}
+
+///
+/// 信頼するプロキシ以外からの転送ヘッダーは採用せず、HTTPSにリダイレクトする
+///
+#[actix_web::test]
+async fn untrusted_headers_are_ignored_and_redirected() {
+    let app = test::init_service(App::new()
+        .wrap(HttpsRedirect::new(Some(HTTPS_PORT)))
+        .wrap(ForwardedHeaders::new(TrustedProxies::parse("10.0.0.0/8").expect("trusted proxies")))
+        .route("/client", web::route().to(client))).await;
+    let request = test::TestRequest::get().uri("/client?page=2")
+        .peer_addr(peer(CLIENT))
+        .insert_header((header::HOST, "example.com:8080"))
+        .insert_header(("x-forwarded-proto", "https"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.headers().get(header::LOCATION).and_then(|value| value.to_str().ok()),
+               Some("https://example.com:8443/client?page=2"));
+
+    // GET以外はメソッドを維持させる
+    let request = test::TestRequest::post().uri("/client")
+        .peer_addr(peer(CLIENT))
+        .insert_header((header::HOST, "example.com"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+}
+
+///
+/// リダイレクトしない構成では接続元のアドレスをそのまま利用する
+///
+#[actix_web::test]
+async fn plain_mode_uses_peer_address() {
+    let app = test::init_service(App::new()
+        .wrap(HttpsRedirect::new(None))
+        .wrap(ForwardedHeaders::new(TrustedProxies::default()))
+        .route("/client", web::get().to(client))).await;
+    let request = test::TestRequest::get().uri("/client")
+        .peer_addr(peer(CLIENT))
+        .insert_header(("x-forwarded-for", "203.0.113.5"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, "198.51.100.7 false");
+}
\ No newline at end of file
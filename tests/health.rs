@@ -0,0 +1,64 @@
+//!
+//! ヘルスチェックの結合テスト
+//!
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test;
+use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, MockExecResult};
+
+// SELECT 1に応答するデータベース
+fn available_database() -> DatabaseConnection {
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results(vec![MockExecResult { last_insert_id: 0 , rows_affected: 1 }])
+        .into_connection()
+}
+
+///
+/// Livenessは依存サービスに関わらず応答する
+///
+#[actix_web::test]
+async fn live_responds_without_dependencies() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+
+    let request = test::TestRequest::get().uri("/health/live").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+///
+/// データベースに接続できる場合はUPを返す
+/// インメモリのセッションストア、Redisを利用しない構成では対象に含めない
+///
+#[actix_web::test]
+async fn ready_reports_up_when_database_responds() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(available_database(), &jwt)).await;
+
+    let request = test::TestRequest::get().uri("/health/ready").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: serde_json::Value = test::read_body_json(response).await;
+    assert_eq!(report["status"], "UP");
+    assert_eq!(report["database"]["status"], "UP");
+    assert!(report.get("redis").is_none());
+    assert!(report.get("session").is_none());
+}
+
+///
+/// データベースが応答しない場合は503を返し、エラーの詳細は出力しない
+///
+#[actix_web::test]
+async fn ready_reports_down_without_error_details() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+
+    let request = test::TestRequest::get().uri("/health/ready").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let report: serde_json::Value = test::read_body_json(response).await;
+    assert_eq!(report["status"], "DOWN");
+    assert_eq!(report["database"]["status"], "DOWN");
+    assert_eq!(report["database"].as_object().map(|status| status.len()), Some(2));
+}
\ No newline at end of file
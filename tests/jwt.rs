@@ -0,0 +1,54 @@
+//!
+//! 環境変数からのJWT設定の結合テスト
+//! 環境変数を変更するため、他のテストと別のバイナリで1つのテストとして実行する
+//!
+use std::path::PathBuf;
+use rcgen::{KeyPair, PKCS_ECDSA_P256_SHA256};
+use web_sample::config::AppEnvironment;
+use web_sample::jwt::WebJwt;
+
+// ES256の鍵ペアを生成し、秘密鍵と公開鍵のパスを返す
+fn write_es256_key_pair() -> (PathBuf, PathBuf) {
+    let key_pair = KeyPair::generate(&PKCS_ECDSA_P256_SHA256).expect("key pair");
+    let id = uuid::Uuid::new_v4();
+    let private_key = std::env::temp_dir().join(format!("web_sample_{}_private.pem", id));
+    let public_key = std::env::temp_dir().join(format!("web_sample_{}_public.pem", id));
+    std::fs::write(&private_key, key_pair.serialize_pem()).expect("write private key");
+    std::fs::write(&public_key, key_pair.public_key_pem()).expect("write public key");
+    (private_key, public_key)
+}
+
+///
+/// 署名鍵のkidに署名鍵と対応しない検証鍵を設定した場合は起動しない
+///
+#[test]
+fn verification_key_must_match_signing_key() {
+    std::env::set_var("JWT_ALGORITHM", "HS256");
+    std::env::set_var("JWT_KEY_ID", "current");
+    std::env::set_var("JWT_SIGNING_KEY", "signing-secret");
+
+    // 署名鍵と異なる共通鍵
+    std::env::set_var("JWT_VERIFICATION_KEYS", "current=other-secret;previous=previous-secret");
+    assert!(WebJwt::from_env(AppEnvironment::Production).is_err());
+
+    // 署名鍵と同じ共通鍵、または署名鍵のkidを含まない場合は起動できる
+    std::env::set_var("JWT_VERIFICATION_KEYS", "current=signing-secret;previous=previous-secret");
+    assert!(WebJwt::from_env(AppEnvironment::Production).is_ok());
+    std::env::set_var("JWT_VERIFICATION_KEYS", "previous=previous-secret");
+    assert!(WebJwt::from_env(AppEnvironment::Production).is_ok());
+
+    // 署名鍵を生成する開発環境で署名鍵のkidの検証鍵を設定した場合
+    std::env::remove_var("JWT_SIGNING_KEY");
+    std::env::set_var("JWT_VERIFICATION_KEYS", "current=signing-secret");
+    assert!(WebJwt::from_env(AppEnvironment::Development).is_err());
+
+    // ES256の秘密鍵と異なる鍵ペアの公開鍵を署名鍵のkidに設定した場合
+    let (signing_key, public_key) = write_es256_key_pair();
+    let (_, other_public_key) = write_es256_key_pair();
+    std::env::set_var("JWT_ALGORITHM", "ES256");
+    std::env::set_var("JWT_SIGNING_KEY", &signing_key);
+    std::env::set_var("JWT_VERIFICATION_KEYS", format!("current={}", other_public_key.display()));
+    assert!(WebJwt::from_env(AppEnvironment::Production).is_err());
+    std::env::set_var("JWT_VERIFICATION_KEYS", format!("current={};previous={}", public_key.display(), other_public_key.display()));
+    assert!(WebJwt::from_env(AppEnvironment::Production).is_ok());
+}
\ No newline at end of file
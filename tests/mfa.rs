@@ -0,0 +1,40 @@
+//!
+//! 二要素認証のリカバリーコードの結合テスト
+//!
+use web_sample::account::mfa::MfaService;
+use web_sample::account::store::{AccountStore, MemoryAccountStore};
+use web_sample::account::totp::Totp;
+
+// テスト用のアカウントId
+const ACCOUNT_ID: &str = "mfa-account";
+
+///
+/// 同じ設定を読み取った並行リクエストでは、同じリカバリーコードを一度しか受け付けない
+///
+#[actix_web::test]
+async fn recovery_code_is_accepted_once_by_concurrent_requests() {
+    let store = MemoryAccountStore::new();
+    let codes = MfaService::enable(&store, ACCOUNT_ID, &Totp::generate_secret(), 0).await.expect("enable");
+    // 二つのリクエストが更新前の設定を読み取った状態
+    let setting = store.find_mfa(ACCOUNT_ID).await.expect("find").expect("setting");
+
+    assert!(MfaService::verify(&store, setting.clone(), &codes[0]).await.expect("first"));
+    assert!(!MfaService::verify(&store, setting, &codes[0]).await.expect("second"));
+    let setting = store.find_mfa(ACCOUNT_ID).await.expect("find").expect("setting");
+    assert_eq!(MfaService::remaining_recovery_codes(&setting), codes.len() - 1);
+}
+
+///
+/// 使用済みのリカバリーコードは再利用できず、他のコードは利用できる
+///
+#[actix_web::test]
+async fn used_recovery_code_is_rejected() {
+    let store = MemoryAccountStore::new();
+    let codes = MfaService::enable(&store, ACCOUNT_ID, &Totp::generate_secret(), 0).await.expect("enable");
+
+    let setting = store.find_mfa(ACCOUNT_ID).await.expect("find").expect("setting");
+    assert!(MfaService::verify(&store, setting, &codes[0]).await.expect("first"));
+    let setting = store.find_mfa(ACCOUNT_ID).await.expect("find").expect("setting");
+    assert!(!MfaService::verify(&store, setting.clone(), &codes[0]).await.expect("reuse"));
+    assert!(MfaService::verify(&store, setting, &codes[1]).await.expect("other code"));
+}
\ No newline at end of file
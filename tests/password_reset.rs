@@ -0,0 +1,113 @@
+//!
+//! パスワード再設定の結合テスト
+//! 送信したメールは記録し、記載されたリンクで再設定する
+//!
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use actix_web::http::StatusCode;
+use actix_web::{rt, test, web};
+use async_trait::async_trait;
+use web_sample::account::entity as account;
+use web_sample::account::reset::PasswordResetConfig;
+use web_sample::account::store::MemoryAccountStore;
+use web_sample::mail::{Mail, Mailer};
+
+// テスト用アカウントのメールアドレス
+const EMAIL: &str = "user01@example.com";
+// 再設定後のパスワード
+const NEW_PASSWORD: &str = "newpassword02";
+
+// 送信したメールを記録する
+#[derive(Default)]
+struct RecordingMailer(Mutex<Vec<Mail>>);
+impl RecordingMailer {
+    fn sent(&self) -> Vec<Mail> {
+        self.0.lock().expect("sent mails").clone()
+    }
+    // 送信処理は応答と非同期に実行されるため、送信されるまで待つ
+    async fn wait_for_mail(&self) -> Option<Mail> {
+        for _ in 0..50 {
+            if let Some(mail) = self.sent().pop() {
+                return Some(mail);
+            }
+            rt::time::sleep(Duration::from_millis(20)).await;
+        }
+        None
+    }
+}
+#[async_trait]
+impl Mailer for RecordingMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), String> {
+        self.0.lock().expect("sent mails").push(mail.clone());
+        Ok(())
+    }
+}
+
+// メールアドレスを登録したテスト用アカウント
+async fn accounts() -> MemoryAccountStore {
+    let model = common::account().await;
+    MemoryAccountStore::new().account(account::Model { email: Some(EMAIL.to_string()) , ..model })
+}
+// メールに記載された再設定用のリンクのパス
+fn reset_path(mail: &Mail) -> String {
+    let start = mail.body.find(PasswordResetConfig::RESET_PATH).expect("reset link");
+    mail.body[start..].split_whitespace().next().expect("reset path").to_string()
+}
+
+///
+/// 登録済みのメールアドレスに送信されたリンクでパスワードを再設定し、リンクは一度だけ利用できる
+///
+#[actix_web::test]
+async fn reset_link_sets_new_password_once() {
+    let jwt = common::jwt();
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(common::app_with_accounts(common::empty_database(), accounts().await, &jwt)
+        .app_data(web::Data::from(mailer.clone() as Arc<dyn Mailer>))
+        .app_data(web::Data::new(PasswordResetConfig::from_env().expect("reset config")))).await;
+
+    let request = test::TestRequest::post().uri("/web_sample/password/forgot")
+        .set_form(&[("email", EMAIL)])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(common::body_text(response).await.contains("リンクを送信しました"));
+    let mail = mailer.wait_for_mail().await.expect("reset mail");
+    assert_eq!(mail.to, EMAIL);
+    let path = reset_path(&mail);
+
+    let request = test::TestRequest::post().uri(&path)
+        .set_form(&[("password", NEW_PASSWORD), ("password_confirm", NEW_PASSWORD)])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert!(common::body_text(response).await.contains("パスワードを再設定しました"));
+
+    let response = common::login(&app, common::USER_NAME, NEW_PASSWORD).await;
+    assert_eq!(common::location(&response), Some("/web_sample/menu"));
+
+    // 使用済みのリンク
+    let request = test::TestRequest::get().uri(&path).to_request();
+    let response = test::call_service(&app, request).await;
+    assert!(common::body_text(response).await.contains("リンクが無効"));
+}
+
+///
+/// 登録されていないメールアドレスにも同じ応答を返し、メールは送信しない
+///
+#[actix_web::test]
+async fn unknown_email_gets_same_response_without_mail() {
+    let jwt = common::jwt();
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(common::app_with_accounts(common::empty_database(), accounts().await, &jwt)
+        .app_data(web::Data::from(mailer.clone() as Arc<dyn Mailer>))
+        .app_data(web::Data::new(PasswordResetConfig::from_env().expect("reset config")))).await;
+
+    let request = test::TestRequest::post().uri("/web_sample/password/forgot")
+        .set_form(&[("email", "unknown@example.com")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(common::body_text(response).await.contains("リンクを送信しました"));
+    assert!(mailer.wait_for_mail().await.is_none());
+}
\ No newline at end of file
@@ -0,0 +1,177 @@
+//!
+//! 商品検索、商品登録の結合テスト
+//!
+mod common;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::test;
+
+///
+/// キーワードが未入力の場合は検索せず、検索画面を再表示する
+/// (MockDatabaseは問い合わせ結果を持たないため、検索するとエラー画面にリダイレクトされる)
+///
+#[actix_web::test]
+async fn search_with_empty_keyword_renders_search_page() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let cookie = common::login_cookie(&jwt, &common::account().await);
+
+    let request = test::TestRequest::post().uri("/web_sample/search/product")
+        .cookie(cookie)
+        .set_form(&[("keyword", "")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = common::body_text(response).await;
+    assert!(body.contains("name=\"keyword\""));
+}
+
+///
+/// 商品入力画面を経由せずに登録すると入力画面にリダイレクトされる
+///
+#[actix_web::test]
+async fn register_without_categories_redirects_to_enter() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let cookie = common::login_cookie(&jwt, &common::account().await);
+
+    let request = test::TestRequest::post().uri("/web_sample/register/product")
+        .cookie(cookie)
+        .set_form(&[("name", "水性ボールペン(黒)"), ("price", "120"), ("category_id", "1")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/register/product"));
+}
+
+///
+/// 入力値が不正な場合は登録せず、セッションの商品カテゴリで入力画面を再表示する
+///
+#[actix_web::test]
+async fn register_with_invalid_form_renders_enter_with_categories() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let cookie = common::login_cookie(&jwt, &common::account().await);
+    let session = common::seed_session(&app, common::SEED_CATEGORIES_PATH, common::categories()).await;
+
+    let request = test::TestRequest::post().uri("/web_sample/register/product")
+        .cookie(cookie)
+        .cookie(session)
+        .set_form(&[("name", ""), ("price", "120"), ("category_id", "1")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = common::body_text(response).await;
+    assert!(body.contains("invalid-feedback"));
+    assert!(body.contains("文房具"));
+    assert!(body.contains("雑貨"));
+}
+
+///
+/// SeaORMのサービスプロバイダで商品を登録し、完了画面に登録した商品を表示する
+///
+#[actix_web::test]
+async fn register_through_database_provider_renders_finish() {
+    let jwt = common::jwt();
+    let db = common::register_database(10, "USBハブ", 1500, (2, "雑貨"));
+    let app = test::init_service(common::app(db, &jwt)).await;
+    let cookie = common::login_cookie(&jwt, &common::account().await);
+    let session = common::seed_session(&app, common::SEED_CATEGORIES_PATH, common::categories()).await;
+
+    let request = test::TestRequest::post().uri("/web_sample/register/product")
+        .cookie(cookie)
+        .cookie(session.clone())
+        .set_form(&[("name", "USBハブ"), ("price", "1500"), ("category_id", "2")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/register/product/finish"));
+    let session = common::response_cookie(&response, common::SESSION_COOKIE).unwrap_or(session);
+
+    let request = test::TestRequest::get().uri("/web_sample/register/product/finish")
+        .cookie(session)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = common::body_text(response).await;
+    assert!(body.contains("USBハブ"));
+    assert!(body.contains("1500"));
+}
+
+///
+/// 登録結果がセッションにない場合、完了画面は入力画面にリダイレクトされる
+///
+#[actix_web::test]
+async fn finish_without_new_product_redirects_to_enter() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+
+    let request = test::TestRequest::get().uri("/web_sample/register/product/finish").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/register/product"));
+}
+
+///
+/// 完了画面は登録結果を一度だけ表示し、再表示は入力画面にリダイレクトされる
+///
+#[actix_web::test]
+async fn finish_renders_new_product_only_once() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let session = common::seed_session(&app, common::SEED_NEW_PRODUCT_PATH, common::new_product()).await;
+
+    let request = test::TestRequest::get().uri("/web_sample/register/product/finish")
+        .cookie(session.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    // 登録結果を削除したセッションのCookieで再度要求する
+    let session = common::response_cookie(&response, common::SESSION_COOKIE).unwrap_or(session);
+    let body = common::body_text(response).await;
+    assert!(body.contains("水性ボールペン(黒)"));
+    assert!(body.contains("文房具"));
+
+    let request = test::TestRequest::get().uri("/web_sample/register/product/finish")
+        .cookie(session)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/register/product"));
+}
+
+///
+/// 登録完了のフラッシュメッセージは次に表示した画面に一度だけ出力され、
+/// 出力後は完了画面も入力画面にリダイレクトされる
+///
+#[actix_web::test]
+async fn completed_flash_is_rendered_once_on_next_page() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let login = common::login_cookie(&jwt, &common::account().await);
+    let session = common::seed_session(&app, common::SEED_NEW_PRODUCT_PATH, common::new_product()).await;
+
+    let request = test::TestRequest::get().uri("/web_sample/menu")
+        .insert_header((header::ACCEPT_LANGUAGE, "ja"))
+        .cookie(login.clone())
+        .cookie(session.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let session = common::response_cookie(&response, common::SESSION_COOKIE).unwrap_or(session);
+    assert!(common::body_text(response).await.contains("商品を登録しました。"));
+
+    let request = test::TestRequest::get().uri("/web_sample/menu")
+        .insert_header((header::ACCEPT_LANGUAGE, "ja"))
+        .cookie(login)
+        .cookie(session.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert!(!common::body_text(response).await.contains("商品を登録しました。"));
+
+    let request = test::TestRequest::get().uri("/web_sample/register/product/finish")
+        .cookie(session)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(common::location(&response), Some("/web_sample/register/product"));
+}
\ No newline at end of file
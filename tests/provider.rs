@@ -0,0 +1,119 @@
+//!
+//! インメモリのサービスプロバイダでハンドラを実行する結合テスト
+//!
+mod common;
+
+use std::sync::Arc;
+use actix_web::http::StatusCode;
+use actix_web::test;
+use app_commons::presentation::forms::LoginForm;
+use app_commons::presentation::jwt::JWT_COOKIE_KEY;
+use web_sample::account::store::MemoryAccountStore;
+use web_sample::provider::{MemoryServiceProvider, ProviderError, ServiceProvider};
+
+// インメモリのサービスプロバイダ
+fn provider() -> Arc<dyn ServiceProvider> {
+    Arc::new(MemoryServiceProvider::sample())
+}
+
+///
+/// データベースを利用せず、インメモリのアカウントで認証する
+///
+#[actix_web::test]
+async fn login_through_memory_accounts_issues_token() {
+    let jwt = common::jwt();
+    let db = Arc::new(common::empty_database());
+    let accounts = MemoryAccountStore::new().account(common::account().await);
+    let app = test::init_service(common::app_with_provider(db, provider(), accounts, &jwt)).await;
+
+    let response = common::login(&app, common::USER_NAME, common::PASSWORD).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/menu"));
+    assert!(common::response_cookie(&response, JWT_COOKIE_KEY).is_some());
+}
+
+///
+/// 登録されたアカウントのユーザー名とパスワードで認証する
+///
+#[actix_web::test]
+async fn memory_provider_authenticates_registered_account() {
+    let account = common::account().await;
+    let accounts = Arc::new(MemoryAccountStore::new().account(account.clone()));
+    let provider = MemoryServiceProvider::sample().accounts(accounts);
+
+    let form = LoginForm { name: common::USER_NAME.to_string() , password: common::PASSWORD.to_string() };
+    let user = provider.authenticate(&form).await.expect("authenticated user");
+    assert_eq!(user.user_id, account.id);
+    assert_eq!(user.user_name, common::USER_NAME);
+
+    let form = LoginForm { name: common::USER_NAME.to_string() , password: String::from("wrong-password") };
+    assert!(matches!(provider.authenticate(&form).await, Err(ProviderError::AuthenticateError(_))));
+    let form = LoginForm { name: String::from("unknown") , password: common::PASSWORD.to_string() };
+    assert!(matches!(provider.authenticate(&form).await, Err(ProviderError::AuthenticateError(_))));
+}
+
+///
+/// キーワードを含む商品だけを表示する
+///
+#[actix_web::test]
+async fn search_renders_matching_products() {
+    let jwt = common::jwt();
+    let db = Arc::new(common::empty_database());
+    let app = test::init_service(common::app_with_provider(db, provider(), MemoryAccountStore::new(), &jwt)).await;
+    let cookie = common::login_cookie(&jwt, &common::account().await);
+
+    let request = test::TestRequest::post().uri("/web_sample/search/product")
+        .cookie(cookie)
+        .set_form(&[("keyword", "ボールペン")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = common::body_text(response).await;
+    assert!(body.contains("水性ボールペン(黒)"));
+    assert!(body.contains("油性ボールペン(黒)"));
+    assert!(!body.contains("無線マウス"));
+}
+
+///
+/// 入力画面、登録、完了画面の順に遷移し、登録した商品を検索できる
+///
+#[actix_web::test]
+async fn register_flow_stores_product() {
+    let jwt = common::jwt();
+    let db = Arc::new(common::empty_database());
+    let app = test::init_service(common::app_with_provider(db, provider(), MemoryAccountStore::new(), &jwt)).await;
+    let cookie = common::login_cookie(&jwt, &common::account().await);
+
+    // 入力画面でサービスプロバイダの商品カテゴリをセッションに格納する
+    let request = test::TestRequest::get().uri("/web_sample/register/product")
+        .cookie(cookie.clone())
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let session = common::response_cookie(&response, common::SESSION_COOKIE).expect("session cookie");
+    assert!(common::body_text(response).await.contains("パソコン周辺機器"));
+
+    let request = test::TestRequest::post().uri("/web_sample/register/product")
+        .cookie(cookie.clone())
+        .cookie(session.clone())
+        .set_form(&[("name", "USBハブ"), ("price", "1500"), ("category_id", "3")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(common::location(&response), Some("/web_sample/register/product/finish"));
+    let session = common::response_cookie(&response, common::SESSION_COOKIE).unwrap_or(session);
+
+    let request = test::TestRequest::get().uri("/web_sample/register/product/finish")
+        .cookie(session)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(common::body_text(response).await.contains("USBハブ"));
+
+    let request = test::TestRequest::post().uri("/web_sample/search/product")
+        .cookie(cookie)
+        .set_form(&[("keyword", "USB")])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert!(common::body_text(response).await.contains("USBハブ"));
+}
\ No newline at end of file
@@ -0,0 +1,58 @@
+//!
+//! レート制限の結合テスト
+//!
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use actix_web::http::{header, StatusCode};
+use actix_web::test;
+use web_sample::middleware::rate_limit::{MemoryRateLimitStore, RateLimitRule, RateLimiter};
+
+const REPORT_PATH: &str = "/csp-report";
+
+// 送信元を指定してCSP違反レポートのリクエストを生成する
+fn report_request(peer: &str) -> actix_http::Request {
+    test::TestRequest::post().uri(REPORT_PATH)
+        .peer_addr(peer.parse::<SocketAddr>().expect("peer address"))
+        .set_payload("{}")
+        .to_request()
+}
+
+///
+/// 容量を超えたリクエストはRetry-After付きの429となり、他の送信元は制限されない
+///
+#[actix_web::test]
+async fn burst_past_capacity_returns_too_many_requests() {
+    let jwt = common::jwt();
+    let rules = RateLimitRule::parse_all("POST /csp-report=2/60").expect("rules");
+    let app = test::init_service(common::app(common::empty_database(), &jwt)
+        .wrap(RateLimiter::new(Arc::new(MemoryRateLimitStore::new()), rules))).await;
+
+    for _ in 0..2 {
+        let response = test::call_service(&app, report_request("192.0.2.1:40000")).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+    let response = test::call_service(&app, report_request("192.0.2.1:40000")).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after = response.headers().get(header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .expect("retry-after header");
+    assert!(retry_after > 0 && retry_after <= 60);
+
+    let response = test::call_service(&app, report_request("192.0.2.2:40000")).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+///
+/// 既定のルールは解析でき、容量や秒数が0のルールと書式の誤りは拒否する
+///
+#[test]
+fn parse_all_rejects_invalid_rules() {
+    assert_eq!(RateLimitRule::parse_all(RateLimitRule::DEFAULT_RULES).expect("default rules").len(), 6);
+    assert!(RateLimitRule::parse_all("").expect("empty rules").is_empty());
+    for invalid in ["POST /x=0/60", "POST /x=5/0", "POST /x", "/x=5/60", "POST /x=5", "POST /x=a/60", "BAD\tMETHOD /x=5/60"] {
+        assert!(RateLimitRule::parse_all(invalid).is_err(), "{} must be rejected", invalid);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,74 @@
+//!
+//! セキュリティヘッダーとCSP違反レポートの結合テスト
+//!
+mod common;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::test;
+use web_sample::config::AppEnvironment;
+use web_sample::handler::csp_report::CspReportHandler;
+use web_sample::middleware::security_headers::{SecurityHeaders, SecurityPolicy};
+
+// 実行環境の既定値のセキュリティヘッダーの設定
+fn policy(environment: AppEnvironment) -> SecurityPolicy {
+    SecurityPolicy::from_env(environment).expect("security policy")
+}
+
+///
+/// 本番環境ではCSPを強制し、HSTSを送出する
+/// CSPのnonceは画面のscript要素に出力したnonceと一致する
+///
+#[actix_web::test]
+async fn production_policy_enforces_csp_with_page_nonce() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)
+        .wrap(SecurityHeaders::new(policy(AppEnvironment::Production)))).await;
+
+    let request = test::TestRequest::get().uri("/web_sample/login").to_request();
+    let response = test::call_service(&app, request).await;
+    let headers = response.headers().clone();
+    let csp = headers.get(header::CONTENT_SECURITY_POLICY).and_then(|value| value.to_str().ok()).expect("csp header");
+    assert!(headers.get(header::CONTENT_SECURITY_POLICY_REPORT_ONLY).is_none());
+    assert!(csp.contains(&format!("report-uri {}", SecurityPolicy::REPORT_PATH)));
+    assert!(headers.get(header::STRICT_TRANSPORT_SECURITY).is_some());
+    assert_eq!(headers.get(header::X_FRAME_OPTIONS).map(|value| value.as_bytes()), Some(&b"DENY"[..]));
+    assert_eq!(headers.get(header::X_CONTENT_TYPE_OPTIONS).map(|value| value.as_bytes()), Some(&b"nosniff"[..]));
+
+    let nonce = csp.split("'nonce-").nth(1).and_then(|rest| rest.split('\'').next()).expect("csp nonce");
+    assert!(common::body_text(response).await.contains(&format!("nonce=\"{}\"", nonce)));
+}
+
+///
+/// 開発環境ではCSPを報告のみとし、HSTSを送出しない
+///
+#[actix_web::test]
+async fn development_policy_reports_csp_only() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)
+        .wrap(SecurityHeaders::new(policy(AppEnvironment::Development)))).await;
+
+    let request = test::TestRequest::get().uri("/web_sample/login").to_request();
+    let response = test::call_service(&app, request).await;
+    assert!(response.headers().get(header::CONTENT_SECURITY_POLICY_REPORT_ONLY).is_some());
+    assert!(response.headers().get(header::CONTENT_SECURITY_POLICY).is_none());
+    assert!(response.headers().get(header::STRICT_TRANSPORT_SECURITY).is_none());
+}
+
+///
+/// CSP違反レポートは認証なしで受け付け、制御文字をエスケープしてログに出力する
+///
+#[actix_web::test]
+async fn csp_report_is_accepted_without_login() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+
+    let request = test::TestRequest::post().uri(SecurityPolicy::REPORT_PATH)
+        .insert_header((header::CONTENT_TYPE, "application/csp-report"))
+        .set_payload("{\"csp-report\":{}}\nforged log line")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    assert_eq!(CspReportHandler::escape_control("line\nforged\r\u{1b}[31m"), "line\\nforged\\r\\u{1b}[31m");
+    assert_eq!(CspReportHandler::escape_control("日本語 'quoted'"), "日本語 'quoted'");
+}
\ No newline at end of file
@@ -0,0 +1,63 @@
+//!
+//! 商品カテゴリと商品のエクスポート、インポートの結合テスト
+//!
+use std::path::PathBuf;
+use web_sample::provider::MemoryServiceProvider;
+use web_sample::seed::SeedData;
+
+// テスト毎に異なる一時ファイルのパス
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("web_sample_{}_{}", uuid::Uuid::new_v4(), name))
+}
+// サンプルデータを持つサービスプロバイダからエクスポートする
+async fn exported() -> SeedData {
+    let provider = MemoryServiceProvider::sample();
+    SeedData::export(&provider).await.expect("export")
+}
+
+///
+/// サービスプロバイダの商品カテゴリと商品をエクスポートする
+///
+#[actix_web::test]
+async fn export_collects_categories_and_products() {
+    let data = exported().await;
+    assert_eq!(data.categories, vec!["文房具", "雑貨", "パソコン周辺機器"]);
+    assert_eq!(data.products.len(), 6);
+    assert!(data.products.iter().any(|product| product.name == "無線マウス" && product.category == "パソコン周辺機器"));
+}
+
+///
+/// YAMLに書き込んだ内容を読み込める
+///
+#[actix_web::test]
+async fn yaml_round_trip() {
+    let data = exported().await;
+    let path = temp_file("catalog.yaml");
+    data.save(&path).expect("save yaml");
+    let loaded = SeedData::load(&path).expect("load yaml");
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.categories, data.categories);
+    assert_eq!(loaded.products.len(), data.products.len());
+}
+
+///
+/// CSVは商品があれば商品、なければ商品カテゴリを書き込み、列で判定して読み込む
+///
+#[actix_web::test]
+async fn csv_round_trip_products_and_categories() {
+    let mut data = exported().await;
+    let path = temp_file("products.csv");
+    data.save(&path).expect("save products");
+    let loaded = SeedData::load(&path).expect("load products");
+    std::fs::remove_file(&path).ok();
+    assert!(loaded.categories.is_empty());
+    assert_eq!(loaded.products.len(), data.products.len());
+
+    data.products.clear();
+    let path = temp_file("categories.csv");
+    data.save(&path).expect("save categories");
+    let loaded = SeedData::load(&path).expect("load categories");
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.categories, data.categories);
+    assert!(loaded.products.is_empty());
+}
\ No newline at end of file
@@ -0,0 +1,69 @@
+//!
+//! 静的ファイル配信の結合テスト
+//!
+mod common;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{test, web};
+use web_sample::assets::StaticAssets;
+
+// テンプレートが参照せず、バイナリに埋め込まれない静的ファイル
+const NOT_EMBEDDED: &str = "css/bootstrap.css";
+
+///
+/// 埋め込まれたファイルはハッシュ付きのURLで、圧縮済みの内容を長期キャッシュさせて返す
+///
+#[actix_web::test]
+async fn embedded_asset_is_served_compressed_and_immutable() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+    let url = StaticAssets::embedded().url("css/bootstrap.min.css");
+    assert_ne!(url, "/static/css/bootstrap.min.css");
+
+    let request = test::TestRequest::get().uri(&url)
+        .insert_header((header::ACCEPT_ENCODING, "gzip, br"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(header::CONTENT_ENCODING).map(|value| value.as_bytes()), Some(&b"br"[..]));
+    assert!(response.headers().get(header::CACHE_CONTROL).and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.contains("immutable")));
+}
+
+///
+/// 埋め込まれていないファイルは、ディスクから配信しない設定では404となる
+///
+#[actix_web::test]
+async fn asset_not_embedded_is_not_found_without_disk_fallback() {
+    let jwt = common::jwt();
+    let app = test::init_service(common::app(common::empty_database(), &jwt)).await;
+
+    let request = test::TestRequest::get().uri(&format!("/static/{}", NOT_EMBEDDED)).to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+///
+/// 開発環境では埋め込まれていないファイルをディスクから非圧縮で返し、毎回検証させる
+///
+#[actix_web::test]
+async fn asset_not_embedded_is_served_from_disk_in_development() {
+    let jwt = common::jwt();
+    let assets = StaticAssets::embedded().disk_fallback("views/static");
+    let app = test::init_service(common::app(common::empty_database(), &jwt)
+        .app_data(web::Data::new(assets.clone()))).await;
+
+    let request = test::TestRequest::get().uri(&format!("/static/{}", NOT_EMBEDDED))
+        .insert_header((header::ACCEPT_ENCODING, "gzip, br"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    assert_eq!(response.headers().get(header::CACHE_CONTROL).map(|value| value.as_bytes()), Some(&b"public, no-cache"[..]));
+    let body = test::read_body(response).await;
+    assert_eq!(&body[..], &std::fs::read(format!("views/static/{}", NOT_EMBEDDED)).expect("static file")[..]);
+
+    // ディレクトリ外を指すパスは読み込まない
+    assert!(assets.read_disk("../Cargo.toml").is_none());
+    assert!(assets.read_disk("/etc/hostname").is_none());
+}
\ No newline at end of file
@@ -0,0 +1,171 @@
+//!
+//! サーバー証明書の読み込みと再読み込みの結合テスト
+//! TLSのバックエンドのフィーチャーを有効にした場合のみ実行する
+//!
+#![cfg(any(feature = "openssl", feature = "rustls"))]
+use std::path::PathBuf;
+use x509_parser::pem::parse_x509_pem;
+use web_sample::demo::Demo;
+use web_sample::tls::{CipherPolicy, TlsCertificate, TlsSettings, TlsVersion};
+
+// テスト毎に異なる一時ファイルのパス
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("web_sample_{}_{}", uuid::Uuid::new_v4(), name))
+}
+// 一時ファイルの証明書と秘密鍵を利用する設定
+fn settings(min_version: TlsVersion, cipher_policy: CipherPolicy) -> TlsSettings {
+    TlsSettings {
+        certificate_file: temp_file("cert.pem") ,
+        private_key_file: temp_file("key.pem") ,
+        min_version ,
+        cipher_policy
+    }
+}
+// 自己署名証明書と秘密鍵を書き込み、証明書(DER)を返す
+fn write_certificate(settings: &TlsSettings) -> Vec<u8> {
+    let (certificate, private_key) = Demo::certificate().expect("self-signed certificate");
+    std::fs::write(&settings.certificate_file, &certificate).expect("write certificate");
+    std::fs::write(&settings.private_key_file, private_key).expect("write private key");
+    der(&certificate)
+}
+// PEMの証明書をDERに変換する
+fn der(certificate: &[u8]) -> Vec<u8> {
+    parse_x509_pem(certificate).expect("certificate pem").1.contents
+}
+
+///
+/// OpenSSLのバックエンドでハンドシェイクし、サーバーが提示した証明書を取得する
+///
+#[cfg(all(feature = "openssl", not(feature = "rustls")))]
+mod handshake {
+    use std::net::{TcpListener, TcpStream};
+    use openssl::ssl::{SslAcceptor, SslConnector, SslMethod, SslVerifyMode};
+    use web_sample::tls::TlsCertificate;
+
+    pub type Acceptor = SslAcceptor;
+
+    pub fn acceptor(certificate: &TlsCertificate) -> Acceptor {
+        certificate.server_config().expect("server config").build()
+    }
+    pub fn served_certificate(acceptor: &Acceptor, _expected: &[u8]) -> Vec<u8> {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let address = listener.local_addr().expect("local address");
+        let acceptor = acceptor.clone();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            acceptor.accept(stream).map(|_| ()).map_err(|error| error.to_string())
+        });
+        // 自己署名証明書のため検証せず、提示された証明書を比較する
+        let mut connector = SslConnector::builder(SslMethod::tls_client()).expect("connector");
+        connector.set_verify(SslVerifyMode::NONE);
+        let stream = connector.build().configure().expect("configure").verify_hostname(false)
+            .connect("localhost", TcpStream::connect(address).expect("connect")).expect("handshake");
+        let certificate = stream.ssl().peer_certificate().expect("peer certificate").to_der().expect("der");
+        server.join().expect("server thread").expect("server handshake");
+        certificate
+    }
+}
+
+///
+/// rustlsのバックエンドでハンドシェイクし、サーバーが提示した証明書を取得する
+///
+#[cfg(feature = "rustls")]
+mod handshake {
+    use std::sync::Arc;
+    use rustls::{ClientConfig, ClientConnection, Connection, RootCertStore, ServerConfig, ServerConnection};
+    use web_sample::tls::TlsCertificate;
+
+    pub type Acceptor = Arc<ServerConfig>;
+
+    pub fn acceptor(certificate: &TlsCertificate) -> Acceptor {
+        Arc::new(certificate.server_config().expect("server config"))
+    }
+    pub fn served_certificate(acceptor: &Acceptor, expected: &[u8]) -> Vec<u8> {
+        // 期待する自己署名証明書のみを信頼する
+        let mut roots = RootCertStore::empty();
+        roots.add(&rustls::Certificate(expected.to_vec())).expect("root certificate");
+        let config = ClientConfig::builder().with_safe_defaults().with_root_certificates(roots).with_no_client_auth();
+        let server_name = "localhost".try_into().expect("server name");
+        let mut client = Connection::from(ClientConnection::new(Arc::new(config), server_name).expect("client"));
+        let mut server = Connection::from(ServerConnection::new(acceptor.clone()).expect("server"));
+        while client.is_handshaking() || server.is_handshaking() {
+            transfer(&mut client, &mut server);
+            transfer(&mut server, &mut client);
+        }
+        client.peer_certificates().and_then(|chain| chain.first()).expect("peer certificate").0.clone()
+    }
+    // 送信待ちのTLSレコードを相手に渡す
+    fn transfer(from: &mut Connection, to: &mut Connection) {
+        let mut records = Vec::new();
+        while from.wants_write() {
+            from.write_tls(&mut records).expect("write tls");
+        }
+        let mut reader = records.as_slice();
+        while !reader.is_empty() {
+            to.read_tls(&mut reader).expect("read tls");
+            to.process_new_packets().expect("handshake");
+        }
+    }
+}
+
+///
+/// 更新した証明書と秘密鍵のファイルを再読み込みし、以降のハンドシェイクで提示する
+///
+#[test]
+fn reload_serves_renewed_certificate() {
+    let settings = settings(TlsVersion::Tls12, CipherPolicy::Intermediate);
+    let current = write_certificate(&settings);
+    let certificate = TlsCertificate::load(settings.clone()).expect("load certificate");
+    // 待ち受け中のTLS設定は再読み込み後も使い続ける
+    let acceptor = handshake::acceptor(&certificate);
+    assert_eq!(handshake::served_certificate(&acceptor, &current), current);
+
+    let renewed = write_certificate(&settings);
+    assert_ne!(renewed, current);
+    certificate.reload().expect("reload certificate");
+    assert_eq!(handshake::served_certificate(&acceptor, &renewed), renewed);
+}
+
+///
+/// 証明書と一致しない秘密鍵に更新された場合は再読み込みに失敗し、現在の証明書を提示し続ける
+///
+#[test]
+fn reload_rejects_mismatched_private_key() {
+    let settings = settings(TlsVersion::Tls12, CipherPolicy::Intermediate);
+    let current = write_certificate(&settings);
+    let certificate = TlsCertificate::load(settings.clone()).expect("load certificate");
+    let acceptor = handshake::acceptor(&certificate);
+
+    // 証明書のみ更新され、秘密鍵が古いままの状態
+    let (renewed, _) = Demo::certificate().expect("self-signed certificate");
+    std::fs::write(&settings.certificate_file, renewed).expect("write certificate");
+    assert!(certificate.reload().is_err());
+    assert_eq!(handshake::served_certificate(&acceptor, &current), current);
+}
+
+///
+/// 読み込めない秘密鍵に更新された場合は再読み込みに失敗し、現在の証明書を使い続ける
+///
+#[test]
+fn reload_failure_keeps_current_certificate() {
+    let settings = settings(TlsVersion::Tls12, CipherPolicy::Intermediate);
+    write_certificate(&settings);
+    let certificate = TlsCertificate::load(settings.clone()).expect("load certificate");
+
+    std::fs::write(&settings.private_key_file, "not a private key").expect("write private key");
+    assert!(certificate.reload().is_err());
+    assert!(certificate.server_config().is_ok());
+}
+
+///
+/// modernはTLS1.3のみを許可する
+///
+#[test]
+fn modern_policy_requires_tls13() {
+    assert!(settings(TlsVersion::Tls12, CipherPolicy::Modern).validate().is_err());
+    let modern = settings(TlsVersion::Tls13, CipherPolicy::Modern);
+    assert!(modern.validate().is_ok());
+    let (certificate, private_key) = Demo::certificate().expect("self-signed certificate");
+    let certificate = TlsCertificate::from_pem(modern, &certificate, &private_key).expect("certificate");
+    assert!(certificate.server_config().is_ok());
+}
\ No newline at end of file